@@ -0,0 +1,168 @@
+//! Database schema expectations for the site's `movies` table.
+//!
+//! The uploader does not yet talk to the database itself — there is no
+//! DATABASE_URL setting and no register_movie step in this build, and we
+//! deliberately don't carry a Postgres driver for a feature that doesn't
+//! exist. What batch users hit today is the *aftermath* of schema drift:
+//! registration done out-of-band fails with cryptic insert errors. This
+//! module holds the expected column set (mirroring `prisma/schema.prisma`)
+//! and the diff logic, exposed as a command that checks columns the
+//! frontend introspected over whatever connection it has. When a real DB
+//! layer lands, `check_db_schema` grows the connection half.
+
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+
+/// Expected columns of the `movies` table, as (name, postgres type). Kept
+/// in sync with the `Movie` model in `prisma/schema.prisma`.
+const EXPECTED_MOVIE_COLUMNS: &[(&str, &str)] = &[
+    ("id", "text"),
+    ("title", "text"),
+    ("year", "integer"),
+    ("duration", "integer"),
+    ("director", "text"),
+    ("genre", "text[]"),
+    ("rating", "double precision"),
+    ("averageRating", "double precision"),
+    ("r2_image_path", "text"),
+    ("r2_video_path", "text"),
+    ("r2_subtitles_path", "text"),
+    ("r2_hls_path", "text"),
+    ("hls_ready", "boolean"),
+    ("description", "text"),
+    ("streaming_url", "text"),
+    ("cloudflare_video_id", "text"),
+    ("created_at", "timestamp with time zone"),
+    ("updated_at", "timestamp with time zone"),
+];
+
+/// One column whose type differs from the expectation.
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnMismatch {
+    pub column: String,
+    pub expected: String,
+    pub found: String,
+}
+
+/// Outcome of comparing an introspected `movies` table against the
+/// expected schema. `fix_ddl` is advisory — nothing is ever auto-migrated.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaReport {
+    pub table: String,
+    pub ok: bool,
+    pub missing: Vec<String>,
+    pub mismatched: Vec<ColumnMismatch>,
+    /// Columns present in the table but not expected; harmless, listed for
+    /// awareness.
+    pub unexpected: Vec<String>,
+    /// `ALTER TABLE` statements that would close the gaps.
+    pub fix_ddl: Vec<String>,
+}
+
+/// Collapse type-name aliases so `varchar` vs `text` isn't flagged.
+fn normalize_type(ty: &str) -> String {
+    match ty.trim().to_ascii_lowercase().as_str() {
+        "character varying" | "varchar" => "text".into(),
+        "float8" => "double precision".into(),
+        "int" | "int4" => "integer".into(),
+        "bool" => "boolean".into(),
+        "timestamptz" => "timestamp with time zone".into(),
+        other => other.to_string(),
+    }
+}
+
+/// Diff introspected columns (name, type) against the expected set.
+fn diff_schema(actual: &[(String, String)]) -> SchemaReport {
+    let mut missing = Vec::new();
+    let mut mismatched = Vec::new();
+    let mut fix_ddl = Vec::new();
+    for (name, expected) in EXPECTED_MOVIE_COLUMNS {
+        match actual.iter().find(|(n, _)| n == name) {
+            None => {
+                missing.push(name.to_string());
+                fix_ddl.push(format!("ALTER TABLE movies ADD COLUMN \"{name}\" {expected};"));
+            }
+            Some((_, found)) if normalize_type(found) != normalize_type(expected) => {
+                mismatched.push(ColumnMismatch {
+                    column: name.to_string(),
+                    expected: expected.to_string(),
+                    found: found.clone(),
+                });
+                fix_ddl.push(format!(
+                    "ALTER TABLE movies ALTER COLUMN \"{name}\" TYPE {expected};"
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    let unexpected = actual
+        .iter()
+        .filter(|(n, _)| !EXPECTED_MOVIE_COLUMNS.iter().any(|(e, _)| e == n))
+        .map(|(n, _)| n.clone())
+        .collect();
+    SchemaReport {
+        table: "movies".into(),
+        ok: missing.is_empty() && mismatched.is_empty(),
+        missing,
+        mismatched,
+        unexpected,
+        fix_ddl,
+    }
+}
+
+/// Check an introspected `movies` column list against the expected schema,
+/// returning what's missing or mismatched plus the DDL that would fix it.
+/// Nothing is migrated; the report is for catching drift before a batch
+/// fails at registration.
+#[tauri::command]
+pub fn check_db_schema(columns: Vec<(String, String)>) -> Result<SchemaReport> {
+    if columns.is_empty() {
+        return Err(AppError::InvalidInput(
+            "no columns provided; introspect the movies table first".into(),
+        ));
+    }
+    Ok(diff_schema(&columns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_schema() -> Vec<(String, String)> {
+        EXPECTED_MOVIE_COLUMNS
+            .iter()
+            .map(|(n, t)| (n.to_string(), t.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn matching_schema_reports_ok() {
+        let report = diff_schema(&full_schema());
+        assert!(report.ok);
+        assert!(report.fix_ddl.is_empty());
+    }
+
+    #[test]
+    fn drift_is_reported_with_repair_ddl_but_never_applied() {
+        let mut actual = full_schema();
+        actual.retain(|(n, _)| n != "hls_ready");
+        let slot = actual.iter_mut().find(|(n, _)| n == "year").unwrap();
+        slot.1 = "text".into();
+        let report = diff_schema(&actual);
+        assert!(!report.ok);
+        assert_eq!(report.missing, vec!["hls_ready"]);
+        assert_eq!(report.mismatched.len(), 1);
+        assert!(report
+            .fix_ddl
+            .contains(&"ALTER TABLE movies ADD COLUMN \"hls_ready\" boolean;".to_string()));
+    }
+
+    #[test]
+    fn type_aliases_are_not_drift() {
+        let mut actual = full_schema();
+        let slot = actual.iter_mut().find(|(n, _)| n == "title").unwrap();
+        slot.1 = "varchar".into();
+        assert!(diff_schema(&actual).ok);
+    }
+}
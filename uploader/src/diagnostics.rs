@@ -0,0 +1,576 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::error::{AppError, Result};
+use crate::settings::SettingsStore;
+
+/// Exactly which build the user is running, for the About dialog and for
+/// making bug reports actionable.
+#[derive(Debug, Clone, Serialize)]
+pub struct AppInfo {
+    pub version: String,
+    pub git_commit: String,
+    pub build_timestamp: String,
+    pub tauri_version: String,
+    pub os: String,
+    pub arch: String,
+    pub debug_build: bool,
+}
+
+#[tauri::command]
+pub async fn app_info() -> AppInfo {
+    AppInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: env!("BUILD_GIT_COMMIT").to_string(),
+        build_timestamp: env!("BUILD_TIMESTAMP").to_string(),
+        tauri_version: tauri::VERSION.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        debug_build: cfg!(debug_assertions),
+    }
+}
+
+/// The release feed document `check_for_updates` polls.
+#[derive(Debug, Deserialize)]
+struct UpdateManifest {
+    version: String,
+    release_notes_url: Option<String>,
+    download_url: Option<String>,
+}
+
+/// What the update check found. `error` carries a note when the feed was
+/// unreachable — that's informational, not a failure.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateCheck {
+    pub update_available: bool,
+    pub current_version: String,
+    pub latest_version: Option<String>,
+    pub release_notes_url: Option<String>,
+    pub download_url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Parse "1.2.3" (optionally "v"-prefixed, pre-release suffix ignored) into
+/// comparable components.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()?;
+    let mut parts = core.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next().unwrap_or("0").parse().ok()?;
+    Some((major, minor, patch))
+}
+
+/// Compare the configured release feed against the running version. This
+/// only informs — it never downloads or installs anything. A feed that
+/// can't be reached or parsed yields `update_available: false` with an
+/// error note rather than a hard failure, so the UI can check on startup
+/// without breaking offline use.
+#[tauri::command]
+pub async fn check_for_updates(store: State<'_, SettingsStore>) -> Result<UpdateCheck> {
+    let settings = store.get();
+    let url = settings.update_manifest_url.ok_or_else(|| {
+        AppError::Settings("update_manifest_url is not configured".into())
+    })?;
+    let current_version = env!("CARGO_PKG_VERSION").to_string();
+
+    let manifest: std::result::Result<UpdateManifest, String> = async {
+        let resp = reqwest::get(&url).await.map_err(|e| e.to_string())?;
+        resp.error_for_status()
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())
+    }
+    .await;
+
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            return Ok(UpdateCheck {
+                update_available: false,
+                current_version,
+                latest_version: None,
+                release_notes_url: None,
+                download_url: None,
+                error: Some(format!("update check failed: {error}")),
+            });
+        }
+    };
+
+    let update_available = match (parse_version(&manifest.version), parse_version(&current_version))
+    {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    };
+    Ok(UpdateCheck {
+        update_available,
+        current_version,
+        latest_version: Some(manifest.version),
+        release_notes_url: manifest.release_notes_url,
+        download_url: manifest.download_url,
+        error: None,
+    })
+}
+
+/// Severity of one subsystem check.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One subsystem's readiness, individually timed so the dashboard can show
+/// which check is slow.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemCheck {
+    pub status: CheckStatus,
+    pub message: String,
+    pub elapsed_ms: u64,
+}
+
+/// Startup readiness summary shown before the user begins work.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub ffmpeg: SubsystemCheck,
+    pub gpu: SubsystemCheck,
+    pub r2: SubsystemCheck,
+    pub disk: SubsystemCheck,
+    /// True when nothing failed outright (warnings are allowed).
+    pub ok: bool,
+}
+
+/// Hard cap per sub-check so one hung probe doesn't stall the dashboard.
+const CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Free space below which the disk check turns into a warning — a single
+/// conversion can easily need this much scratch space.
+const DISK_WARN_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+async fn timed<F>(check: F) -> SubsystemCheck
+where
+    F: std::future::Future<Output = (CheckStatus, String)>,
+{
+    let started = std::time::Instant::now();
+    let (status, message) = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(outcome) => outcome,
+        Err(_) => (CheckStatus::Fail, "check timed out".into()),
+    };
+    SubsystemCheck {
+        status,
+        message,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+async fn check_ffmpeg() -> (CheckStatus, String) {
+    match tokio::process::Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .await
+    {
+        Ok(out) if out.status.success() => {
+            let first_line = String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .next()
+                .unwrap_or("ffmpeg available")
+                .to_string();
+            (CheckStatus::Pass, first_line)
+        }
+        Ok(out) => (CheckStatus::Fail, format!("ffmpeg exited with {}", out.status)),
+        Err(e) => (CheckStatus::Fail, format!("ffmpeg not found: {e}")),
+    }
+}
+
+async fn check_gpu() -> (CheckStatus, String) {
+    let capabilities = crate::gpu::detect().await;
+    if capabilities.nvenc_available {
+        (
+            CheckStatus::Pass,
+            format!(
+                "{} (driver {})",
+                capabilities.gpu_name.unwrap_or_else(|| "GPU".into()),
+                capabilities.driver_version.unwrap_or_else(|| "unknown".into())
+            ),
+        )
+    } else {
+        (CheckStatus::Warn, "no GPU encoder; conversions run on CPU".into())
+    }
+}
+
+async fn check_r2(settings: &crate::settings::Settings) -> (CheckStatus, String) {
+    if settings.r2_account_id.is_empty() || settings.r2_access_key_id.is_empty() {
+        return (CheckStatus::Warn, "R2 credentials are not configured".into());
+    }
+    if crate::r2::check_connectivity(settings).await {
+        (CheckStatus::Pass, "R2 endpoint reachable".into())
+    } else {
+        (CheckStatus::Fail, "cannot reach the R2 endpoint".into())
+    }
+}
+
+async fn check_disk(settings: &crate::settings::Settings) -> (CheckStatus, String) {
+    if let Err(e) = tokio::fs::create_dir_all(&settings.output_dir).await {
+        return (
+            CheckStatus::Fail,
+            format!("output dir {} unavailable: {e}", settings.output_dir.display()),
+        );
+    }
+    match fs2::available_space(&settings.output_dir) {
+        Ok(free) => {
+            let free_gib = free as f64 / (1024.0 * 1024.0 * 1024.0);
+            if free < DISK_WARN_BYTES {
+                (CheckStatus::Warn, format!("only {free_gib:.1} GiB free in output dir"))
+            } else {
+                (CheckStatus::Pass, format!("{free_gib:.1} GiB free in output dir"))
+            }
+        }
+        Err(e) => (CheckStatus::Fail, format!("cannot stat output dir: {e}")),
+    }
+}
+
+/// How much data a disk throughput test writes and reads back. Large enough
+/// to outlast drive write caches on typical SSDs, small enough to finish in
+/// a few seconds.
+const THROUGHPUT_TEST_BYTES: u64 = 256 * 1024 * 1024;
+const THROUGHPUT_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+
+/// Measured sequential throughput of one directory's filesystem.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskThroughput {
+    pub path: std::path::PathBuf,
+    pub bytes_tested: u64,
+    pub write_mb_per_sec: f64,
+    pub read_mb_per_sec: f64,
+}
+
+/// The blocking measurement itself. The test file is removed on every exit
+/// path, including errors mid-write.
+fn measure_throughput(test_file: &std::path::Path) -> std::io::Result<(f64, f64)> {
+    use std::io::{Read, Write};
+
+    let result = (|| {
+        let mut file = std::fs::File::create(test_file)?;
+        let chunk = vec![0u8; THROUGHPUT_CHUNK_BYTES];
+        let started = std::time::Instant::now();
+        let mut written = 0u64;
+        while written < THROUGHPUT_TEST_BYTES {
+            file.write_all(&chunk)?;
+            written += chunk.len() as u64;
+        }
+        // Without the sync the timer mostly measures the page cache.
+        file.sync_all()?;
+        let write_secs = started.elapsed().as_secs_f64();
+        drop(file);
+
+        let mut file = std::fs::File::open(test_file)?;
+        let mut buf = vec![0u8; THROUGHPUT_CHUNK_BYTES];
+        let started = std::time::Instant::now();
+        loop {
+            if file.read(&mut buf)? == 0 {
+                break;
+            }
+        }
+        let read_secs = started.elapsed().as_secs_f64();
+
+        let mb = written as f64 / 1_000_000.0;
+        Ok((mb / write_secs.max(f64::EPSILON), mb / read_secs.max(f64::EPSILON)))
+    })();
+    let _ = std::fs::remove_file(test_file);
+    result
+}
+
+/// Write a few hundred MB to a scratch file under `path`, read it back, and
+/// report sequential MB/s each way. The file is deleted afterwards even when
+/// the test fails. The read figure is optimistic on machines with plenty of
+/// RAM — the file just written is usually still in the page cache — so treat
+/// it as an upper bound; the write figure is what matters for conversion
+/// scratch space.
+#[tauri::command]
+pub async fn test_disk_throughput(path: std::path::PathBuf) -> Result<DiskThroughput> {
+    tokio::fs::create_dir_all(&path).await?;
+    let test_file = path.join(".cinemafred-throughput-test");
+    let (write_mb_per_sec, read_mb_per_sec) = tokio::task::spawn_blocking({
+        let test_file = test_file.clone();
+        move || measure_throughput(&test_file)
+    })
+    .await
+    .expect("throughput task panicked")?;
+    Ok(DiskThroughput {
+        path,
+        bytes_tested: THROUGHPUT_TEST_BYTES,
+        write_mb_per_sec,
+        read_mb_per_sec,
+    })
+}
+
+/// Seconds of source the pipeline benchmark converts and uploads. Long
+/// enough to smooth out ffmpeg startup overhead, short enough to stay
+/// interactive.
+const PIPELINE_SAMPLE_SECONDS: f64 = 30.0;
+
+/// End-to-end figures from [`benchmark_pipeline`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineBenchmark {
+    pub encoder: String,
+    /// The rendition the sample was encoded at (the top of the ladder).
+    pub rendition: String,
+    pub sample_seconds: f64,
+    pub convert_seconds: f64,
+    /// Encoded frames per wall second; None when the source didn't report
+    /// a frame rate.
+    pub convert_fps: Option<f64>,
+    pub uploaded_bytes: u64,
+    pub upload_seconds: f64,
+    pub upload_mbps: f64,
+    pub total_seconds: f64,
+    /// "convert" or "upload" — the phase that dominated wall time and is
+    /// therefore worth tuning first.
+    pub bottleneck: String,
+}
+
+/// Convert a short sample of `sample_input` with the real rendition
+/// arguments, upload the result to a throwaway prefix, and report each
+/// phase's throughput plus which one bounds the pipeline — one actionable
+/// number set for tuning parallelism and bitrate together. Local and
+/// remote artifacts are removed afterwards, including when a phase fails.
+#[tauri::command]
+pub async fn benchmark_pipeline(
+    app: tauri::AppHandle,
+    store: State<'_, SettingsStore>,
+    sample_input: std::path::PathBuf,
+) -> Result<PipelineBenchmark> {
+    let mut settings = store.get();
+    // The prefix is exclusively ours, so the pre-upload head checks would
+    // only skew the measurement.
+    settings.overwrite_existing = true;
+    let client = crate::r2::client(&settings)?;
+    let scratch =
+        std::env::temp_dir().join(format!("uploader-pipeline-bench-{}", std::process::id()));
+    let prefix = format!(
+        "benchmarks/pipeline-{}",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    );
+
+    let outcome = async {
+        let total_started = std::time::Instant::now();
+        let encode = crate::ffmpeg::encode_benchmark_sample(
+            &app,
+            &settings,
+            &sample_input,
+            &scratch,
+            PIPELINE_SAMPLE_SECONDS,
+        )
+        .await?;
+
+        let options = crate::r2::UploadOptions::default();
+        let mut uploaded_bytes = 0u64;
+        let upload_started = std::time::Instant::now();
+        let mut entries = tokio::fs::read_dir(&scratch).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            crate::r2::upload_file(
+                &app,
+                &client,
+                &settings,
+                &entry.path(),
+                &format!("{prefix}/{name}"),
+                &options,
+            )
+            .await?;
+            uploaded_bytes += entry.metadata().await?.len();
+        }
+        let upload_seconds = upload_started.elapsed().as_secs_f64().max(f64::EPSILON);
+
+        let bottleneck = if encode.elapsed_seconds >= upload_seconds {
+            "convert"
+        } else {
+            "upload"
+        };
+        Ok(PipelineBenchmark {
+            encoder: encode.encoder,
+            rendition: encode.rendition,
+            sample_seconds: encode.sample_seconds,
+            convert_seconds: encode.elapsed_seconds,
+            convert_fps: encode.frames.map(|f| f / encode.elapsed_seconds),
+            uploaded_bytes,
+            upload_seconds,
+            upload_mbps: uploaded_bytes as f64 * 8.0 / 1_000_000.0 / upload_seconds,
+            total_seconds: total_started.elapsed().as_secs_f64(),
+            bottleneck: bottleneck.into(),
+        })
+    }
+    .await;
+
+    // Cleanup runs regardless of the outcome. The keys mirror the scratch
+    // file names, so a partially completed upload is covered too; deleting
+    // a key that never landed is a no-op.
+    let mut keys = Vec::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(&scratch).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            keys.push(format!(
+                "{prefix}/{}",
+                entry.file_name().to_string_lossy()
+            ));
+        }
+    }
+    if !keys.is_empty() {
+        let _ = crate::r2::delete_batch(&client, &settings, keys).await;
+    }
+    let _ = tokio::fs::remove_dir_all(&scratch).await;
+    outcome
+}
+
+/// Everything that determines how the app will actually behave: the stored
+/// settings plus the runtime values derived from them. Secrets are redacted
+/// — this is meant to be pasted into bug reports.
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub settings: crate::settings::Settings,
+    /// Where `ffmpeg` resolves on PATH, if anywhere.
+    pub ffmpeg_path: Option<std::path::PathBuf>,
+    pub ffmpeg_version: Option<String>,
+    /// First encoder in the fallback chain that initializes; None when the
+    /// whole chain fails.
+    pub selected_encoder: Option<String>,
+    pub cpu_count: usize,
+    /// Conversion slots that can actually run at once: `max_concurrent_jobs`
+    /// clamped to the CPU count.
+    pub effective_parallelism: usize,
+    /// `output_dir` with symlinks and relative components resolved, when it
+    /// exists.
+    pub resolved_output_dir: Option<std::path::PathBuf>,
+    pub temp_dir: std::path::PathBuf,
+    pub gpu: crate::gpu::GpuCapabilities,
+    /// The S3 endpoint uploads will hit.
+    pub s3_endpoint: String,
+}
+
+/// First hit for an executable name across `path_var`'s entries, honoring
+/// Windows' `.exe` suffix. Split out from the command for testability.
+fn find_in_path(name: &str, path_var: &str) -> Option<std::path::PathBuf> {
+    std::env::split_paths(path_var).find_map(|dir| {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if cfg!(windows) {
+            let exe = dir.join(format!("{name}.exe"));
+            if exe.is_file() {
+                return Some(exe);
+            }
+        }
+        None
+    })
+}
+
+/// Snapshot the full effective configuration — stored settings plus derived
+/// runtime values (resolved ffmpeg, selected encoder, clamped parallelism,
+/// resolved dirs, GPU, S3 endpoint) — so a single paste explains how the
+/// app will behave on this machine.
+#[tauri::command]
+pub async fn dump_effective_config(
+    app: tauri::AppHandle,
+    store: State<'_, SettingsStore>,
+) -> Result<EffectiveConfig> {
+    let mut settings = store.get();
+    if !settings.r2_secret_access_key.is_empty() {
+        settings.r2_secret_access_key = "<redacted>".into();
+    }
+
+    let ffmpeg_path =
+        std::env::var("PATH").ok().and_then(|path| find_in_path("ffmpeg", &path));
+    let ffmpeg_version = match tokio::process::Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .await
+    {
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .next()
+            .map(String::from),
+        _ => None,
+    };
+    let selected_encoder = crate::ffmpeg::select_encoder(&app, &settings).await.ok();
+    let cpu_count = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let s3_endpoint = format!(
+        "https://{}.r2.cloudflarestorage.com",
+        settings.r2_account_id
+    );
+    Ok(EffectiveConfig {
+        ffmpeg_path,
+        ffmpeg_version,
+        selected_encoder,
+        cpu_count,
+        effective_parallelism: settings.max_concurrent_jobs.min(cpu_count),
+        resolved_output_dir: settings.output_dir.canonicalize().ok(),
+        temp_dir: std::env::temp_dir(),
+        gpu: crate::gpu::detect().await,
+        s3_endpoint,
+        settings,
+    })
+}
+
+/// Run every readiness check concurrently and report per-subsystem status,
+/// for the startup dashboard.
+#[tauri::command]
+pub async fn health_check(store: State<'_, SettingsStore>) -> Result<HealthReport> {
+    let settings = store.get();
+    let (ffmpeg, gpu, r2, disk) = tokio::join!(
+        timed(check_ffmpeg()),
+        timed(check_gpu()),
+        timed(check_r2(&settings)),
+        timed(check_disk(&settings)),
+    );
+    let ok = [&ffmpeg, &gpu, &r2, &disk]
+        .iter()
+        .all(|c| c.status != CheckStatus::Fail);
+    Ok(HealthReport {
+        ffmpeg,
+        gpu,
+        r2,
+        disk,
+        ok,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_orders_versions() {
+        assert_eq!(parse_version("1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("v0.10.0"), Some((0, 10, 0)));
+        assert_eq!(parse_version("2.0.0-rc.1"), Some((2, 0, 0)));
+        assert!(parse_version("0.10.0") > parse_version("0.9.9"));
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    #[test]
+    fn path_lookup_returns_the_first_matching_entry() {
+        let base = std::env::temp_dir().join(format!("uploader-path-test-{}", std::process::id()));
+        let first = base.join("first");
+        let second = base.join("second");
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(second.join("sometool"), b"").unwrap();
+
+        let path_var = std::env::join_paths([&first, &second])
+            .unwrap()
+            .into_string()
+            .unwrap();
+        assert_eq!(find_in_path("sometool", &path_var), Some(second.join("sometool")));
+        assert_eq!(find_in_path("missing", &path_var), None);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// Unified error type surfaced to the frontend by every command.
+///
+/// Serialized as `{ kind, message, ... }` so the UI can branch on `kind`
+/// (e.g. to offer an overwrite confirmation) while still having a
+/// displayable message.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("R2 error: {0}")]
+    R2(String),
+    #[error("object already exists: {key} ({size} bytes, last modified {last_modified})")]
+    ObjectExists {
+        key: String,
+        size: i64,
+        last_modified: String,
+    },
+    #[error("ffmpeg error: {0}")]
+    Ffmpeg(String),
+    /// The ffmpeg binary isn't on PATH at all.
+    #[error("ffmpeg not found: {0}")]
+    FfmpegNotFound(String),
+    /// ffmpeg exists but the OS refused to execute it.
+    #[error("ffmpeg not executable: {0}")]
+    FfmpegNotExecutable(String),
+    #[error("ffmpeg exited with {status}")]
+    FfmpegFailed {
+        status: String,
+        /// The exact argv that was run, for reproducing the failure.
+        argv: Vec<String>,
+        /// Last ~200 lines of stderr, ring-buffered to bound memory.
+        stderr_tail: Vec<String>,
+    },
+    #[error("timed out: {0}")]
+    Timeout(String),
+    #[error("ffprobe error: {0}")]
+    Ffprobe(String),
+    #[error("settings error: {0}")]
+    Settings(String),
+    #[error("job error: {0}")]
+    Job(String),
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+}
+
+impl AppError {
+    fn kind(&self) -> &'static str {
+        match self {
+            AppError::Io(_) => "io",
+            AppError::R2(_) => "r2",
+            AppError::ObjectExists { .. } => "object_exists",
+            AppError::Ffmpeg(_) => "ffmpeg",
+            AppError::FfmpegNotFound(_) => "ffmpeg_not_found",
+            AppError::FfmpegNotExecutable(_) => "ffmpeg_not_executable",
+            AppError::FfmpegFailed { .. } => "ffmpeg_failed",
+            AppError::Timeout(_) => "timeout",
+            AppError::Ffprobe(_) => "ffprobe",
+            AppError::Settings(_) => "settings",
+            AppError::Job(_) => "job",
+            AppError::InvalidInput(_) => "invalid_input",
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            AppError::ObjectExists {
+                key,
+                size,
+                last_modified,
+            } => {
+                let mut s = serializer.serialize_struct("AppError", 5)?;
+                s.serialize_field("kind", self.kind())?;
+                s.serialize_field("message", &self.to_string())?;
+                s.serialize_field("key", key)?;
+                s.serialize_field("size", size)?;
+                s.serialize_field("lastModified", last_modified)?;
+                s.end()
+            }
+            AppError::FfmpegFailed {
+                status: _,
+                argv,
+                stderr_tail,
+            } => {
+                let mut s = serializer.serialize_struct("AppError", 4)?;
+                s.serialize_field("kind", self.kind())?;
+                s.serialize_field("message", &self.to_string())?;
+                s.serialize_field("argv", argv)?;
+                s.serialize_field("stderrTail", stderr_tail)?;
+                s.end()
+            }
+            _ => {
+                let mut s = serializer.serialize_struct("AppError", 2)?;
+                s.serialize_field("kind", self.kind())?;
+                s.serialize_field("message", &self.to_string())?;
+                s.end()
+            }
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, AppError>;
@@ -0,0 +1,3525 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use crate::error::{AppError, Result};
+use crate::settings::{
+    AudioStrategy, HlsPlaylistType, HlsSegmentType, Settings, SettingsStore, TimestampFix,
+};
+
+/// Map a failed ffmpeg spawn to something actionable: a binary missing from
+/// PATH and one the OS won't execute are different fixes, and neither
+/// deserves a generic "ffmpeg error".
+pub(crate) fn spawn_error(e: std::io::Error) -> AppError {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::FfmpegNotFound(
+            "ffmpeg is not on PATH; install it or add its directory to PATH".into(),
+        ),
+        std::io::ErrorKind::PermissionDenied => AppError::FfmpegNotExecutable(
+            "ffmpeg was found but cannot be executed; check its file permissions".into(),
+        ),
+        _ => AppError::Ffmpeg(format!("failed to spawn ffmpeg: {e}")),
+    }
+}
+
+/// Check the paths a conversion needs before any ffmpeg runs, so a missing
+/// input or an unwritable output directory fails up front with a pointed
+/// error instead of an opaque ffmpeg one mid-encode.
+fn preflight_conversion(input: &Path, output_dir: &Path) -> Result<()> {
+    if !input.is_file() {
+        return Err(AppError::InvalidInput(format!(
+            "input {} does not exist or is not a file; check the path",
+            input.display()
+        )));
+    }
+    std::fs::create_dir_all(output_dir).map_err(|e| {
+        AppError::Settings(format!(
+            "output_dir {} cannot be created: {e}; point output_dir at a writable directory",
+            output_dir.display()
+        ))
+    })?;
+    let probe = output_dir.join(".write-test");
+    std::fs::write(&probe, b"").map_err(|e| {
+        AppError::Settings(format!(
+            "output_dir {} is not writable: {e}; point output_dir at a writable directory",
+            output_dir.display()
+        ))
+    })?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// What ffprobe tells us about a source file, trimmed to the fields the
+/// conversion pipeline actually uses.
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoMetadata {
+    pub width: u32,
+    pub height: u32,
+    pub duration_seconds: f64,
+    pub bit_rate: Option<u64>,
+    pub video_codec: String,
+    pub audio_codec: Option<String>,
+    pub audio_tracks: Vec<AudioTrack>,
+    /// Average frame rate, in frames per second.
+    pub average_frame_rate: Option<f64>,
+    /// Source has variable frame rate; conversion forces CFR to avoid
+    /// broken HLS timing and A/V desync.
+    pub variable_frame_rate: bool,
+    /// Pixel format of the video stream (e.g. "yuv420p", "yuv420p10le").
+    pub pix_fmt: Option<String>,
+    /// Display rotation carried in container metadata, normalized to
+    /// 0/90/180/270 degrees clockwise. None when the source carries no
+    /// rotation.
+    pub rotation: Option<u32>,
+}
+
+/// One audio stream of the source, with the metadata the HLS audio group
+/// needs. Missing language tags default to "und". Deserialize is for
+/// reading it back out of a package manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioTrack {
+    pub index: u32,
+    pub language: String,
+    pub title: Option<String>,
+    pub codec: String,
+    pub channels: u32,
+    pub default: bool,
+}
+
+/// Emitted on the `conversion-progress` channel while ffmpeg runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionProgress {
+    pub movie_id: String,
+    pub rendition: String,
+    pub seconds_done: f64,
+    pub total_seconds: f64,
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+    format: ProbeFormat,
+    streams: Vec<ProbeStream>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    index: Option<u32>,
+    codec_type: String,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    channels: Option<u32>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    pix_fmt: Option<String>,
+    #[serde(default)]
+    side_data_list: Vec<ProbeSideData>,
+    #[serde(default)]
+    disposition: std::collections::HashMap<String, i32>,
+    #[serde(default)]
+    tags: std::collections::HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ProbeSideData {
+    /// Degrees counter-clockwise, as ffprobe reports the display matrix.
+    rotation: Option<f64>,
+}
+
+/// The display rotation of a stream, normalized to 0/90/180/270 clockwise.
+/// ffprobe reports the display matrix as counter-clockwise degrees in side
+/// data; older files carry a clockwise `rotate` tag instead.
+fn stream_rotation(stream: &ProbeStream) -> Option<u32> {
+    let degrees = stream
+        .side_data_list
+        .iter()
+        .find_map(|s| s.rotation)
+        .map(|ccw| -ccw)
+        .or_else(|| stream.tags.get("rotate").and_then(|r| r.parse().ok()))?;
+    let normalized = (degrees.round() as i64).rem_euclid(360) as u32;
+    (normalized != 0).then_some(normalized)
+}
+
+/// Parse an ffprobe rational frame rate ("24000/1001", "25/1") into fps.
+fn parse_frame_rate(rate: &str) -> Option<f64> {
+    let (num, den) = rate.trim().split_once('/')?;
+    let num: f64 = num.parse().ok()?;
+    let den: f64 = den.parse().ok()?;
+    if den == 0.0 || num == 0.0 {
+        return None;
+    }
+    Some(num / den)
+}
+
+/// VFR heuristic: ffprobe's average and nominal frame rates diverge (or
+/// the average is unparseable while a nominal rate exists). CFR sources
+/// report the same value for both.
+fn is_variable_frame_rate(avg: Option<&str>, nominal: Option<&str>) -> bool {
+    let nominal_fps = nominal.and_then(parse_frame_rate);
+    match (avg.and_then(parse_frame_rate), nominal_fps) {
+        (Some(avg), Some(nominal)) => (avg - nominal).abs() / nominal > 0.005,
+        (None, Some(_)) => true,
+        _ => false,
+    }
+}
+
+/// Run ffprobe against `input` and parse the bits we care about.
+pub async fn probe(input: &Path) -> Result<VideoMetadata> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(input)
+        .output()
+        .await
+        .map_err(|e| AppError::Ffprobe(format!("failed to spawn ffprobe: {e}")))?;
+    if !output.status.success() {
+        return Err(AppError::Ffprobe(format!(
+            "ffprobe exited with {} for {}",
+            output.status,
+            input.display()
+        )));
+    }
+    let probe: ProbeOutput = serde_json::from_slice(&output.stdout)
+        .map_err(|e| AppError::Ffprobe(format!("unparseable ffprobe output: {e}")))?;
+
+    let video = probe
+        .streams
+        .iter()
+        .find(|s| s.codec_type == "video")
+        .ok_or_else(|| AppError::Ffprobe(format!("no video stream in {}", input.display())))?;
+    let audio = probe.streams.iter().find(|s| s.codec_type == "audio");
+    let audio_tracks = probe
+        .streams
+        .iter()
+        .filter(|s| s.codec_type == "audio")
+        .map(|s| AudioTrack {
+            index: s.index.unwrap_or(0),
+            language: s
+                .tags
+                .get("language")
+                .cloned()
+                .unwrap_or_else(|| "und".into()),
+            title: s.tags.get("title").cloned(),
+            codec: s.codec_name.clone().unwrap_or_default(),
+            channels: s.channels.unwrap_or(0),
+            default: s.disposition.get("default") == Some(&1),
+        })
+        .collect();
+
+    Ok(VideoMetadata {
+        width: video.width.unwrap_or(0),
+        height: video.height.unwrap_or(0),
+        duration_seconds: probe
+            .format
+            .duration
+            .as_deref()
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(0.0),
+        bit_rate: probe.format.bit_rate.as_deref().and_then(|b| b.parse().ok()),
+        video_codec: video.codec_name.clone().unwrap_or_default(),
+        audio_codec: audio.and_then(|a| a.codec_name.clone()),
+        audio_tracks,
+        average_frame_rate: video.avg_frame_rate.as_deref().and_then(parse_frame_rate),
+        variable_frame_rate: is_variable_frame_rate(
+            video.avg_frame_rate.as_deref(),
+            video.r_frame_rate.as_deref(),
+        ),
+        pix_fmt: video.pix_fmt.clone(),
+        rotation: stream_rotation(video),
+    })
+}
+
+/// Video encoders this ffmpeg build ships, parsed from `ffmpeg -encoders`.
+pub async fn available_encoders() -> Result<std::collections::HashSet<String>> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-encoders"])
+        .output()
+        .await
+        .map_err(spawn_error)?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        // Encoder lines look like " V....D libx264  H.264 / AVC ...".
+        .filter(|line| line.starts_with(" V"))
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(String::from)
+        .collect())
+}
+
+/// Check whether `encoder` actually initializes by encoding a fraction of a
+/// second of test video. Listing in `-encoders` isn't enough: nvenc can be
+/// compiled in but fail at runtime without a working driver.
+async fn encoder_initializes(encoder: &str) -> bool {
+    Command::new("ffmpeg")
+        .args([
+            "-hide_banner",
+            "-f",
+            "lavfi",
+            "-i",
+            "nullsrc=s=256x144:d=0.1",
+            "-c:v",
+            encoder,
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .await
+        .map(|out| out.status.success())
+        .unwrap_or(false)
+}
+
+/// Walk the configured fallback chain and return the first encoder that
+/// initializes, emitting `encoder-selected` so the UI can show what's in
+/// use. Errors only if nothing in the chain works.
+pub async fn select_encoder(app: &AppHandle, settings: &Settings) -> Result<String> {
+    for encoder in &settings.encoder_fallback_chain {
+        if encoder_initializes(encoder).await {
+            let _ = app.emit("encoder-selected", encoder.clone());
+            return Ok(encoder.clone());
+        }
+        let _ = app.emit(
+            "encoder-unavailable",
+            format!("{encoder} failed to initialize, trying next in chain"),
+        );
+    }
+    Err(AppError::Ffmpeg(format!(
+        "no encoder in the fallback chain initialized: {:?}",
+        settings.encoder_fallback_chain
+    )))
+}
+
+/// A chapter marker carried in the source container (common in MKV rips).
+#[derive(Debug, Clone, Serialize)]
+pub struct Chapter {
+    pub title: Option<String>,
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+}
+
+#[derive(Deserialize)]
+struct ChapterProbeOutput {
+    #[serde(default)]
+    chapters: Vec<RawChapter>,
+}
+
+#[derive(Deserialize)]
+struct RawChapter {
+    start_time: Option<String>,
+    end_time: Option<String>,
+    #[serde(default)]
+    tags: std::collections::HashMap<String, String>,
+}
+
+/// One subtitle stream of the source container.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubtitleStream {
+    pub index: u32,
+    pub codec: String,
+    pub language: String,
+    pub title: Option<String>,
+}
+
+/// List the subtitle streams in `input`, with language defaulting to "und".
+pub async fn probe_subtitle_streams(input: &Path) -> Result<Vec<SubtitleStream>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "quiet", "-print_format", "json", "-show_streams"])
+        .arg(input)
+        .output()
+        .await
+        .map_err(|e| AppError::Ffprobe(format!("failed to spawn ffprobe: {e}")))?;
+    if !output.status.success() {
+        return Err(AppError::Ffprobe(format!(
+            "ffprobe exited with {} for {}",
+            output.status,
+            input.display()
+        )));
+    }
+    #[derive(Deserialize)]
+    struct Streams {
+        #[serde(default)]
+        streams: Vec<ProbeStream>,
+    }
+    let probe: Streams = serde_json::from_slice(&output.stdout)
+        .map_err(|e| AppError::Ffprobe(format!("unparseable ffprobe output: {e}")))?;
+    Ok(probe
+        .streams
+        .iter()
+        .filter(|s| s.codec_type == "subtitle")
+        .map(|s| SubtitleStream {
+            index: s.index.unwrap_or(0),
+            codec: s.codec_name.clone().unwrap_or_default(),
+            language: s
+                .tags
+                .get("language")
+                .cloned()
+                .unwrap_or_else(|| "und".into()),
+            title: s.tags.get("title").cloned(),
+        })
+        .collect())
+}
+
+/// Read chapter markers via ffprobe. Sources without chapters yield an
+/// empty list, not an error.
+#[tauri::command]
+pub async fn extract_chapters(input_path: PathBuf) -> Result<Vec<Chapter>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "quiet", "-print_format", "json", "-show_chapters"])
+        .arg(&input_path)
+        .output()
+        .await
+        .map_err(|e| AppError::Ffprobe(format!("failed to spawn ffprobe: {e}")))?;
+    if !output.status.success() {
+        return Err(AppError::Ffprobe(format!(
+            "ffprobe exited with {} for {}",
+            output.status,
+            input_path.display()
+        )));
+    }
+    let probe: ChapterProbeOutput = serde_json::from_slice(&output.stdout)
+        .map_err(|e| AppError::Ffprobe(format!("unparseable ffprobe output: {e}")))?;
+    Ok(probe
+        .chapters
+        .into_iter()
+        .map(|c| Chapter {
+            title: c.tags.get("title").cloned(),
+            start_seconds: c
+                .start_time
+                .as_deref()
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(0.0),
+            end_seconds: c
+                .end_time
+                .as_deref()
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(0.0),
+        })
+        .collect())
+}
+
+/// How many points of the file `detect_crop` samples. Several spread-out
+/// samples beat one long pass: the start of a movie is often a black
+/// fade-in that cropdetect would mistake for full-frame letterboxing.
+const CROP_SAMPLES: usize = 5;
+/// Seconds of video analyzed per sample point.
+const CROP_SAMPLE_SECONDS: f64 = 2.0;
+
+/// Suggested crop for letterboxed content, with how consistently the
+/// sampled points agreed on it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CropDetection {
+    /// ffmpeg crop rectangle (`w:h:x:y`), or None when no sample produced
+    /// a detection.
+    pub crop: Option<String>,
+    pub samples: usize,
+    /// Samples whose detection matched the winning rectangle.
+    pub agreeing_samples: usize,
+    /// `agreeing_samples / samples` — treat anything below ~0.8 as content
+    /// with a variable frame (and don't auto-crop it).
+    pub confidence: f64,
+}
+
+/// The last crop rectangle cropdetect printed, from ffmpeg's stderr.
+fn last_cropdetect(stderr: &str) -> Option<String> {
+    stderr
+        .lines()
+        .rev()
+        .find_map(|line| line.split("crop=").nth(1))
+        .map(|rest| {
+            rest.split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .filter(|s| !s.is_empty())
+}
+
+/// Run cropdetect at several evenly spaced points of the source and report
+/// the most common detected rectangle plus how many samples agreed on it.
+/// The frontend can offer the crop when confidence is high.
+#[tauri::command]
+pub async fn detect_crop(input_path: PathBuf) -> Result<CropDetection> {
+    let metadata = probe(&input_path).await?;
+    if metadata.duration_seconds <= 0.0 {
+        return Err(AppError::Ffprobe(format!(
+            "{} reports no duration",
+            input_path.display()
+        )));
+    }
+
+    let mut tally: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    let mut samples = 0;
+    for i in 0..CROP_SAMPLES {
+        let start = metadata.duration_seconds * (i + 1) as f64 / (CROP_SAMPLES + 1) as f64;
+        let output = Command::new("ffmpeg")
+            .args(["-hide_banner", "-ss", &format!("{start:.3}")])
+            .args(["-t", &format!("{CROP_SAMPLE_SECONDS:.1}")])
+            .arg("-i")
+            .arg(&input_path)
+            .args(["-vf", "cropdetect", "-f", "null", "-"])
+            .output()
+            .await
+            .map_err(spawn_error)?;
+        samples += 1;
+        if let Some(crop) = last_cropdetect(&String::from_utf8_lossy(&output.stderr)) {
+            *tally.entry(crop).or_insert(0) += 1;
+        }
+    }
+
+    let winner = tally.into_iter().max_by_key(|(_, count)| *count);
+    let (crop, agreeing_samples) = match winner {
+        Some((crop, count)) => (Some(crop), count),
+        None => (None, 0),
+    };
+    Ok(CropDetection {
+        crop,
+        samples,
+        agreeing_samples,
+        confidence: agreeing_samples as f64 / samples.max(1) as f64,
+    })
+}
+
+/// Category of a compatibility warning, so the UI can group and style them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompatibilityKind {
+    PixelFormat,
+    AudioCodec,
+    Duration,
+    Rotation,
+}
+
+/// One pre-conversion warning about a source, with a suggested remedy.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatibilityWarning {
+    pub kind: CompatibilityKind,
+    pub message: String,
+    pub remedy: String,
+}
+
+/// Pixel formats the pipeline handles without surprises. Anything else
+/// (10/12-bit, 4:2:2, 4:4:4) either fails hardware encoders outright or
+/// produces output many players reject.
+fn unusual_pixel_format(pix_fmt: &str) -> bool {
+    !matches!(pix_fmt, "yuv420p" | "yuvj420p" | "nv12")
+}
+
+/// Audio codecs ffmpeg reliably decodes on every build we ship against;
+/// anything else gets flagged rather than failing mid-conversion.
+const COMMON_AUDIO_CODECS: &[&str] = &[
+    "aac", "ac3", "eac3", "mp3", "dts", "truehd", "flac", "opus", "vorbis",
+];
+
+/// The warnings `analyze_compatibility` derives from a probe.
+fn compatibility_warnings(metadata: &VideoMetadata) -> Vec<CompatibilityWarning> {
+    let mut warnings = Vec::new();
+    if let Some(pix_fmt) = metadata.pix_fmt.as_deref() {
+        if unusual_pixel_format(pix_fmt) {
+            warnings.push(CompatibilityWarning {
+                kind: CompatibilityKind::PixelFormat,
+                message: format!(
+                    "unusual pixel format {pix_fmt}: hardware encoders may fail and some players \
+                     reject the output"
+                ),
+                remedy: "re-encode with -pix_fmt yuv420p (disable fast remux so the video is \
+                         actually re-encoded)"
+                    .into(),
+            });
+        }
+    }
+    for track in &metadata.audio_tracks {
+        if !track.codec.is_empty()
+            && !track.codec.starts_with("pcm_")
+            && !COMMON_AUDIO_CODECS.contains(&track.codec.as_str())
+        {
+            warnings.push(CompatibilityWarning {
+                kind: CompatibilityKind::AudioCodec,
+                message: format!("audio track {} uses uncommon codec {}", track.index, track.codec),
+                remedy: "audio is re-encoded to AAC; if conversion fails, extract and convert the \
+                         track separately first"
+                    .into(),
+            });
+        }
+    }
+    if metadata.duration_seconds <= 0.0 {
+        warnings.push(CompatibilityWarning {
+            kind: CompatibilityKind::Duration,
+            message: "source reports no duration".into(),
+            remedy: "remux the file (ffmpeg -i in -c copy out.mkv) to rebuild its index; progress \
+                     and size estimates are unavailable until then"
+                .into(),
+        });
+    }
+    if let Some(rotation) = metadata.rotation {
+        warnings.push(CompatibilityWarning {
+            kind: CompatibilityKind::Rotation,
+            message: format!("rotation metadata present ({rotation}°); will be applied"),
+            remedy: "none needed while respect_rotation is on: the rotation is baked into the \
+                     output pixels (fast remux is skipped for rotated sources)"
+                .into(),
+        });
+    }
+    warnings
+}
+
+/// Probe `input_path` and report the things likely to cause a confusing
+/// failure or broken playback later — odd pixel formats, exotic audio,
+/// missing duration, rotation metadata — each with a suggested remedy.
+/// Front-loads diagnosis the conversion would otherwise surface as an
+/// opaque ffmpeg error.
+#[tauri::command]
+pub async fn analyze_compatibility(input_path: PathBuf) -> Result<Vec<CompatibilityWarning>> {
+    Ok(compatibility_warnings(&probe(&input_path).await?))
+}
+
+/// Whether an encoder runs on dedicated hardware (and therefore counts
+/// against the GPU session limit rather than the CPU one).
+pub fn is_hardware_encoder(encoder: &str) -> bool {
+    ["_nvenc", "_qsv", "_vaapi", "_amf", "_videotoolbox"]
+        .iter()
+        .any(|suffix| encoder.ends_with(suffix))
+}
+
+/// File extension of a media segment for the configured container.
+fn segment_extension(segment_type: HlsSegmentType) -> &'static str {
+    match segment_type {
+        HlsSegmentType::Mpegts => "ts",
+        HlsSegmentType::Fmp4 => "m4s",
+    }
+}
+
+/// Whether any audio track in the source carries more than two channels.
+fn source_has_surround_audio(metadata: &VideoMetadata) -> bool {
+    metadata.audio_tracks.iter().any(|t| t.channels > 2)
+}
+
+/// The `-hwaccel` backend matching a hardware encoder, so decode can stay
+/// on the same device as encode. CPU encoders get None: GPU decode feeding
+/// a CPU encoder would need an explicit hwdownload filter, which isn't
+/// worth the complexity here.
+fn hwaccel_for_encoder(encoder: &str) -> Option<&'static str> {
+    if encoder.ends_with("_nvenc") {
+        Some("cuda")
+    } else if encoder.ends_with("_qsv") {
+        Some("qsv")
+    } else if encoder.ends_with("_vaapi") {
+        Some("vaapi")
+    } else if encoder.ends_with("_videotoolbox") {
+        Some("videotoolbox")
+    } else {
+        None
+    }
+}
+
+/// Quality name for the source's native resolution, matching the keys the
+/// web player expects (`original-1080p`, `original-720p`, ...).
+pub fn original_rendition_name(height: u32) -> &'static str {
+    match height {
+        h if h >= 2160 => "original-4k",
+        h if h >= 1440 => "original-1440p",
+        h if h >= 1080 => "original-1080p",
+        h if h >= 720 => "original-720p",
+        h if h >= 480 => "original-480p",
+        _ => "original",
+    }
+}
+
+/// One output rendition of a conversion.
+#[derive(Debug, Clone)]
+pub struct Rendition {
+    pub name: String,
+    /// None means "keep source resolution".
+    pub target_height: Option<u32>,
+    pub video_bitrate: Option<String>,
+}
+
+/// The renditions we produce: a 480p version for slow connections (when the
+/// source is taller than 480) plus the original quality.
+pub fn plan_renditions(metadata: &VideoMetadata) -> Vec<Rendition> {
+    let mut renditions = Vec::new();
+    if metadata.height > 480 {
+        renditions.push(Rendition {
+            name: "480p".into(),
+            target_height: Some(480),
+            video_bitrate: Some("1400k".into()),
+        });
+    }
+    renditions.push(Rendition {
+        name: original_rendition_name(metadata.height).into(),
+        target_height: None,
+        video_bitrate: None,
+    });
+    renditions
+}
+
+/// Parse an ffmpeg-style bitrate ("1400k", "2M", "800000") into bits/sec.
+fn parse_bitrate(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if let Some(v) = s.strip_suffix(['k', 'K']) {
+        v.parse::<u64>().ok().map(|v| v * 1_000)
+    } else if let Some(v) = s.strip_suffix(['m', 'M']) {
+        v.parse::<u64>().ok().map(|v| v * 1_000_000)
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// Audio bitrate the pipeline encodes at (see `encode_rendition`).
+const AUDIO_BITRATE_BPS: u64 = 128_000;
+
+/// Fallback when the source reports no bitrate and the rendition copies it.
+const FALLBACK_VIDEO_BITRATE_BPS: u64 = 5_000_000;
+
+/// Directory name and AAC bitrate of the optional audio-only fallback
+/// rendition (`audio_only_rendition`).
+const AUDIO_ONLY_RENDITION: &str = "audio";
+const AUDIO_ONLY_BITRATE: &str = "64k";
+/// BANDWIDTH advertised for the audio-only variant: the AAC bitrate plus
+/// container overhead — far below any video rendition, which is the point.
+const AUDIO_ONLY_BANDWIDTH: u64 = 72_000;
+
+/// Directory, target height, and encode bitrate of the optional I-frame-only
+/// trick-play rendition (`generate_iframe_playlist`). Scrubbing previews
+/// don't need full resolution, so keyframes are scaled down and re-encoded
+/// cheaply rather than byterange-indexed out of the main renditions.
+const IFRAME_RENDITION: &str = "iframes";
+const IFRAME_HEIGHT: u32 = 360;
+const IFRAME_BITRATE: &str = "200k";
+/// BANDWIDTH advertised in the `#EXT-X-I-FRAME-STREAM-INF` entry: the encode
+/// bitrate plus container overhead.
+const IFRAME_BANDWIDTH: u64 = 220_000;
+
+/// Estimated storage for one planned rendition.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenditionSizeEstimate {
+    pub rendition: String,
+    pub estimated_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputSizeEstimate {
+    pub renditions: Vec<RenditionSizeEstimate>,
+    pub total_bytes: u64,
+}
+
+/// Ballpark the R2 storage a conversion will consume: per rendition,
+/// duration × (video + audio bitrate) / 8. The original rendition uses the
+/// probed source bitrate since it's stream-copied. Container/segmentation
+/// overhead and VBR variance are deliberately ignored — treat this as an
+/// estimate for the UI, not a quota.
+#[tauri::command]
+pub async fn estimate_output_size(input: PathBuf) -> Result<OutputSizeEstimate> {
+    let metadata = probe(&input).await?;
+    let mut renditions = Vec::new();
+    let mut total_bytes = 0u64;
+    for rendition in plan_renditions(&metadata) {
+        let estimated_bytes = estimate_rendition_bytes(&metadata, &rendition);
+        total_bytes += estimated_bytes;
+        renditions.push(RenditionSizeEstimate {
+            rendition: rendition.name,
+            estimated_bytes,
+        });
+    }
+    Ok(OutputSizeEstimate {
+        renditions,
+        total_bytes,
+    })
+}
+
+/// duration × (video + audio bitrate) / 8 for one rendition.
+fn estimate_rendition_bytes(metadata: &VideoMetadata, rendition: &Rendition) -> u64 {
+    let video_bps = rendition
+        .video_bitrate
+        .as_deref()
+        .and_then(parse_bitrate)
+        .or(metadata.bit_rate)
+        .unwrap_or(FALLBACK_VIDEO_BITRATE_BPS);
+    (metadata.duration_seconds * (video_bps + AUDIO_BITRATE_BPS) as f64 / 8.0) as u64
+}
+
+/// Seconds of source each rendition encodes in `check_realtime_capability`.
+/// Long enough to get past encoder warm-up, short enough to finish while
+/// the user is still looking at the screen.
+const REALTIME_BENCHMARK_SECONDS: f64 = 10.0;
+
+/// How fast one rendition of the ladder encodes on this machine.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenditionRealtime {
+    pub rendition: String,
+    pub encoder: String,
+    pub elapsed_seconds: f64,
+    /// Seconds of content encoded per wall-clock second; >= 1.0 keeps up
+    /// with playback.
+    pub realtime_multiplier: f64,
+}
+
+/// Result of the realtime benchmark: per-rendition speed plus the verdict
+/// for a full conversion.
+#[derive(Debug, Clone, Serialize)]
+pub struct RealtimeCapability {
+    /// Seconds of source each rendition encoded.
+    pub benchmark_seconds: f64,
+    pub renditions: Vec<RenditionRealtime>,
+    /// Multiplier across the whole ladder encoded sequentially, which is
+    /// how `convert` runs it.
+    pub overall_multiplier: f64,
+    pub realtime: bool,
+}
+
+/// Encode the first few seconds of `input` once per planned rendition,
+/// with the exact arguments a real conversion would use, and report how
+/// much faster than playback each one runs. Lets users judge before a
+/// batch whether this machine keeps up — a multiplier below 1.0 means an
+/// overnight queue, not a lunch break.
+#[tauri::command]
+pub async fn check_realtime_capability(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    input: PathBuf,
+) -> Result<RealtimeCapability> {
+    let settings = store.get();
+    let metadata = oriented_metadata(&settings, probe(&input).await?);
+    let encoder = select_encoder(&app, &settings).await?;
+    let bench = if metadata.duration_seconds > 0.0 {
+        REALTIME_BENCHMARK_SECONDS.min(metadata.duration_seconds)
+    } else {
+        REALTIME_BENCHMARK_SECONDS
+    };
+
+    let scratch = std::env::temp_dir().join(format!("uploader-realtime-{}", std::process::id()));
+    let result = benchmark_ladder(&settings, &input, &metadata, &encoder, &scratch, bench).await;
+    let _ = tokio::fs::remove_dir_all(&scratch).await;
+    let renditions = result?;
+
+    let total_elapsed: f64 = renditions.iter().map(|r| r.elapsed_seconds).sum();
+    let overall_multiplier = bench / total_elapsed.max(f64::EPSILON);
+    Ok(RealtimeCapability {
+        benchmark_seconds: bench,
+        renditions,
+        overall_multiplier,
+        realtime: overall_multiplier >= 1.0,
+    })
+}
+
+/// Run and time the benchmark encode for each planned rendition.
+async fn benchmark_ladder(
+    settings: &Settings,
+    input: &Path,
+    metadata: &VideoMetadata,
+    encoder: &str,
+    scratch: &Path,
+    bench: f64,
+) -> Result<Vec<RenditionRealtime>> {
+    let mut results = Vec::new();
+    for rendition in plan_renditions(metadata) {
+        let encoder = rendition_encoder(settings, &rendition, encoder);
+        let out_dir = scratch.join(&rendition.name);
+        tokio::fs::create_dir_all(&out_dir).await?;
+        let mut args = build_ffmpeg_args(
+            settings, input, metadata, &rendition, encoder, &out_dir, None, None, false, None,
+            None,
+        );
+        // Cap the encode at the benchmark window; the playlist path must
+        // stay the final argument.
+        let playlist = args.pop().expect("argv always ends with the playlist");
+        args.push("-t".into());
+        args.push(format!("{bench:.3}").into());
+        args.push(playlist);
+
+        let started = std::time::Instant::now();
+        let output = Command::new("ffmpeg")
+            .args(&args)
+            .output()
+            .await
+            .map_err(spawn_error)?;
+        if !output.status.success() {
+            return Err(AppError::Ffmpeg(format!(
+                "benchmark encode of rendition {} exited with {}",
+                rendition.name, output.status
+            )));
+        }
+        let elapsed_seconds = started.elapsed().as_secs_f64().max(f64::EPSILON);
+        results.push(RenditionRealtime {
+            rendition: rendition.name,
+            encoder: encoder.to_string(),
+            elapsed_seconds,
+            realtime_multiplier: bench / elapsed_seconds,
+        });
+    }
+    Ok(results)
+}
+
+/// One bounded encode of the top planned rendition, timed; the pipeline
+/// benchmark uploads what lands in `out_dir`.
+pub(crate) struct SampleEncode {
+    pub encoder: String,
+    pub rendition: String,
+    /// Seconds of source actually encoded (clamped to the duration).
+    pub sample_seconds: f64,
+    pub elapsed_seconds: f64,
+    /// Source frames the sample covers, when the probe reported a frame
+    /// rate; None leaves the fps figure out rather than guessing one.
+    pub frames: Option<f64>,
+}
+
+/// Encode `seconds` of `input` at the top planned rendition with the exact
+/// arguments a real conversion would use, into `out_dir`. Used by
+/// `benchmark_pipeline` so its convert figure reflects real settings.
+pub(crate) async fn encode_benchmark_sample(
+    app: &AppHandle,
+    settings: &Settings,
+    input: &Path,
+    out_dir: &Path,
+    seconds: f64,
+) -> Result<SampleEncode> {
+    let metadata = oriented_metadata(settings, probe(input).await?);
+    let selected = select_encoder(app, settings).await?;
+    let rendition = plan_renditions(&metadata)
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::Ffmpeg("no rendition planned for this input".into()))?;
+    let encoder = rendition_encoder(settings, &rendition, &selected).to_string();
+    let sample = if metadata.duration_seconds > 0.0 {
+        seconds.min(metadata.duration_seconds)
+    } else {
+        seconds
+    };
+
+    tokio::fs::create_dir_all(out_dir).await?;
+    let mut args = build_ffmpeg_args(
+        settings, input, &metadata, &rendition, &encoder, out_dir, None, None, false, None, None,
+    );
+    let playlist = args.pop().expect("argv always ends with the playlist");
+    args.push("-t".into());
+    args.push(format!("{sample:.3}").into());
+    args.push(playlist);
+
+    let started = std::time::Instant::now();
+    let output = Command::new("ffmpeg")
+        .args(&args)
+        .output()
+        .await
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "benchmark encode of rendition {} exited with {}",
+            rendition.name, output.status
+        )));
+    }
+    Ok(SampleEncode {
+        encoder,
+        rendition: rendition.name,
+        sample_seconds: sample,
+        elapsed_seconds: started.elapsed().as_secs_f64().max(f64::EPSILON),
+        frames: metadata.average_frame_rate.map(|fps| fps * sample),
+    })
+}
+
+/// How much of the source the timestamp scan reads. Broken DTS almost
+/// always shows up early; scanning a whole 3-hour remux would double the
+/// conversion's I/O for nothing.
+const TIMESTAMP_SCAN_SECONDS: f64 = 120.0;
+
+/// Largest audio sync correction accepted, either direction.
+const MAX_AUDIO_OFFSET_MS: i64 = 60_000;
+
+/// Does this ffmpeg stderr report non-monotonic or otherwise broken
+/// timestamps?
+fn stderr_reports_broken_timestamps(stderr: &str) -> bool {
+    stderr.lines().any(|l| {
+        l.contains("Non-monotonic DTS") || l.contains("non monotonically increasing dts")
+    })
+}
+
+/// Stream-copy the first couple of minutes into the null muxer and watch
+/// stderr for timestamp complaints — the same warnings a real conversion
+/// would hit, without the encode.
+async fn detect_broken_timestamps(input: &Path) -> Result<bool> {
+    let output = Command::new("ffmpeg")
+        .args(["-v", "warning", "-i"])
+        .arg(input)
+        .args(["-t", &format!("{TIMESTAMP_SCAN_SECONDS:.0}")])
+        .args(["-map", "0", "-c", "copy", "-f", "null", "-"])
+        .output()
+        .await
+        .map_err(spawn_error)?;
+    Ok(stderr_reports_broken_timestamps(&String::from_utf8_lossy(
+        &output.stderr,
+    )))
+}
+
+/// One side of an A/B encode comparison: an encoder plus the quality knobs
+/// worth tuning. Unset knobs fall back to the encoder's defaults.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AbProfile {
+    pub encoder: String,
+    /// `-crf` value (libx264/libx265); ignored by encoders without CRF.
+    pub crf: Option<u32>,
+    /// `-b:v`, e.g. "2500k".
+    pub video_bitrate: Option<String>,
+    /// `-preset`, e.g. "medium" or nvenc's "p5".
+    pub preset: Option<String>,
+}
+
+/// Side-by-side numbers for the two profiles. VMAF scores are present only
+/// when this ffmpeg build carries libvmaf; `note` says so when they aren't.
+#[derive(Debug, Clone, Serialize)]
+pub struct AbComparison {
+    pub clip_seconds: f64,
+    pub size_a: u64,
+    pub size_b: u64,
+    pub encode_time_a: f64,
+    pub encode_time_b: f64,
+    pub vmaf_a: Option<f64>,
+    pub vmaf_b: Option<f64>,
+    pub note: Option<String>,
+}
+
+/// Pull the score out of libvmaf's stderr summary line.
+fn parse_vmaf_score(stderr: &str) -> Option<f64> {
+    stderr
+        .lines()
+        .rev()
+        .find_map(|l| l.split("VMAF score:").nth(1))
+        .and_then(|s| s.trim().parse().ok())
+}
+
+/// Encode one clip with one profile, returning (bytes, wall seconds).
+async fn encode_ab_clip(
+    input: &Path,
+    clip_seconds: f64,
+    profile: &AbProfile,
+    out: &Path,
+) -> Result<(u64, f64)> {
+    let mut args: Vec<std::ffi::OsString> = vec!["-y".into(), "-i".into(), input.into()];
+    args.push("-t".into());
+    args.push(format!("{clip_seconds:.3}").into());
+    args.push("-c:v".into());
+    args.push(profile.encoder.as_str().into());
+    if let Some(crf) = profile.crf {
+        args.push("-crf".into());
+        args.push(crf.to_string().into());
+    }
+    if let Some(bitrate) = &profile.video_bitrate {
+        args.push("-b:v".into());
+        args.push(bitrate.as_str().into());
+    }
+    if let Some(preset) = &profile.preset {
+        args.push("-preset".into());
+        args.push(preset.as_str().into());
+    }
+    for s in ["-c:a", "aac", "-b:a", "128k"] {
+        args.push(s.into());
+    }
+    args.push(out.into());
+
+    let started = std::time::Instant::now();
+    let output = Command::new("ffmpeg")
+        .args(&args)
+        .output()
+        .await
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "A/B encode with {} exited with {}",
+            profile.encoder, output.status
+        )));
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    let size = tokio::fs::metadata(out).await?.len();
+    Ok((size, elapsed))
+}
+
+/// Score one encoded clip against the source with libvmaf. None when the
+/// filter run fails or prints no score.
+async fn vmaf_against_source(source: &Path, clip_seconds: f64, encoded: &Path) -> Option<f64> {
+    let output = Command::new("ffmpeg")
+        .arg("-i")
+        .arg(encoded)
+        .arg("-t")
+        .arg(format!("{clip_seconds:.3}"))
+        .arg("-i")
+        .arg(source)
+        .args(["-lavfi", "libvmaf", "-f", "null", "-"])
+        .output()
+        .await
+        .ok()?;
+    parse_vmaf_score(&String::from_utf8_lossy(&output.stderr))
+}
+
+/// Does this ffmpeg build carry the libvmaf filter?
+async fn has_libvmaf() -> bool {
+    match Command::new("ffmpeg")
+        .args(["-hide_banner", "-filters"])
+        .output()
+        .await
+    {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains("libvmaf"),
+        Err(_) => false,
+    }
+}
+
+/// Encode the same short clip of `input` with two profiles and report size,
+/// encode time, and (when libvmaf is available) VMAF against the source —
+/// the concrete numbers behind "is CRF 20 worth it over 23 here". Both
+/// temp outputs are removed afterwards.
+#[tauri::command]
+pub async fn ab_compare(
+    input_path: PathBuf,
+    clip_seconds: f64,
+    settings_a: AbProfile,
+    settings_b: AbProfile,
+) -> Result<AbComparison> {
+    if !clip_seconds.is_finite() || clip_seconds <= 0.0 {
+        return Err(AppError::InvalidInput(
+            "clip_seconds must be positive".into(),
+        ));
+    }
+    let scratch = std::env::temp_dir().join(format!("uploader-ab-{}", std::process::id()));
+    tokio::fs::create_dir_all(&scratch).await?;
+    let out_a = scratch.join("a.mp4");
+    let out_b = scratch.join("b.mp4");
+
+    let result = async {
+        let (size_a, encode_time_a) =
+            encode_ab_clip(&input_path, clip_seconds, &settings_a, &out_a).await?;
+        let (size_b, encode_time_b) =
+            encode_ab_clip(&input_path, clip_seconds, &settings_b, &out_b).await?;
+        let (vmaf_a, vmaf_b, note) = if has_libvmaf().await {
+            (
+                vmaf_against_source(&input_path, clip_seconds, &out_a).await,
+                vmaf_against_source(&input_path, clip_seconds, &out_b).await,
+                None,
+            )
+        } else {
+            (
+                None,
+                None,
+                Some("this ffmpeg build lacks libvmaf; comparing size and time only".to_string()),
+            )
+        };
+        Ok(AbComparison {
+            clip_seconds,
+            size_a,
+            size_b,
+            encode_time_a,
+            encode_time_b,
+            vmaf_a,
+            vmaf_b,
+            note,
+        })
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&scratch).await;
+    result
+}
+
+/// Target height and bitrate of editing proxies: small enough to scrub on a
+/// laptop, sharp enough to read burned-in timecode.
+const PROXY_HEIGHT: u32 = 540;
+const PROXY_VIDEO_BITRATE: &str = "1000k";
+const PROXY_AUDIO_BITRATE: &str = "96k";
+
+/// What `generate_proxy` wrote.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyResult {
+    pub output_path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Speed-favoring preset flags per encoder family: a proxy is judged on how
+/// fast it encodes, not how small it is.
+fn proxy_preset_args(encoder: &str) -> &'static [&'static str] {
+    if encoder.ends_with("_nvenc") {
+        &["-preset", "p1"]
+    } else if encoder.ends_with("_qsv") {
+        &["-preset", "veryfast"]
+    } else if encoder.starts_with("lib") {
+        &["-preset", "ultrafast", "-tune", "fastdecode"]
+    } else {
+        &[]
+    }
+}
+
+/// The proxy's video filter: a half-height scale, plus a drawtext timecode
+/// strip along the bottom when burn-in is requested.
+fn proxy_filter(frame_rate: f64, burn_timecode: bool) -> String {
+    let scale = format!("scale=-2:{PROXY_HEIGHT}");
+    if !burn_timecode {
+        return scale;
+    }
+    format!(
+        "{scale},drawtext=timecode='00\\:00\\:00\\:00':rate={frame_rate:.3}:fontcolor=white:\
+         fontsize=24:box=1:boxcolor=black@0.5:x=(w-tw)/2:y=h-(2*lh)"
+    )
+}
+
+/// Transcode a lightweight 540p H.264 proxy of `input_path` for offline
+/// editing — a different artifact from the HLS renditions, aimed at editors
+/// rather than the site. Uses the configured encoder chain with
+/// speed-favoring presets; `burn_timecode` stamps a timecode strip into the
+/// picture.
+#[tauri::command]
+pub async fn generate_proxy(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    input_path: PathBuf,
+    output_path: PathBuf,
+    burn_timecode: Option<bool>,
+) -> Result<ProxyResult> {
+    let settings = store.get();
+    let input_path = crate::paths::normalize(&input_path.to_string_lossy())?;
+    let metadata = probe(&input_path).await?;
+    let encoder = select_encoder(&app, &settings).await?;
+    if let Some(parent) = output_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut args: Vec<std::ffi::OsString> =
+        vec!["-y".into(), "-i".into(), input_path.as_os_str().into()];
+    args.push("-vf".into());
+    args.push(
+        proxy_filter(
+            metadata.average_frame_rate.unwrap_or(25.0),
+            burn_timecode.unwrap_or(false),
+        )
+        .into(),
+    );
+    args.push("-c:v".into());
+    args.push(encoder.as_str().into());
+    for s in proxy_preset_args(&encoder) {
+        args.push(s.into());
+    }
+    for s in ["-b:v", PROXY_VIDEO_BITRATE, "-c:a", "aac", "-b:a", PROXY_AUDIO_BITRATE, "-ac", "2"]
+    {
+        args.push(s.into());
+    }
+    for s in ["-movflags", "+faststart"] {
+        args.push(s.into());
+    }
+    args.push(output_path.as_os_str().into());
+
+    let output = Command::new("ffmpeg")
+        .args(&args)
+        .output()
+        .await
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "proxy encode exited with {}",
+            output.status
+        )));
+    }
+    let bytes = tokio::fs::metadata(&output_path).await?.len();
+    Ok(ProxyResult { output_path, bytes })
+}
+
+/// Where a crashed conversion can pick up within one rendition, derived
+/// from the playlist the previous run left behind. The growing playlist
+/// doubles as crash-persistence: every completed segment is already listed
+/// in it, so resumption only needs to read it back. The final listed
+/// segment may have been mid-write when the process died, so it is always
+/// redone: with N segments listed we restart at index N-1.
+#[derive(Debug, Clone)]
+struct ResumePoint {
+    /// The playlist carries `#EXT-X-ENDLIST`; the rendition is done.
+    complete: bool,
+    /// Segment index to restart ffmpeg at (`-start_number`).
+    next_segment: u32,
+    /// Input seek offset: the summed durations of the segments we keep.
+    seek_seconds: f64,
+    /// The playlist rewritten without the redone final segment.
+    truncated_playlist: String,
+    /// Filename of the dropped (possibly corrupt) segment.
+    dropped_segment: Option<String>,
+}
+
+/// Analyze a rendition playlist left by an earlier run.
+fn resume_point(playlist: &str) -> ResumePoint {
+    let lines: Vec<&str> = playlist.lines().collect();
+    let complete = lines.iter().any(|l| l.trim() == "#EXT-X-ENDLIST");
+
+    // (line index of the #EXTINF, its duration, line index of the segment,
+    // segment filename) per entry, in playlist order.
+    let mut segments = Vec::new();
+    let mut pending_extinf: Option<(usize, f64)> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("#EXTINF:") {
+            let duration = rest
+                .split(',')
+                .next()
+                .and_then(|d| d.trim().parse().ok())
+                .unwrap_or(0.0);
+            pending_extinf = Some((i, duration));
+        } else if !line.is_empty() && !line.starts_with('#') {
+            segments.push((pending_extinf.take(), i, line.to_string()));
+        }
+    }
+
+    if complete || segments.is_empty() {
+        return ResumePoint {
+            complete,
+            next_segment: segments.len() as u32,
+            seek_seconds: 0.0,
+            truncated_playlist: playlist.to_string(),
+            dropped_segment: None,
+        };
+    }
+
+    let (last_extinf, last_line, last_name) = segments.pop().expect("checked non-empty");
+    let seek_seconds = segments
+        .iter()
+        .map(|(extinf, _, _)| extinf.map(|(_, d)| d).unwrap_or(0.0))
+        .sum();
+    let mut truncated_playlist = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i == last_line || Some(i) == last_extinf.map(|(i, _)| i) {
+            continue;
+        }
+        truncated_playlist.push_str(line);
+        truncated_playlist.push('\n');
+    }
+    ResumePoint {
+        complete: false,
+        next_segment: segments.len() as u32,
+        seek_seconds,
+        truncated_playlist,
+        dropped_segment: Some(last_name),
+    }
+}
+
+/// Prepare a rendition directory left by a crashed run for resumption:
+/// truncate the playlist to drop the (possibly corrupt) final segment and
+/// delete its file. Returns None when there is nothing worth resuming.
+async fn prepare_resume(out_dir: &Path) -> Result<Option<ResumePoint>> {
+    let playlist_path = out_dir.join("playlist.m3u8");
+    let contents = match tokio::fs::read_to_string(&playlist_path).await {
+        Ok(contents) => contents,
+        Err(_) => return Ok(None),
+    };
+    let resume = resume_point(&contents);
+    if resume.complete {
+        return Ok(Some(resume));
+    }
+    if resume.next_segment == 0 {
+        // Nothing usable survived; clear the header-only playlist so ffmpeg
+        // starts a fresh one.
+        tokio::fs::remove_file(&playlist_path).await?;
+        return Ok(None);
+    }
+    tokio::fs::write(&playlist_path, &resume.truncated_playlist).await?;
+    if let Some(dropped) = &resume.dropped_segment {
+        let _ = tokio::fs::remove_file(out_dir.join(dropped)).await;
+    }
+    Ok(Some(resume))
+}
+
+/// Whether a rendition can skip the video encode entirely and be remuxed
+/// into HLS segments with `-c copy`: fast mode is enabled, the rendition
+/// keeps the source resolution, the source codec is already HLS-friendly,
+/// and no filter (e.g. a burned-in subtitle) forces a re-encode. A rotated
+/// source also forces a re-encode when rotation is respected: TS segments
+/// can't carry the rotation tag, so it has to be baked into the pixels.
+fn is_fast_remux(
+    settings: &Settings,
+    metadata: &VideoMetadata,
+    rendition: &Rendition,
+    burn_filter: Option<&str>,
+) -> bool {
+    settings.fast_remux_if_compatible
+        && rendition.target_height.is_none()
+        && matches!(metadata.video_codec.as_str(), "h264" | "hevc")
+        && burn_filter.is_none()
+        && (metadata.rotation.is_none() || !settings.respect_rotation)
+}
+
+/// The probed metadata as displayed: sources rotated 90/270° swap their
+/// stored width and height, so the rendition ladder and master playlist
+/// describe what viewers actually see. ffmpeg's autorotate bakes the same
+/// rotation into the re-encoded pixels.
+fn oriented_metadata(settings: &Settings, mut metadata: VideoMetadata) -> VideoMetadata {
+    if settings.respect_rotation && matches!(metadata.rotation, Some(90) | Some(270)) {
+        std::mem::swap(&mut metadata.width, &mut metadata.height);
+    }
+    metadata
+}
+
+/// Escape a path for use inside an ffmpeg filter argument, where ':', '\'
+/// and quotes are structural characters.
+fn escape_filter_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace(':', "\\:")
+        .replace('\'', "\\'")
+}
+
+/// Build the `subtitles` filter that burns stream `index` of `input` into
+/// the video, validating that the stream exists and is text-based. The
+/// filter addresses subtitle streams by their position among subtitle
+/// streams (`si`), not the container-wide index users see in probes.
+async fn burn_subtitle_filter(input: &Path, index: u32) -> Result<String> {
+    let streams = probe_subtitle_streams(input).await?;
+    let position = streams
+        .iter()
+        .position(|s| s.index == index)
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("no subtitle stream with index {index} in the source"))
+        })?;
+    let stream = &streams[position];
+    if !crate::subtitles::TEXT_SUBTITLE_CODECS.contains(&stream.codec.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "subtitle stream {index} is {}, which cannot be burned in; only text subtitles are supported",
+            stream.codec
+        )));
+    }
+    Ok(format!(
+        "subtitles={}:si={position}",
+        escape_filter_path(input)
+    ))
+}
+
+/// The key file and ffmpeg keyinfo file a conversion with AES-128
+/// encryption writes at the root of the movie's output dir. Both are
+/// local-only: `r2::collect_files` excludes them from uploads, and the key
+/// is handed to the key server out-of-band.
+pub const HLS_KEY_FILE: &str = "hls.key";
+pub const HLS_KEY_INFO_FILE: &str = "hls.keyinfo";
+
+/// The `#EXT-X-KEY` URI for a movie: the configured key-server template
+/// with `{slug}` substituted.
+fn resolve_key_uri(template: &str, movie_id: &str) -> String {
+    template.replace("{slug}", movie_id)
+}
+
+/// The ffmpeg `-hls_key_info_file` contents: key URI on the first line
+/// (copied verbatim into the playlist's `#EXT-X-KEY`), local key path on
+/// the second. No explicit IV line; ffmpeg derives IVs from the segment
+/// sequence number, as the HLS spec defaults.
+fn key_info_contents(key_uri: &str, key_path: &Path) -> String {
+    format!("{key_uri}\n{}\n", key_path.display())
+}
+
+/// 16 bytes of key material. Hashes two fresh `RandomState`s (each seeded
+/// from OS entropy) plus time and pid through SHA-256 — no extra RNG
+/// dependency, and ample entropy for a per-movie content key.
+fn generate_key_bytes() -> [u8; 16] {
+    use std::hash::{BuildHasher, Hasher};
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for _ in 0..2 {
+        let mut h = std::collections::hash_map::RandomState::new().build_hasher();
+        h.write_u64(0);
+        hasher.update(h.finish().to_le_bytes());
+    }
+    hasher.update(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos()
+            .to_le_bytes(),
+    );
+    hasher.update(std::process::id().to_le_bytes());
+    let digest = hasher.finalize();
+    digest[..16].try_into().expect("sha256 yields 32 bytes")
+}
+
+/// When a key server is configured, generate this movie's AES-128 key and
+/// the keyinfo file ffmpeg encrypts against, both at the root of the
+/// output dir, and return the keyinfo path. Reuses an existing key so a
+/// resumed conversion doesn't re-key half the segments.
+async fn write_encryption_key(
+    settings: &Settings,
+    movie_id: &str,
+    out_dir: &Path,
+) -> Result<Option<PathBuf>> {
+    let Some(template) = &settings.key_server_url_template else {
+        return Ok(None);
+    };
+    let key_path = out_dir.join(HLS_KEY_FILE);
+    if tokio::fs::metadata(&key_path).await.is_err() {
+        tokio::fs::write(&key_path, generate_key_bytes()).await?;
+    }
+    let key_info_path = out_dir.join(HLS_KEY_INFO_FILE);
+    let uri = resolve_key_uri(template, movie_id);
+    tokio::fs::write(&key_info_path, key_info_contents(&uri, &key_path)).await?;
+    Ok(Some(key_info_path))
+}
+
+/// The ffmpeg argv for one rendition (everything except the progress
+/// plumbing), shared between execution and dry-run planning.
+#[allow(clippy::too_many_arguments)]
+fn build_ffmpeg_args(
+    settings: &Settings,
+    input: &Path,
+    metadata: &VideoMetadata,
+    rendition: &Rendition,
+    encoder: &str,
+    out_dir: &Path,
+    burn_filter: Option<&str>,
+    key_info: Option<&Path>,
+    repair_timestamps: bool,
+    audio_offset_ms: Option<i64>,
+    resume: Option<&ResumePoint>,
+) -> Vec<std::ffi::OsString> {
+    let playlist = out_dir.join("playlist.m3u8");
+    // Under single_file the filename is literal — one file holds every
+    // segment and the playlist addresses it with #EXT-X-BYTERANGE.
+    let segment_pattern = if settings.hls_single_file {
+        out_dir.join("media.ts")
+    } else {
+        out_dir.join(format!(
+            "segment_%03d.{}",
+            segment_extension(settings.hls_segment_type)
+        ))
+    };
+
+    let mut args: Vec<std::ffi::OsString> = Vec::new();
+    args.push("-y".into());
+    if let Some(resume) = resume {
+        args.push("-ss".into());
+        args.push(format!("{:.3}", resume.seek_seconds).into());
+    }
+    // GPU decode, but only alongside a matching hardware encoder. ffmpeg's
+    // -hwaccel is best-effort: if the accelerator can't handle the source it
+    // falls back to software decode on its own.
+    if settings.hwaccel_decode {
+        if let Some(hwaccel) = hwaccel_for_encoder(encoder) {
+            args.push("-hwaccel".into());
+            args.push(hwaccel.into());
+        }
+    }
+    // VAAPI addresses devices through render nodes rather than an encoder
+    // option, so the pin goes before the input.
+    if let Some(device) = settings.gpu_device_index {
+        if encoder.ends_with("_vaapi") {
+            args.push("-vaapi_device".into());
+            args.push(format!("/dev/dri/renderD{}", 128 + device).into());
+        }
+    }
+    // autorotate (ffmpeg's default) bakes rotation metadata into re-encoded
+    // pixels and clears the tag; disabled, the frames pass through as
+    // stored.
+    if !settings.respect_rotation {
+        args.push("-noautorotate".into());
+    }
+    // Input-side format flags must precede -i: +genpts regenerates PTS for
+    // sources with broken DTS/PTS, +bitexact keeps demuxing reproducible.
+    let mut fflags = String::new();
+    if repair_timestamps {
+        fflags.push_str("+genpts");
+    }
+    if settings.deterministic {
+        fflags.push_str("+bitexact");
+    }
+    if !fflags.is_empty() {
+        args.push("-fflags".into());
+        args.push(fflags.into());
+    }
+    args.push("-i".into());
+    args.push(input.into());
+    let stream_copy = is_fast_remux(settings, metadata, rendition, burn_filter);
+    let mut filters = Vec::new();
+    if let Some(height) = rendition.target_height {
+        filters.push(format!("scale=-2:{height}"));
+    }
+    if let Some(burn) = burn_filter {
+        filters.push(burn.to_string());
+    }
+    if !filters.is_empty() {
+        args.push("-vf".into());
+        args.push(filters.join(",").into());
+    }
+    if stream_copy {
+        args.push("-c:v".into());
+        args.push("copy".into());
+    } else {
+        args.push("-c:v".into());
+        args.push(encoder.into());
+        // Pin nvenc to the configured device on multi-GPU machines.
+        if let Some(device) = settings.gpu_device_index {
+            if encoder.ends_with("_nvenc") {
+                args.push("-gpu".into());
+                args.push(device.to_string().into());
+            }
+        }
+        if let Some(bitrate) = &rendition.video_bitrate {
+            args.push("-b:v".into());
+            args.push(bitrate.as_str().into());
+        }
+    }
+    // VFR sources are forced to a constant rate when re-encoding (VFR
+    // breaks HLS segment timing); stream copies keep their timestamps.
+    if metadata.variable_frame_rate && !stream_copy {
+        args.push("-vsync".into());
+        args.push("cfr".into());
+        if let Some(fps) = metadata.average_frame_rate {
+            args.push("-r".into());
+            args.push(format!("{fps:.3}").into());
+        }
+    }
+    // A fast remux copies AAC audio as well; any other codec (or a
+    // requested downmix or sync correction) still re-encodes the audio to
+    // AAC.
+    let downmix = settings.downmix_to_stereo && source_has_surround_audio(metadata);
+    if settings.audio_strategy == AudioStrategy::SharedGroup {
+        // The shared audio rendition carries the sound; variants stay
+        // video-only and the master's EXT-X-MEDIA points players at it.
+        args.push("-an".into());
+    } else if stream_copy
+        && metadata.audio_codec.as_deref() == Some("aac")
+        && !downmix
+        && audio_offset_ms.is_none()
+    {
+        args.push("-c:a".into());
+        args.push("copy".into());
+    } else {
+        // Sync correction: positive offsets pad silence in front of the
+        // audio, negative ones trim its head — both through filters, so a
+        // single input serves video and audio alike.
+        if let Some(offset) = audio_offset_ms {
+            args.push("-af".into());
+            if offset > 0 {
+                args.push(format!("adelay={offset}:all=1").into());
+            } else {
+                args.push(
+                    format!(
+                        "atrim=start={:.3},asetpts=PTS-STARTPTS",
+                        -offset as f64 / 1000.0
+                    )
+                    .into(),
+                );
+            }
+        }
+        for s in ["-c:a", "aac", "-b:a", "128k"] {
+            args.push(s.into());
+        }
+        // Surround sources get folded down to stereo when asked; ffmpeg's
+        // default -ac 2 downmix handles the channel coefficients. Stereo and
+        // mono sources pass through untouched.
+        if downmix {
+            args.push("-ac".into());
+            args.push("2".into());
+        }
+    }
+    // Drop container metadata and chapters when asked. Stream-level
+    // language tags aren't needed in the output: the master playlist's
+    // audio group is built from probing the source, not the segments.
+    if settings.strip_metadata {
+        for s in ["-map_metadata", "-1", "-map_chapters", "-1"] {
+            args.push(s.into());
+        }
+    }
+    // Reproducible output: one encoder thread (frame threading reorders
+    // nondeterministically) and bitexact muxing/coding, at a large speed
+    // cost.
+    if settings.deterministic {
+        for s in ["-threads", "1", "-flags", "+bitexact"] {
+            args.push(s.into());
+        }
+    } else if let Some(threads) = settings.ffmpeg_threads_per_job {
+        // Caps encoder threads so parallel CPU jobs don't oversubscribe
+        // the machine; deterministic mode already pins a single thread.
+        args.push("-threads".into());
+        args.push(threads.to_string().into());
+    }
+    for s in ["-f", "hls", "-hls_time"] {
+        args.push(s.into());
+    }
+    args.push(settings.segment_duration.to_string().into());
+    args.push("-hls_playlist_type".into());
+    args.push(
+        match settings.hls_playlist_type {
+            HlsPlaylistType::Vod => "vod",
+            HlsPlaylistType::Event => "event",
+        }
+        .into(),
+    );
+    if settings.hls_segment_type == HlsSegmentType::Fmp4 {
+        args.push("-hls_segment_type".into());
+        args.push("fmp4".into());
+    }
+    if let Some(resume) = resume {
+        args.push("-start_number".into());
+        args.push(resume.next_segment.to_string().into());
+    }
+    let mut hls_flags = Vec::new();
+    if resume.is_some() {
+        hls_flags.push("append_list");
+    }
+    if settings.independent_segments {
+        hls_flags.push("independent_segments");
+    }
+    if settings.hls_single_file {
+        hls_flags.push("single_file");
+    }
+    if !hls_flags.is_empty() {
+        args.push("-hls_flags".into());
+        args.push(hls_flags.join(",").into());
+    }
+    if let Some(key_info) = key_info {
+        args.push("-hls_key_info_file".into());
+        args.push(key_info.into());
+    }
+    args.push("-hls_segment_filename".into());
+    args.push(segment_pattern.into());
+    args.push(playlist.into());
+    args
+}
+
+/// One rendition of a dry-run plan: the exact command that would run and
+/// where its output would land in R2.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedRendition {
+    pub name: String,
+    pub ffmpeg_command: String,
+    pub estimated_bytes: u64,
+    pub target_playlist_key: String,
+    pub segment_key_pattern: String,
+}
+
+/// Everything a dry run reveals about one input, without touching ffmpeg or
+/// the network beyond the probe.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionPlan {
+    pub movie_id: String,
+    pub input_path: PathBuf,
+    pub metadata: VideoMetadata,
+    pub master_playlist_key: String,
+    pub renditions: Vec<PlannedRendition>,
+    pub estimated_total_bytes: u64,
+}
+
+/// Build the plan `convert` would execute for this input: rendition ladder,
+/// exact ffmpeg argv, size estimates and target object keys.
+pub async fn plan_conversion(
+    settings: &Settings,
+    movie_id: &str,
+    input: &Path,
+) -> Result<ConversionPlan> {
+    let metadata = oriented_metadata(settings, probe(input).await?);
+    let out_dir = settings.output_dir.join(movie_id);
+    // A dry run can't trial-initialize encoders, so assume the chain's first
+    // choice; convert() itself still falls back at execution time.
+    let encoder = settings
+        .encoder_fallback_chain
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "libx264".into());
+
+    let mut renditions = Vec::new();
+    let mut estimated_total_bytes = 0;
+    for rendition in plan_renditions(&metadata) {
+        let rendition_dir = out_dir.join(&rendition.name);
+        let args = build_ffmpeg_args(
+            settings,
+            input,
+            &metadata,
+            &rendition,
+            rendition_encoder(settings, &rendition, &encoder),
+            &rendition_dir,
+            None,
+            None,
+            // Auto detection is skipped in dry runs; only an explicit On
+            // shows up in the planned command.
+            settings.fix_timestamps == TimestampFix::On,
+            None,
+            None,
+        );
+        let ffmpeg_command = std::iter::once("ffmpeg".to_string())
+            .chain(args.iter().map(|a| a.to_string_lossy().into_owned()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let estimated_bytes = estimate_rendition_bytes(&metadata, &rendition);
+        estimated_total_bytes += estimated_bytes;
+        renditions.push(PlannedRendition {
+            target_playlist_key: format!("hls/{movie_id}/{}/playlist.m3u8", rendition.name),
+            segment_key_pattern: if settings.hls_single_file {
+                format!("hls/{movie_id}/{}/media.ts", rendition.name)
+            } else {
+                format!(
+                    "hls/{movie_id}/{}/segment_*.{}",
+                    rendition.name,
+                    segment_extension(settings.hls_segment_type)
+                )
+            },
+            name: rendition.name,
+            ffmpeg_command,
+            estimated_bytes,
+        });
+    }
+
+    Ok(ConversionPlan {
+        movie_id: movie_id.to_string(),
+        input_path: input.to_path_buf(),
+        metadata,
+        master_playlist_key: format!("hls/{movie_id}/playlist.m3u8"),
+        renditions,
+        estimated_total_bytes,
+    })
+}
+
+/// The exact ffmpeg argv for one rendition of a planned conversion.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedCommand {
+    pub rendition: String,
+    pub argv: Vec<String>,
+}
+
+/// Return the full argv `convert_video` would execute for this input, one
+/// command per rendition, without running anything — for the dry-run view
+/// and for advanced users who want to inspect or run it externally. Built
+/// by the same [`build_ffmpeg_args`] the execution path uses, so it cannot
+/// drift from what actually runs (minus the progress plumbing
+/// `encode_rendition` splices in).
+#[tauri::command]
+pub async fn build_ffmpeg_command(
+    store: State<'_, SettingsStore>,
+    movie_id: String,
+    input: PathBuf,
+) -> Result<Vec<PlannedCommand>> {
+    let settings = store.get();
+    let metadata = oriented_metadata(&settings, probe(&input).await?);
+    let out_dir = settings.output_dir.join(&movie_id);
+    let encoder = settings
+        .encoder_fallback_chain
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "libx264".into());
+
+    let mut commands = Vec::new();
+    for rendition in plan_renditions(&metadata) {
+        let args = build_ffmpeg_args(
+            &settings,
+            &input,
+            &metadata,
+            &rendition,
+            rendition_encoder(&settings, &rendition, &encoder),
+            &out_dir.join(&rendition.name),
+            None,
+            None,
+            settings.fix_timestamps == TimestampFix::On,
+            None,
+            None,
+        );
+        commands.push(PlannedCommand {
+            rendition: rendition.name,
+            argv: std::iter::once("ffmpeg".to_string())
+                .chain(args.iter().map(|a| a.to_string_lossy().into_owned()))
+                .collect(),
+        });
+    }
+    Ok(commands)
+}
+
+/// Cap on one `ffmpeg-log` event and the minimum interval between events;
+/// lines beyond the cap within an interval are dropped (and counted) so a
+/// chatty encode can't flood the IPC bridge.
+const LOG_BATCH_LINES: usize = 50;
+const LOG_BATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// A batch of raw ffmpeg stderr, emitted on `ffmpeg-log` while a verbose
+/// conversion runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct FfmpegLog {
+    pub movie_id: String,
+    pub rendition: String,
+    pub lines: Vec<String>,
+    /// Lines the rate limit dropped since the previous batch.
+    pub dropped: usize,
+}
+
+/// Run ffmpeg for a single rendition, streaming progress events as segments
+/// are written.
+#[allow(clippy::too_many_arguments)]
+async fn encode_rendition(
+    app: &AppHandle,
+    settings: &Settings,
+    movie_id: &str,
+    input: &Path,
+    metadata: &VideoMetadata,
+    rendition: &Rendition,
+    encoder: &str,
+    out_dir: &Path,
+    burn_filter: Option<&str>,
+    key_info: Option<&Path>,
+    repair_timestamps: bool,
+    audio_offset_ms: Option<i64>,
+    verbose: bool,
+) -> Result<()> {
+    tokio::fs::create_dir_all(out_dir).await?;
+
+    // Pick up where a crashed run stopped instead of redoing the whole
+    // rendition; a rendition whose playlist already ends in ENDLIST is done.
+    let resume = prepare_resume(out_dir).await?;
+    if let Some(resume) = &resume {
+        if resume.complete {
+            return Ok(());
+        }
+        let _ = app.emit(
+            "conversion-resumed",
+            ConversionProgress {
+                movie_id: movie_id.to_string(),
+                rendition: rendition.name.clone(),
+                seconds_done: resume.seek_seconds,
+                total_seconds: metadata.duration_seconds,
+            },
+        );
+    }
+
+    let mut args = build_ffmpeg_args(
+        settings,
+        input,
+        metadata,
+        rendition,
+        encoder,
+        out_dir,
+        burn_filter,
+        key_info,
+        repair_timestamps,
+        audio_offset_ms,
+        resume.as_ref(),
+    );
+    // The playlist path must stay the final argument; splice the progress
+    // flags in just before it.
+    let playlist = args.pop().expect("argv always ends with the playlist");
+    args.push("-progress".into());
+    args.push("pipe:1".into());
+    args.push("-nostats".into());
+    args.push(playlist);
+
+    let argv: Vec<String> = std::iter::once("ffmpeg".to_string())
+        .chain(args.iter().map(|a| a.to_string_lossy().into_owned()))
+        .collect();
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(&args);
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    let mut child = cmd
+        .spawn()
+        .map_err(spawn_error)?;
+
+    // Collect the tail of stderr so a failure surfaces the real diagnostics
+    // instead of just an exit code. Ring-buffered to bound memory on chatty
+    // encodes.
+    const STDERR_TAIL_LINES: usize = 200;
+    let stderr = child.stderr.take();
+    // The live console is a second consumer of the same lines: with verbose
+    // off the sink is None and the loop below does nothing extra.
+    let log_sink = verbose.then(|| (app.clone(), movie_id.to_string(), rendition.name.clone()));
+    let stderr_task = tokio::spawn(async move {
+        let mut tail = std::collections::VecDeque::with_capacity(STDERR_TAIL_LINES);
+        let mut pending: Vec<String> = Vec::new();
+        let mut dropped = 0usize;
+        let mut last_emit = std::time::Instant::now();
+        if let Some(stderr) = stderr {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Some((app, movie_id, rendition)) = &log_sink {
+                    if pending.len() == LOG_BATCH_LINES {
+                        pending.remove(0);
+                        dropped += 1;
+                    }
+                    pending.push(line.clone());
+                    if last_emit.elapsed() >= LOG_BATCH_INTERVAL {
+                        let _ = app.emit(
+                            "ffmpeg-log",
+                            FfmpegLog {
+                                movie_id: movie_id.clone(),
+                                rendition: rendition.clone(),
+                                lines: std::mem::take(&mut pending),
+                                dropped,
+                            },
+                        );
+                        dropped = 0;
+                        last_emit = std::time::Instant::now();
+                    }
+                }
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }
+        }
+        if let Some((app, movie_id, rendition)) = &log_sink {
+            if !pending.is_empty() || dropped > 0 {
+                let _ = app.emit(
+                    "ffmpeg-log",
+                    FfmpegLog {
+                        movie_id: movie_id.clone(),
+                        rendition: rendition.clone(),
+                        lines: pending,
+                        dropped,
+                    },
+                );
+            }
+        }
+        tail
+    });
+
+    // Watchdog: with a timeout configured, kill ffmpeg when the rendition
+    // exceeds the limit outright, or when the progress stream goes silent
+    // for a grace period (a healthy encode reports every second or so; a
+    // hung one reports nothing). The watchdog needs no separate task — the
+    // progress read loop doubles as it, so normal completion just falls out
+    // of the loop.
+    let timeout = settings
+        .conversion_timeout_secs
+        .map(|s| std::time::Duration::from_secs(s as u64));
+    const NO_PROGRESS_GRACE: std::time::Duration = std::time::Duration::from_secs(120);
+    let started = std::time::Instant::now();
+    let mut timed_out = None;
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        loop {
+            let next = match timeout {
+                Some(limit) => {
+                    match tokio::time::timeout(NO_PROGRESS_GRACE.min(limit), lines.next_line())
+                        .await
+                    {
+                        Ok(next) => next,
+                        Err(_) => {
+                            timed_out = Some("made no progress".to_string());
+                            break;
+                        }
+                    }
+                }
+                None => lines.next_line().await,
+            };
+            let line = match next {
+                Ok(Some(line)) => line,
+                _ => break,
+            };
+            if let Some(limit) = timeout {
+                if started.elapsed() > limit {
+                    timed_out = Some(format!("exceeded {}s", limit.as_secs()));
+                    break;
+                }
+            }
+            // `-progress` emits key=value lines; out_time_us tracks position.
+            if let Some(us) = line.strip_prefix("out_time_us=") {
+                if let Ok(us) = us.parse::<i64>() {
+                    let _ = app.emit(
+                        "conversion-progress",
+                        ConversionProgress {
+                            movie_id: movie_id.to_string(),
+                            rendition: rendition.name.clone(),
+                            seconds_done: us as f64 / 1_000_000.0,
+                            total_seconds: metadata.duration_seconds,
+                        },
+                    );
+                }
+            }
+        }
+    }
+    if let Some(reason) = timed_out {
+        let _ = child.kill().await;
+        let _ = child.wait().await;
+        return Err(AppError::Timeout(format!(
+            "ffmpeg for rendition {} {reason}; killed by the conversion watchdog",
+            rendition.name
+        )));
+    }
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| AppError::Ffmpeg(format!("ffmpeg wait failed: {e}")))?;
+    let stderr_tail: Vec<String> = stderr_task.await.unwrap_or_default().into();
+    if !status.success() {
+        return Err(AppError::FfmpegFailed {
+            status: status.to_string(),
+            argv,
+            stderr_tail,
+        });
+    }
+    Ok(())
+}
+
+/// Write the master playlist that references each rendition playlist.
+///
+/// Audio stays muxed into the variants, but each track is still declared as
+/// `#EXT-X-MEDIA` (no URI) so players can label and pick languages.
+fn write_master_playlist(
+    settings: &Settings,
+    out_dir: &Path,
+    renditions: &[(Rendition, VideoMetadata)],
+    audio_tracks: &[AudioTrack],
+    audio_only: bool,
+) -> Result<()> {
+    let master = master_playlist_contents(settings, renditions, audio_tracks, audio_only);
+    std::fs::write(out_dir.join("playlist.m3u8"), master)?;
+    Ok(())
+}
+
+/// The master playlist text, kept separate from the file write so the
+/// header handling is testable.
+fn master_playlist_contents(
+    settings: &Settings,
+    renditions: &[(Rendition, VideoMetadata)],
+    audio_tracks: &[AudioTrack],
+    audio_only: bool,
+) -> String {
+    let mut master = format!("#EXTM3U\n#EXT-X-VERSION:{}\n", settings.hls_version);
+    if settings.independent_segments {
+        master.push_str("#EXT-X-INDEPENDENT-SEGMENTS\n");
+    }
+    if settings.audio_strategy == AudioStrategy::SharedGroup && !audio_tracks.is_empty() {
+        // Variants are video-only; the one encoded audio rendition (the
+        // main track) is the group's single playable entry, so declaring
+        // the other source tracks URI-less would promise audio that
+        // doesn't exist.
+        let track = &audio_tracks[0];
+        let name = track.title.clone().unwrap_or_else(|| "Audio".to_string());
+        master.push_str(&format!(
+            "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"audio\",NAME=\"{name}\",LANGUAGE=\"{}\",DEFAULT=YES,URI=\"{AUDIO_ONLY_RENDITION}/playlist.m3u8\"\n",
+            track.language
+        ));
+    } else {
+        for (i, track) in audio_tracks.iter().enumerate() {
+            let name = track
+                .title
+                .clone()
+                .unwrap_or_else(|| format!("Audio {}", i + 1));
+            master.push_str(&format!(
+                "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"audio\",NAME=\"{name}\",LANGUAGE=\"{}\",DEFAULT={}\n",
+                track.language,
+                if track.default { "YES" } else { "NO" }
+            ));
+        }
+    }
+    let audio_attr = if audio_tracks.is_empty() {
+        String::new()
+    } else {
+        ",AUDIO=\"audio\"".to_string()
+    };
+    for (rendition, metadata) in renditions {
+        let bandwidth = metadata.bit_rate.unwrap_or(2_000_000);
+        master.push_str(&format!(
+            "#EXT-X-STREAM-INF:BANDWIDTH={bandwidth},RESOLUTION={}x{}{audio_attr}\n{}/playlist.m3u8\n",
+            metadata.width, metadata.height, rendition.name
+        ));
+    }
+    // Last, so players walking the list top-down prefer video variants.
+    if audio_only {
+        master.push_str(&format!(
+            "#EXT-X-STREAM-INF:BANDWIDTH={AUDIO_ONLY_BANDWIDTH},CODECS=\"mp4a.40.2\"{audio_attr}\n\
+             {AUDIO_ONLY_RENDITION}/playlist.m3u8\n"
+        ));
+    }
+    // URI-only entry, so its position doesn't affect variant selection.
+    if settings.generate_iframe_playlist {
+        if let Some((_, metadata)) = renditions.first() {
+            master.push_str(&format!(
+                "#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH={IFRAME_BANDWIDTH},RESOLUTION={}x{IFRAME_HEIGHT},URI=\"{IFRAME_RENDITION}/playlist.m3u8\"\n",
+                iframe_width(metadata)
+            ));
+        }
+    }
+    master
+}
+
+/// Width of the trick-play rendition: the source aspect ratio at
+/// `IFRAME_HEIGHT`, rounded down to even (matching the `-2` scale filter).
+fn iframe_width(metadata: &VideoMetadata) -> u32 {
+    if metadata.height == 0 {
+        return metadata.width;
+    }
+    (metadata.width * IFRAME_HEIGHT / metadata.height) & !1
+}
+
+/// Encode the I-frame-only trick-play playlist: only keyframes are decoded
+/// (`-skip_frame nokey`), scaled down and re-encoded all-intra so the hls
+/// muxer's `iframes_only` flag can tag the playlist `#EXT-X-I-FRAMES-ONLY`.
+/// Always libx264 — the stream is tiny, so hardware queueing isn't worth it.
+async fn encode_iframe_playlist(
+    settings: &Settings,
+    input: &Path,
+    out_dir: &Path,
+    key_info: Option<&Path>,
+) -> Result<()> {
+    tokio::fs::create_dir_all(out_dir).await?;
+    let mut args: Vec<std::ffi::OsString> =
+        vec!["-y".into(), "-skip_frame".into(), "nokey".into(), "-i".into(), input.into()];
+    for s in ["-map", "0:v:0", "-an", "-sn", "-vsync", "vfr"] {
+        args.push(s.into());
+    }
+    args.push("-vf".into());
+    args.push(format!("scale=-2:{IFRAME_HEIGHT}").into());
+    for s in ["-c:v", "libx264", "-b:v", IFRAME_BITRATE, "-g", "1"] {
+        args.push(s.into());
+    }
+    for s in ["-f", "hls", "-hls_time"] {
+        args.push(s.into());
+    }
+    args.push(settings.segment_duration.to_string().into());
+    args.push("-hls_playlist_type".into());
+    args.push(
+        match settings.hls_playlist_type {
+            HlsPlaylistType::Vod => "vod",
+            HlsPlaylistType::Event => "event",
+        }
+        .into(),
+    );
+    if settings.hls_segment_type == HlsSegmentType::Fmp4 {
+        args.push("-hls_segment_type".into());
+        args.push("fmp4".into());
+    }
+    args.push("-hls_flags".into());
+    args.push(if settings.hls_single_file {
+        "iframes_only+single_file".into()
+    } else {
+        "iframes_only".into()
+    });
+    if let Some(key_info) = key_info {
+        args.push("-hls_key_info_file".into());
+        args.push(key_info.into());
+    }
+    args.push("-hls_segment_filename".into());
+    args.push(if settings.hls_single_file {
+        out_dir.join("media.ts").into()
+    } else {
+        out_dir
+            .join(format!(
+                "segment_%03d.{}",
+                segment_extension(settings.hls_segment_type)
+            ))
+            .into()
+    });
+    args.push(out_dir.join("playlist.m3u8").into());
+
+    let output = Command::new("ffmpeg")
+        .args(&args)
+        .output()
+        .await
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "I-frame playlist exited with {}",
+            output.status
+        )));
+    }
+    Ok(())
+}
+
+/// Encode the audio-only fallback rendition: the main audio track alone,
+/// re-encoded to low-bitrate stereo AAC and segmented like the video
+/// renditions (including encryption when configured).
+async fn encode_audio_only(
+    settings: &Settings,
+    input: &Path,
+    out_dir: &Path,
+    key_info: Option<&Path>,
+) -> Result<()> {
+    tokio::fs::create_dir_all(out_dir).await?;
+    let mut args: Vec<std::ffi::OsString> = vec!["-y".into(), "-i".into(), input.into()];
+    for s in ["-map", "0:a:0", "-vn", "-sn", "-c:a", "aac", "-b:a", AUDIO_ONLY_BITRATE, "-ac", "2"]
+    {
+        args.push(s.into());
+    }
+    for s in ["-f", "hls", "-hls_time"] {
+        args.push(s.into());
+    }
+    args.push(settings.segment_duration.to_string().into());
+    args.push("-hls_playlist_type".into());
+    args.push(
+        match settings.hls_playlist_type {
+            HlsPlaylistType::Vod => "vod",
+            HlsPlaylistType::Event => "event",
+        }
+        .into(),
+    );
+    if settings.hls_segment_type == HlsSegmentType::Fmp4 {
+        args.push("-hls_segment_type".into());
+        args.push("fmp4".into());
+    }
+    if settings.hls_single_file {
+        args.push("-hls_flags".into());
+        args.push("single_file".into());
+    }
+    if let Some(key_info) = key_info {
+        args.push("-hls_key_info_file".into());
+        args.push(key_info.into());
+    }
+    args.push("-hls_segment_filename".into());
+    args.push(if settings.hls_single_file {
+        out_dir.join("media.ts").into()
+    } else {
+        out_dir
+            .join(format!(
+                "segment_%03d.{}",
+                segment_extension(settings.hls_segment_type)
+            ))
+            .into()
+    });
+    args.push(out_dir.join("playlist.m3u8").into());
+
+    let output = Command::new("ffmpeg")
+        .args(&args)
+        .output()
+        .await
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "audio-only rendition exited with {}",
+            output.status
+        )));
+    }
+    Ok(())
+}
+
+/// Remux the source into `original.mp4` under the output dir, with the
+/// moov atom moved to the front when `faststart_original` is set so the
+/// file progressive-downloads. Returns None (with no error) when the
+/// source codec can't live in an MP4 container without re-encoding.
+async fn keep_original_copy(
+    settings: &Settings,
+    input: &Path,
+    metadata: &VideoMetadata,
+    out_dir: &Path,
+) -> Result<Option<PathBuf>> {
+    if !matches!(metadata.video_codec.as_str(), "h264" | "hevc") {
+        return Ok(None);
+    }
+    let target = out_dir.join("original.mp4");
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-y").arg("-i").arg(input).args(["-c", "copy"]);
+    if settings.faststart_original {
+        cmd.args(["-movflags", "+faststart"]);
+    }
+    let output = cmd
+        .arg(&target)
+        .output()
+        .await
+        .map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "remuxing the original MP4 exited with {}",
+            output.status
+        )));
+    }
+    Ok(Some(target))
+}
+
+/// What one rendition actually produced on disk.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenditionOutput {
+    pub name: String,
+    pub height: u32,
+    pub playlist_path: PathBuf,
+    pub segment_count: usize,
+    pub bytes: u64,
+    /// The source stream was segmented with `-c copy` rather than
+    /// re-encoded (see `fast_remux_if_compatible`).
+    pub remuxed: bool,
+}
+
+/// Outcome of a conversion, returned as a typed payload so the frontend
+/// can sanity-check the output (nonzero segments, size vs. the earlier
+/// estimate) before upload. `job_id` is filled in by the queue; a direct
+/// `convert_video` call leaves it None.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionResult {
+    pub job_id: Option<u64>,
+    pub output_dir: PathBuf,
+    pub master_playlist: PathBuf,
+    pub renditions: Vec<RenditionOutput>,
+    pub encoder_used: String,
+    pub duration_seconds: f64,
+    /// Source frame rate, when the probe reported one; queue throughput
+    /// stats derive encoded-frames-per-second from it.
+    pub average_frame_rate: Option<f64>,
+    pub total_bytes: u64,
+    /// The progressive-download copy of the source, when `keep_original_mp4`
+    /// is set and the source could be remuxed.
+    pub original_mp4: Option<PathBuf>,
+    /// Timestamps were regenerated (`-fflags +genpts`) because the source's
+    /// DTS/PTS were broken or `fix_timestamps` forced it.
+    pub timestamps_repaired: bool,
+    /// Audio sync correction that was applied, when one was requested.
+    pub audio_offset_ms: Option<i64>,
+    /// Non-fatal notes worth surfacing, e.g. an encoder fallback.
+    pub warnings: Vec<String>,
+}
+
+/// Recursively apply `mode` to every file a conversion wrote. Directories
+/// keep their default permissions; only the playlists and segments matter
+/// to whatever local service reads them.
+#[cfg(unix)]
+fn chmod_tree(dir: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            chmod_tree(&path, mode)?;
+        } else {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))?;
+        }
+    }
+    Ok(())
+}
+
+/// Tally a finished rendition by walking its playlist and stat-ing each
+/// referenced segment.
+fn rendition_output(
+    rendition_dir: &Path,
+    name: &str,
+    height: u32,
+    remuxed: bool,
+) -> Result<RenditionOutput> {
+    let playlist_path = rendition_dir.join("playlist.m3u8");
+    let playlist = std::fs::read_to_string(&playlist_path)?;
+    let mut segment_count = 0;
+    let mut bytes = 0;
+    for line in playlist.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        segment_count += 1;
+        bytes += std::fs::metadata(rendition_dir.join(line))?.len();
+    }
+    Ok(RenditionOutput {
+        name: name.to_string(),
+        height,
+        playlist_path,
+        segment_count,
+        bytes,
+        remuxed,
+    })
+}
+
+/// The encoder a specific rendition runs with: its configured override if
+/// one exists, otherwise the fallback-chain selection.
+fn rendition_encoder<'a>(settings: &'a Settings, rendition: &Rendition, selected: &'a str) -> &'a str {
+    settings
+        .rendition_encoders
+        .get(&rendition.name)
+        .map(String::as_str)
+        .unwrap_or(selected)
+}
+
+/// Convert `input` into the HLS layout the site serves:
+/// `{output_dir}/{movie_id}/{rendition}/segment_*.ts` plus playlists.
+/// The output folder in the result is ready for `upload_folder_to_r2`.
+pub async fn convert(
+    app: &AppHandle,
+    settings: &Settings,
+    movie_id: &str,
+    input: &Path,
+    burn_subtitles: Option<u32>,
+    audio_offset_ms: Option<i64>,
+    verbose: bool,
+) -> Result<ConversionResult> {
+    // Sync corrections beyond a minute are almost certainly a typo'd unit,
+    // and a zero offset is no correction at all.
+    let audio_offset_ms = audio_offset_ms.filter(|offset| *offset != 0);
+    if let Some(offset) = audio_offset_ms {
+        if offset.abs() > MAX_AUDIO_OFFSET_MS {
+            return Err(AppError::InvalidInput(format!(
+                "audio_offset_ms {offset} is outside ±{MAX_AUDIO_OFFSET_MS} ms"
+            )));
+        }
+    }
+    preflight_conversion(input, &settings.output_dir)?;
+    let encoder = select_encoder(app, settings).await?;
+    let burn_filter = match burn_subtitles {
+        Some(index) => Some(burn_subtitle_filter(input, index).await?),
+        None => None,
+    };
+    // A stale device pin (GPU removed, different machine) would otherwise
+    // surface as an opaque encoder init failure mid-conversion.
+    if let Some(device) = settings.gpu_device_index {
+        let uses_hardware = is_hardware_encoder(&encoder)
+            || settings.rendition_encoders.values().any(|e| is_hardware_encoder(e));
+        if uses_hardware {
+            let detected = crate::gpu::detect().await;
+            if !detected.gpus.iter().any(|g| g.index == device) {
+                return Err(AppError::Ffmpeg(format!(
+                    "gpu_device_index {device} is not among the {} detected GPU(s)",
+                    detected.gpus.len()
+                )));
+            }
+        }
+    }
+    // Per-rendition overrides bypass the fallback chain, so check them up
+    // front rather than failing mid-conversion with half the ladder done.
+    if !settings.rendition_encoders.is_empty() {
+        let available = available_encoders().await?;
+        for (rendition, override_encoder) in &settings.rendition_encoders {
+            if !available.contains(override_encoder) {
+                return Err(AppError::Ffmpeg(format!(
+                    "encoder {override_encoder} configured for rendition {rendition} is not available"
+                )));
+            }
+        }
+    }
+    let mut result = convert_with_encoder(
+        app,
+        settings,
+        movie_id,
+        input,
+        &encoder,
+        burn_filter.as_deref(),
+        audio_offset_ms,
+        verbose,
+    )
+    .await?;
+    if let Some(preferred) = settings.encoder_fallback_chain.first() {
+        if preferred != &encoder {
+            result
+                .warnings
+                .push(format!("fell back from {preferred} to {encoder}"));
+        }
+    }
+    Ok(result)
+}
+
+/// The conversion itself, with the default encoder already chosen.
+/// Per-rendition overrides from `rendition_encoders` are applied here, and
+/// the GPU session permit is held per rendition: only renditions actually
+/// running on a hardware encoder count against the GPU limit, so a CPU
+/// 480p pass doesn't block another job's nvenc session.
+#[allow(clippy::too_many_arguments)]
+pub async fn convert_with_encoder(
+    app: &AppHandle,
+    settings: &Settings,
+    movie_id: &str,
+    input: &Path,
+    encoder: &str,
+    burn_filter: Option<&str>,
+    audio_offset_ms: Option<i64>,
+    verbose: bool,
+) -> Result<ConversionResult> {
+    let metadata = oriented_metadata(settings, probe(input).await?);
+    let out_dir = settings.output_dir.join(movie_id);
+    tokio::fs::create_dir_all(&out_dir).await?;
+    let renditions = plan_renditions(&metadata);
+    let key_info = write_encryption_key(settings, movie_id, &out_dir).await?;
+    let repair_timestamps = match settings.fix_timestamps {
+        TimestampFix::On => true,
+        TimestampFix::Off => false,
+        TimestampFix::Auto => detect_broken_timestamps(input).await?,
+    };
+    let mut produced = Vec::new();
+    let mut outputs = Vec::new();
+    for rendition in &renditions {
+        let rendition_dir = out_dir.join(&rendition.name);
+        let encoder = rendition_encoder(settings, rendition, encoder);
+        let _gpu_permit = if is_hardware_encoder(encoder) {
+            Some(app.state::<crate::gpu::GpuLimiter>().acquire().await)
+        } else {
+            None
+        };
+        encode_rendition(
+            app,
+            settings,
+            movie_id,
+            input,
+            &metadata,
+            rendition,
+            encoder,
+            &rendition_dir,
+            burn_filter,
+            key_info.as_deref(),
+            repair_timestamps,
+            audio_offset_ms,
+            verbose,
+        )
+        .await?;
+        let height = rendition.target_height.unwrap_or(metadata.height);
+        outputs.push(rendition_output(
+            &rendition_dir,
+            &rendition.name,
+            height,
+            is_fast_remux(settings, &metadata, rendition, burn_filter),
+        )?);
+        let width = if metadata.height > 0 {
+            metadata.width * height / metadata.height
+        } else {
+            metadata.width
+        };
+        produced.push((
+            rendition.clone(),
+            VideoMetadata {
+                width,
+                height,
+                ..metadata.clone()
+            },
+        ));
+    }
+    let audio_only = settings.audio_only_rendition && !metadata.audio_tracks.is_empty();
+    // A shared audio group needs the audio rendition even when the
+    // audio-only fallback wasn't asked for — it's the only audio there is.
+    let shared_audio = settings.audio_strategy == AudioStrategy::SharedGroup
+        && !metadata.audio_tracks.is_empty();
+    if audio_only || shared_audio {
+        let audio_dir = out_dir.join(AUDIO_ONLY_RENDITION);
+        encode_audio_only(settings, input, &audio_dir, key_info.as_deref()).await?;
+        outputs.push(rendition_output(&audio_dir, AUDIO_ONLY_RENDITION, 0, false)?);
+    }
+    if settings.generate_iframe_playlist {
+        let iframe_dir = out_dir.join(IFRAME_RENDITION);
+        encode_iframe_playlist(settings, input, &iframe_dir, key_info.as_deref()).await?;
+        outputs.push(rendition_output(&iframe_dir, IFRAME_RENDITION, IFRAME_HEIGHT, false)?);
+    }
+    write_master_playlist(settings, &out_dir, &produced, &metadata.audio_tracks, audio_only)?;
+    let mut warnings = Vec::new();
+    let original_mp4 = if settings.keep_original_mp4 {
+        let copy = keep_original_copy(settings, input, &metadata, &out_dir).await?;
+        if copy.is_none() {
+            warnings.push(format!(
+                "source codec {} cannot be remuxed into MP4; original not kept",
+                metadata.video_codec
+            ));
+        }
+        copy
+    } else {
+        None
+    };
+    #[cfg(unix)]
+    if let Some(mode) = settings.output_file_mode {
+        chmod_tree(&out_dir, mode)?;
+    }
+    if burn_filter.is_some() {
+        warnings.push(
+            "subtitles burned in: every rendition was re-encoded, including ones stream copy \
+             could otherwise have handled"
+                .into(),
+        );
+    }
+    if settings.respect_rotation {
+        if let Some(rotation) = metadata.rotation {
+            warnings.push(format!(
+                "source carries {rotation}° rotation metadata; baked into the output pixels"
+            ));
+        }
+    }
+    if let Some(offset) = audio_offset_ms {
+        warnings.push(format!("audio shifted by {offset} ms to correct sync"));
+    }
+    if repair_timestamps {
+        warnings.push(match settings.fix_timestamps {
+            TimestampFix::Auto => {
+                "non-monotonic timestamps detected; regenerated PTS/DTS with -fflags +genpts".into()
+            }
+            _ => "timestamp repair forced on; regenerated PTS/DTS with -fflags +genpts".to_string(),
+        });
+    }
+    if metadata.variable_frame_rate {
+        warnings.push(format!(
+            "variable frame rate source: forced constant frame rate{}",
+            metadata
+                .average_frame_rate
+                .map(|fps| format!(" at {fps:.3} fps"))
+                .unwrap_or_default()
+        ));
+    }
+    Ok(ConversionResult {
+        job_id: None,
+        master_playlist: out_dir.join("playlist.m3u8"),
+        output_dir: out_dir,
+        total_bytes: outputs.iter().map(|r| r.bytes).sum(),
+        renditions: outputs,
+        encoder_used: encoder.to_string(),
+        duration_seconds: metadata.duration_seconds,
+        average_frame_rate: metadata.average_frame_rate,
+        original_mp4,
+        timestamps_repaired: repair_timestamps,
+        audio_offset_ms,
+        warnings,
+    })
+}
+
+#[tauri::command]
+pub async fn get_video_metadata(input: PathBuf) -> Result<VideoMetadata> {
+    let input = crate::paths::normalize(&input.to_string_lossy())?;
+    probe(&input).await
+}
+
+/// One entry of a batch probe; the error is carried inline so a single bad
+/// file doesn't abort probing the rest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub path: PathBuf,
+    pub metadata: Option<VideoMetadata>,
+    pub error: Option<String>,
+}
+
+/// Probe a whole batch concurrently (bounded) so the UI can show durations
+/// and resolutions up front without waiting on serial ffprobe runs.
+#[tauri::command]
+pub async fn probe_videos(paths: Vec<PathBuf>) -> Vec<ProbeResult> {
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
+
+    const MAX_CONCURRENT_PROBES: usize = 8;
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_PROBES));
+    let mut handles = Vec::with_capacity(paths.len());
+    for path in paths {
+        let permits = permits.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = permits.acquire().await.expect("semaphore never closed");
+            match probe(&path).await {
+                Ok(metadata) => ProbeResult {
+                    path,
+                    metadata: Some(metadata),
+                    error: None,
+                },
+                Err(e) => ProbeResult {
+                    path,
+                    metadata: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        }));
+    }
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.expect("probe task panicked"));
+    }
+    results
+}
+
+#[tauri::command]
+pub async fn convert_video(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    movie_id: String,
+    input: PathBuf,
+    burn_subtitles: Option<u32>,
+    audio_offset_ms: Option<i64>,
+    verbose: Option<bool>,
+) -> Result<ConversionResult> {
+    let settings = store.get();
+    let input = crate::paths::normalize(&input.to_string_lossy())?;
+    convert(
+        &app,
+        &settings,
+        &movie_id,
+        &input,
+        burn_subtitles,
+        audio_offset_ms,
+        verbose.unwrap_or(false),
+    )
+    .await
+}
+
+/// One rendition to add to an already-published package.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResolutionProfile {
+    pub name: String,
+    pub height: u32,
+    /// `-b:v` for the encode; None falls back to the source bitrate.
+    pub video_bitrate: Option<String>,
+}
+
+/// Outcome of [`add_renditions`].
+#[derive(Debug, Clone, Serialize)]
+pub struct AddedRenditions {
+    pub master_key: String,
+    pub added: Vec<String>,
+    /// Requested renditions the master already listed, left untouched.
+    pub skipped_existing: Vec<String>,
+}
+
+/// Sum of the `#EXTINF` durations in a media playlist.
+fn playlist_total_duration(playlist: &str) -> f64 {
+    playlist
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("#EXTINF:"))
+        .filter_map(|l| l.split(',').next())
+        .filter_map(|d| d.trim().parse::<f64>().ok())
+        .sum()
+}
+
+/// Rendition names a master playlist references (`480p/playlist.m3u8` →
+/// `480p`).
+fn master_rendition_names(master: &str) -> Vec<String> {
+    master
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.strip_suffix("/playlist.m3u8"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Append one variant entry to an existing master playlist, preserving
+/// everything already in it.
+fn append_stream_inf(master: &str, name: &str, bandwidth: u64, width: u32, height: u32) -> String {
+    let mut master = master.trim_end().to_string();
+    master.push_str(&format!(
+        "\n#EXT-X-STREAM-INF:BANDWIDTH={bandwidth},RESOLUTION={width}x{height}\n{name}/playlist.m3u8\n"
+    ));
+    master
+}
+
+/// Encode only the renditions missing from an already-published package,
+/// upload them under its prefix, and extend the master playlist — adding
+/// 4K later shouldn't mean re-encoding the whole ladder. The source must
+/// match the published content: its duration is checked against an
+/// existing rendition playlist before anything is encoded.
+#[tauri::command]
+pub async fn add_renditions(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    source_path: PathBuf,
+    prefix: String,
+    new_renditions: Vec<ResolutionProfile>,
+) -> Result<AddedRenditions> {
+    if new_renditions.is_empty() {
+        return Err(AppError::InvalidInput("no renditions requested".into()));
+    }
+    let settings = store.get();
+    // An encrypted package was segmented with a key this machine may no
+    // longer have; a fresh key would make the new renditions unplayable
+    // alongside the old ones.
+    if settings.key_server_url_template.is_some() {
+        return Err(AppError::InvalidInput(
+            "add_renditions cannot extend an encrypted package; the original AES key is not \
+             guaranteed to be available"
+                .into(),
+        ));
+    }
+    let client = crate::r2::client(&settings)?;
+    let prefix = prefix.trim_end_matches('/').to_string();
+    let master_key = format!("{prefix}/playlist.m3u8");
+    let mut master = crate::r2::get_string(&client, &settings, &master_key).await?;
+    let existing = master_rendition_names(&master);
+
+    let metadata = oriented_metadata(&settings, probe(&source_path).await?);
+    if let Some(first) = existing.first() {
+        let media =
+            crate::r2::get_string(&client, &settings, &format!("{prefix}/{first}/playlist.m3u8"))
+                .await?;
+        let published = playlist_total_duration(&media);
+        let tolerance = (2.0 * settings.segment_duration as f64).max(published * 0.02);
+        if (published - metadata.duration_seconds).abs() > tolerance {
+            return Err(AppError::InvalidInput(format!(
+                "source runs {:.1}s but the published package runs {published:.1}s; \
+                 this does not look like the same content",
+                metadata.duration_seconds
+            )));
+        }
+    }
+
+    let movie_id = prefix.rsplit('/').next().unwrap_or(&prefix).to_string();
+    let encoder = select_encoder(&app, &settings).await?;
+    let out_dir = settings.output_dir.join(&movie_id);
+    let mut added = Vec::new();
+    let mut skipped_existing = Vec::new();
+    for profile in new_renditions {
+        if existing.contains(&profile.name) {
+            skipped_existing.push(profile.name);
+            continue;
+        }
+        let rendition = Rendition {
+            name: profile.name.clone(),
+            target_height: Some(profile.height),
+            video_bitrate: profile.video_bitrate.clone(),
+        };
+        let rendition_dir = out_dir.join(&rendition.name);
+        encode_rendition(
+            &app,
+            &settings,
+            &movie_id,
+            &source_path,
+            &metadata,
+            &rendition,
+            rendition_encoder(&settings, &rendition, &encoder),
+            &rendition_dir,
+            None,
+            None,
+            false,
+            None,
+            false,
+        )
+        .await?;
+        for (relative, absolute) in crate::r2::collect_files(&rendition_dir)? {
+            let key = format!(
+                "{prefix}/{}/{}",
+                rendition.name,
+                relative.to_string_lossy().replace('\\', "/")
+            );
+            crate::r2::upload_file_cancellable(
+                &app,
+                &client,
+                &settings,
+                &absolute,
+                &key,
+                &crate::r2::UploadOptions::default(),
+                None,
+            )
+            .await?;
+        }
+        let bandwidth = profile
+            .video_bitrate
+            .as_deref()
+            .and_then(parse_bitrate)
+            .or(metadata.bit_rate)
+            .unwrap_or(FALLBACK_VIDEO_BITRATE_BPS)
+            + AUDIO_BITRATE_BPS;
+        let width = if metadata.height > 0 {
+            metadata.width * profile.height / metadata.height
+        } else {
+            metadata.width
+        };
+        master = append_stream_inf(&master, &rendition.name, bandwidth, width, profile.height);
+        added.push(rendition.name);
+    }
+    if !added.is_empty() {
+        crate::r2::put_string(
+            &client,
+            &settings,
+            &master_key,
+            &master,
+            "application/vnd.apple.mpegurl",
+        )
+        .await?;
+    }
+    Ok(AddedRenditions {
+        master_key,
+        added,
+        skipped_existing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumes_partial_playlist_redoing_last_segment() {
+        // A run that died mid-way through segment 2: the playlist lists
+        // three segments but no ENDLIST.
+        let playlist = "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:6\n\
+                        #EXTINF:6.000000,\nsegment_000.ts\n\
+                        #EXTINF:6.000000,\nsegment_001.ts\n\
+                        #EXTINF:4.500000,\nsegment_002.ts\n";
+        let resume = resume_point(playlist);
+        assert!(!resume.complete);
+        assert_eq!(resume.next_segment, 2);
+        assert!((resume.seek_seconds - 12.0).abs() < f64::EPSILON);
+        assert_eq!(resume.dropped_segment.as_deref(), Some("segment_002.ts"));
+        assert!(!resume.truncated_playlist.contains("segment_002.ts"));
+        assert!(resume.truncated_playlist.contains("segment_001.ts"));
+    }
+
+    #[test]
+    fn finished_playlist_needs_no_redo() {
+        let playlist = "#EXTM3U\n#EXTINF:6.000000,\nsegment_000.ts\n#EXT-X-ENDLIST\n";
+        let resume = resume_point(playlist);
+        assert!(resume.complete);
+        assert!(resume.dropped_segment.is_none());
+    }
+
+    #[test]
+    fn header_only_playlist_restarts_from_scratch() {
+        let resume = resume_point("#EXTM3U\n#EXT-X-VERSION:3\n");
+        assert!(!resume.complete);
+        assert_eq!(resume.next_segment, 0);
+    }
+
+    #[test]
+    fn detects_variable_frame_rate_sources() {
+        // CFR: average and nominal agree.
+        assert!(!is_variable_frame_rate(Some("24000/1001"), Some("24000/1001")));
+        // VFR: average diverges from nominal.
+        assert!(is_variable_frame_rate(Some("18500/1000"), Some("30/1")));
+        // VFR: ffprobe couldn't compute an average at all.
+        assert!(is_variable_frame_rate(Some("0/0"), Some("25/1")));
+        assert_eq!(parse_frame_rate("24000/1001").map(|f| (f * 100.0).round()), Some(2398.0));
+    }
+
+    #[test]
+    fn master_playlist_honors_version_and_independent_segments() {
+        let mut settings = Settings::default();
+        settings.hls_version = 6;
+        settings.independent_segments = true;
+        let master = master_playlist_contents(&settings, &[], &[], false);
+        assert!(master.contains("#EXT-X-VERSION:6\n"));
+        assert!(master.contains("#EXT-X-INDEPENDENT-SEGMENTS\n"));
+
+        settings.independent_segments = false;
+        settings.hls_version = 3;
+        let master = master_playlist_contents(&settings, &[], &[], false);
+        assert!(master.contains("#EXT-X-VERSION:3\n"));
+        assert!(!master.contains("#EXT-X-INDEPENDENT-SEGMENTS"));
+    }
+
+    #[test]
+    fn audio_only_variant_sits_below_every_video_bandwidth() {
+        let settings = Settings::default();
+        let rendition = Rendition {
+            name: "480p".into(),
+            target_height: Some(480),
+            video_bitrate: Some("1400k".into()),
+        };
+        let mut metadata = metadata_with_codec("h264");
+        metadata.bit_rate = Some(1_400_000);
+        let master =
+            master_playlist_contents(&settings, &[(rendition, metadata)], &[], true);
+        assert!(master.contains(&format!(
+            "#EXT-X-STREAM-INF:BANDWIDTH={AUDIO_ONLY_BANDWIDTH},CODECS=\"mp4a.40.2\""
+        )));
+        assert!(master.contains("audio/playlist.m3u8"));
+        assert!(AUDIO_ONLY_BANDWIDTH < 1_400_000);
+    }
+
+    #[test]
+    fn audio_strategies_shape_the_master_and_the_encode_differently() {
+        let track = AudioTrack {
+            index: 0,
+            language: "eng".into(),
+            title: Some("English".into()),
+            codec: "aac".into(),
+            channels: 2,
+            default: true,
+        };
+        let rendition = Rendition {
+            name: "480p".into(),
+            target_height: Some(480),
+            video_bitrate: Some("1400k".into()),
+        };
+        let mut metadata = metadata_with_codec("h264");
+        metadata.bit_rate = Some(1_400_000);
+
+        // Per-rendition (default): URI-less declaration, audio muxed into
+        // the variant's encode.
+        let mut settings = Settings::default();
+        let muxed = master_playlist_contents(
+            &settings,
+            &[(rendition.clone(), metadata.clone())],
+            std::slice::from_ref(&track),
+            false,
+        );
+        assert!(muxed.contains("NAME=\"English\",LANGUAGE=\"eng\",DEFAULT=YES\n"));
+        assert!(!muxed.contains("URI=\"audio/playlist.m3u8\""));
+        assert!(muxed.contains(",AUDIO=\"audio\"\n480p/playlist.m3u8"));
+
+        // Shared group: the declaration carries the audio rendition's URI
+        // and the variant encodes video-only.
+        settings.audio_strategy = AudioStrategy::SharedGroup;
+        let shared = master_playlist_contents(
+            &settings,
+            &[(rendition.clone(), metadata.clone())],
+            std::slice::from_ref(&track),
+            false,
+        );
+        assert!(shared.contains("URI=\"audio/playlist.m3u8\""));
+        assert!(shared.contains(",AUDIO=\"audio\"\n480p/playlist.m3u8"));
+
+        let args = build_ffmpeg_args(
+            &settings,
+            Path::new("/tmp/in.mkv"),
+            &metadata,
+            &rendition,
+            "libx264",
+            Path::new("/tmp/out"),
+            None,
+            None,
+            false,
+            None,
+            None,
+        );
+        let strings: Vec<String> = args
+            .iter()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert!(strings.iter().any(|a| a == "-an"));
+        assert!(!strings.iter().any(|a| a == "-c:a"));
+    }
+
+    #[test]
+    fn proxy_filter_scales_and_optionally_burns_timecode() {
+        assert_eq!(proxy_filter(23.976, false), "scale=-2:540");
+        let burned = proxy_filter(23.976, true);
+        assert!(burned.starts_with("scale=-2:540,drawtext=timecode="));
+        assert!(burned.contains("rate=23.976"));
+        assert_eq!(proxy_preset_args("libx264")[1], "ultrafast");
+        assert_eq!(proxy_preset_args("h264_nvenc")[1], "p1");
+    }
+
+    #[test]
+    fn startup_failures_map_to_specific_variants() {
+        use std::io::{Error, ErrorKind};
+
+        assert!(matches!(
+            spawn_error(Error::from(ErrorKind::NotFound)),
+            AppError::FfmpegNotFound(_)
+        ));
+        assert!(matches!(
+            spawn_error(Error::from(ErrorKind::PermissionDenied)),
+            AppError::FfmpegNotExecutable(_)
+        ));
+        assert!(matches!(
+            spawn_error(Error::from(ErrorKind::Interrupted)),
+            AppError::Ffmpeg(_)
+        ));
+
+        let scratch =
+            std::env::temp_dir().join(format!("uploader-preflight-test-{}", std::process::id()));
+        std::fs::create_dir_all(&scratch).unwrap();
+        assert!(matches!(
+            preflight_conversion(&scratch.join("missing.mkv"), &scratch),
+            Err(AppError::InvalidInput(_))
+        ));
+        let input = scratch.join("input.mkv");
+        std::fs::write(&input, b"").unwrap();
+        assert!(preflight_conversion(&input, &scratch).is_ok());
+        // Permission bits can't model an unwritable output dir when the
+        // suite runs as root, so occupy the path with a regular file and
+        // let create_dir_all fail instead.
+        let occupied = scratch.join("occupied");
+        std::fs::write(&occupied, b"").unwrap();
+        assert!(matches!(
+            preflight_conversion(&input, &occupied),
+            Err(AppError::Settings(_))
+        ));
+        std::fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    #[test]
+    fn master_references_the_iframe_playlist_when_enabled() {
+        let mut settings = Settings::default();
+        settings.generate_iframe_playlist = true;
+        let rendition = Rendition {
+            name: "1080p".into(),
+            target_height: None,
+            video_bitrate: None,
+        };
+        let metadata = metadata_with_codec("h264");
+        let master =
+            master_playlist_contents(&settings, &[(rendition.clone(), metadata.clone())], &[], false);
+        assert!(master.contains(&format!(
+            "#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH={IFRAME_BANDWIDTH},RESOLUTION=640x360,URI=\"{IFRAME_RENDITION}/playlist.m3u8\"\n"
+        )));
+
+        settings.generate_iframe_playlist = false;
+        let master = master_playlist_contents(&settings, &[(rendition, metadata)], &[], false);
+        assert!(!master.contains("#EXT-X-I-FRAME-STREAM-INF"));
+    }
+
+    #[test]
+    fn hwaccel_backend_matches_encoder_hardware() {
+        assert_eq!(hwaccel_for_encoder("h264_nvenc"), Some("cuda"));
+        assert_eq!(hwaccel_for_encoder("hevc_qsv"), Some("qsv"));
+        assert_eq!(hwaccel_for_encoder("h264_videotoolbox"), Some("videotoolbox"));
+        assert_eq!(hwaccel_for_encoder("libx264"), None);
+    }
+
+    fn metadata_with_codec(codec: &str) -> VideoMetadata {
+        VideoMetadata {
+            width: 1920,
+            height: 1080,
+            duration_seconds: 0.0,
+            bit_rate: None,
+            video_codec: codec.into(),
+            audio_codec: None,
+            audio_tracks: Vec::new(),
+            average_frame_rate: None,
+            variable_frame_rate: false,
+            pix_fmt: Some("yuv420p".into()),
+            rotation: None,
+        }
+    }
+
+    #[test]
+    fn fast_remux_needs_compatible_source_and_no_filters() {
+        let mut settings = Settings::default();
+        let original = Rendition {
+            name: "original-1080p".into(),
+            target_height: None,
+            video_bitrate: None,
+        };
+        let scaled = Rendition {
+            name: "480p".into(),
+            target_height: Some(480),
+            video_bitrate: Some("1400k".into()),
+        };
+        let h264 = metadata_with_codec("h264");
+        assert!(is_fast_remux(&settings, &h264, &original, None));
+        assert!(!is_fast_remux(&settings, &h264, &scaled, None));
+        assert!(!is_fast_remux(&settings, &h264, &original, Some("subtitles=a.mkv:si=0")));
+        assert!(!is_fast_remux(&settings, &metadata_with_codec("vp9"), &original, None));
+        settings.fast_remux_if_compatible = false;
+        assert!(!is_fast_remux(&settings, &h264, &original, None));
+    }
+
+    #[test]
+    fn extracts_the_last_cropdetect_rectangle() {
+        let stderr = "[Parsed_cropdetect_0] x1:0 x2:1919 crop=1920:800:0:140\n\
+                      [Parsed_cropdetect_0] x1:0 x2:1919 crop=1920:804:0:138\n";
+        assert_eq!(last_cropdetect(stderr).as_deref(), Some("1920:804:0:138"));
+        assert_eq!(last_cropdetect("frame=  48 fps=0.0"), None);
+    }
+
+    #[test]
+    fn escapes_filter_paths() {
+        assert_eq!(escape_filter_path(Path::new("/tmp/a b.mkv")), "/tmp/a b.mkv");
+        assert_eq!(
+            escape_filter_path(Path::new("C:\\movies\\it's.mkv")),
+            "C\\:\\\\movies\\\\it\\'s.mkv"
+        );
+    }
+
+    #[test]
+    fn rendition_encoder_prefers_configured_override() {
+        let mut settings = Settings::default();
+        settings
+            .rendition_encoders
+            .insert("480p".into(), "libx264".into());
+        let low = Rendition {
+            name: "480p".into(),
+            target_height: Some(480),
+            video_bitrate: Some("1400k".into()),
+        };
+        let original = Rendition {
+            name: "original-1080p".into(),
+            target_height: None,
+            video_bitrate: None,
+        };
+        assert_eq!(rendition_encoder(&settings, &low, "h264_nvenc"), "libx264");
+        assert_eq!(rendition_encoder(&settings, &original, "h264_nvenc"), "h264_nvenc");
+    }
+
+    #[test]
+    fn rotated_sources_swap_dimensions_and_skip_fast_remux() {
+        let mut settings = Settings::default();
+        let original = Rendition {
+            name: "original-1080p".into(),
+            target_height: None,
+            video_bitrate: None,
+        };
+        let mut rotated = metadata_with_codec("h264");
+        rotated.rotation = Some(90);
+
+        let displayed = oriented_metadata(&settings, rotated.clone());
+        assert_eq!((displayed.width, displayed.height), (1080, 1920));
+        assert!(!is_fast_remux(&settings, &rotated, &original, None));
+
+        // With rotation ignored, the source is treated as stored.
+        settings.respect_rotation = false;
+        let stored = oriented_metadata(&settings, rotated.clone());
+        assert_eq!((stored.width, stored.height), (1920, 1080));
+        assert!(is_fast_remux(&settings, &rotated, &original, None));
+
+        // 180° keeps the aspect either way.
+        settings.respect_rotation = true;
+        rotated.rotation = Some(180);
+        let flipped = oriented_metadata(&settings, rotated);
+        assert_eq!((flipped.width, flipped.height), (1920, 1080));
+    }
+
+    #[test]
+    fn compatibility_analysis_flags_the_troublesome_sources() {
+        let mut clean = metadata_with_codec("h264");
+        clean.duration_seconds = 5400.0;
+        assert!(compatibility_warnings(&clean).is_empty());
+
+        let mut odd = metadata_with_codec("h264");
+        odd.pix_fmt = Some("yuv420p10le".into());
+        odd.duration_seconds = 0.0;
+        odd.rotation = Some(90);
+        let warnings = compatibility_warnings(&odd);
+        let kinds: Vec<_> = warnings.iter().map(|w| w.kind).collect();
+        assert!(kinds.contains(&CompatibilityKind::PixelFormat));
+        assert!(kinds.contains(&CompatibilityKind::Duration));
+        assert!(kinds.contains(&CompatibilityKind::Rotation));
+        assert!(!kinds.contains(&CompatibilityKind::AudioCodec));
+    }
+
+    #[test]
+    fn rotation_comes_from_side_data_or_legacy_tag() {
+        let stream = |rotation: Option<f64>, tag: Option<&str>| ProbeStream {
+            index: Some(0),
+            codec_type: "video".into(),
+            codec_name: Some("h264".into()),
+            width: Some(1920),
+            height: Some(1080),
+            channels: None,
+            avg_frame_rate: None,
+            r_frame_rate: None,
+            pix_fmt: None,
+            side_data_list: rotation.map(|r| ProbeSideData { rotation: Some(r) }).into_iter().collect(),
+            disposition: Default::default(),
+            tags: tag
+                .map(|t| [("rotate".to_string(), t.to_string())].into())
+                .unwrap_or_default(),
+        };
+        // ffprobe reports the display matrix counter-clockwise: -90 means
+        // rotate 90° clockwise for display.
+        assert_eq!(stream_rotation(&stream(Some(-90.0), None)), Some(90));
+        assert_eq!(stream_rotation(&stream(Some(180.0), None)), Some(180));
+        assert_eq!(stream_rotation(&stream(None, Some("90"))), Some(90));
+        assert_eq!(stream_rotation(&stream(Some(0.0), None)), None);
+        assert_eq!(stream_rotation(&stream(None, None)), None);
+    }
+
+    #[test]
+    fn endlist_is_written_only_for_vod_playlists() {
+        // ffmpeg writes #EXT-X-ENDLIST only under `-hls_playlist_type vod`,
+        // so asserting on the flag value asserts on the playlist ending.
+        let playlist_type_arg = |settings: &Settings| {
+            let rendition = Rendition {
+                name: "original-1080p".into(),
+                target_height: None,
+                video_bitrate: None,
+            };
+            let args = build_ffmpeg_args(
+                settings,
+                Path::new("/tmp/in.mkv"),
+                &metadata_with_codec("h264"),
+                &rendition,
+                "libx264",
+                Path::new("/tmp/out"),
+                None,
+                None,
+                false,
+                None,
+                None,
+            );
+            let at = args
+                .iter()
+                .position(|a| a == "-hls_playlist_type")
+                .expect("playlist type is always set");
+            args[at + 1].to_string_lossy().into_owned()
+        };
+
+        let mut settings = Settings::default();
+        assert_eq!(playlist_type_arg(&settings), "vod");
+        settings.hls_playlist_type = HlsPlaylistType::Event;
+        assert_eq!(playlist_type_arg(&settings), "event");
+    }
+
+    #[test]
+    fn single_file_mode_packs_renditions_into_byteranged_ts() {
+        // ffmpeg emits #EXT-X-BYTERANGE entries exactly when `single_file`
+        // is among the hls_flags, so asserting on the flag asserts on the
+        // playlist format.
+        let rendition = Rendition {
+            name: "original-1080p".into(),
+            target_height: None,
+            video_bitrate: None,
+        };
+        let mut settings = Settings::default();
+        settings.hls_single_file = true;
+        let args = build_ffmpeg_args(
+            &settings,
+            Path::new("/tmp/in.mkv"),
+            &metadata_with_codec("h264"),
+            &rendition,
+            "libx264",
+            Path::new("/tmp/out"),
+            None,
+            None,
+            false,
+            None,
+            None,
+        );
+        let at = args
+            .iter()
+            .position(|a| a == "-hls_flags")
+            .expect("single_file rides on -hls_flags");
+        let flags = args[at + 1].to_string_lossy().into_owned();
+        assert!(flags.split(',').any(|f| f == "single_file"), "{flags}");
+        let name = args
+            .iter()
+            .position(|a| a == "-hls_segment_filename")
+            .map(|at| args[at + 1].to_string_lossy().into_owned())
+            .expect("segment filename is always set");
+        assert!(name.ends_with("media.ts"), "{name}");
+    }
+
+    #[test]
+    fn audio_offsets_delay_or_trim_through_filters() {
+        let rendition = Rendition {
+            name: "original-1080p".into(),
+            target_height: None,
+            video_bitrate: None,
+        };
+        let settings = Settings::default();
+        let audio_args = |offset: i64| {
+            let args = build_ffmpeg_args(
+                &settings,
+                Path::new("/tmp/in.mkv"),
+                &metadata_with_codec("h264"),
+                &rendition,
+                "libx264",
+                Path::new("/tmp/out"),
+                None,
+                None,
+                false,
+                Some(offset),
+                None,
+            );
+            let at = args.iter().position(|a| a == "-af").expect("-af present");
+            args[at + 1].to_string_lossy().into_owned()
+        };
+        assert_eq!(audio_args(250), "adelay=250:all=1");
+        assert_eq!(audio_args(-1500), "atrim=start=1.500,asetpts=PTS-STARTPTS");
+    }
+
+    #[test]
+    fn deterministic_encodes_pin_threads_and_bitexact() {
+        // Identical argv on identical input is what makes two runs produce
+        // identical bytes (for encoders without internal nondeterminism),
+        // so the flags are what the checksum equality rests on.
+        let rendition = Rendition {
+            name: "original-1080p".into(),
+            target_height: None,
+            video_bitrate: None,
+        };
+        let mut settings = Settings::default();
+        settings.deterministic = true;
+        let args = build_ffmpeg_args(
+            &settings,
+            Path::new("/tmp/in.mkv"),
+            &metadata_with_codec("h264"),
+            &rendition,
+            "libx264",
+            Path::new("/tmp/out"),
+            None,
+            None,
+            false,
+            None,
+            None,
+        );
+        let strings: Vec<String> = args
+            .iter()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        let threads = strings.iter().position(|a| a == "-threads").unwrap();
+        assert_eq!(strings[threads + 1], "1");
+        let flags = strings.iter().position(|a| a == "-flags").unwrap();
+        assert_eq!(strings[flags + 1], "+bitexact");
+        let fflags = strings.iter().position(|a| a == "-fflags").unwrap();
+        assert_eq!(strings[fflags + 1], "+bitexact");
+        assert!(fflags < strings.iter().position(|a| a == "-i").unwrap());
+    }
+
+    #[test]
+    fn thread_cap_applies_only_outside_deterministic_mode() {
+        let rendition = Rendition {
+            name: "original-1080p".into(),
+            target_height: None,
+            video_bitrate: None,
+        };
+        let mut settings = Settings::default();
+        settings.ffmpeg_threads_per_job = Some(4);
+        let args_for = |settings: &Settings| -> Vec<String> {
+            build_ffmpeg_args(
+                settings,
+                Path::new("/tmp/in.mkv"),
+                &metadata_with_codec("h264"),
+                &rendition,
+                "libx264",
+                Path::new("/tmp/out"),
+                None,
+                None,
+                false,
+                None,
+                None,
+            )
+            .iter()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+        };
+
+        let strings = args_for(&settings);
+        let threads = strings.iter().position(|a| a == "-threads").unwrap();
+        assert_eq!(strings[threads + 1], "4");
+
+        // Deterministic mode keeps its single pinned thread.
+        settings.deterministic = true;
+        let strings = args_for(&settings);
+        let threads = strings.iter().position(|a| a == "-threads").unwrap();
+        assert_eq!(strings[threads + 1], "1");
+
+        // Unset means ffmpeg chooses.
+        settings.deterministic = false;
+        settings.ffmpeg_threads_per_job = None;
+        assert!(!args_for(&settings).iter().any(|a| a == "-threads"));
+    }
+
+    #[test]
+    fn appended_renditions_extend_the_master_without_touching_existing_entries() {
+        let master = "#EXTM3U\n#EXT-X-VERSION:3\n\
+                      #EXT-X-STREAM-INF:BANDWIDTH=1528000,RESOLUTION=854x480\n480p/playlist.m3u8\n";
+        assert_eq!(master_rendition_names(master), vec!["480p"]);
+        let extended = append_stream_inf(master, "2160p", 16_000_000, 3840, 2160);
+        assert!(extended.contains("480p/playlist.m3u8"));
+        assert!(extended
+            .contains("#EXT-X-STREAM-INF:BANDWIDTH=16000000,RESOLUTION=3840x2160\n2160p/playlist.m3u8"));
+        assert_eq!(master_rendition_names(&extended), vec!["480p", "2160p"]);
+    }
+
+    #[test]
+    fn playlist_duration_sums_extinf_entries() {
+        let playlist = "#EXTM3U\n#EXTINF:6.000000,\nsegment_000.ts\n\
+                        #EXTINF:4.500000,\nsegment_001.ts\n#EXT-X-ENDLIST\n";
+        assert!((playlist_total_duration(playlist) - 10.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn broken_timestamps_are_spotted_in_ffmpeg_stderr() {
+        let stderr = "[mpegts @ 0x55] Non-monotonic DTS in output stream 0:0; \
+                      previous: 183759, current: 183759; changing to 183760.";
+        assert!(stderr_reports_broken_timestamps(stderr));
+        assert!(stderr_reports_broken_timestamps(
+            "[mp4 @ 0x55] Application provided invalid, non monotonically increasing dts"
+        ));
+        assert!(!stderr_reports_broken_timestamps("frame= 240 fps= 48"));
+    }
+
+    #[test]
+    fn vmaf_score_is_parsed_from_the_summary_line() {
+        let stderr = "\
+[Parsed_libvmaf_0 @ 0x55] VMAF score: 94.532817
+frame= 240 fps= 48 q=-0.0 Lsize=N/A";
+        assert_eq!(parse_vmaf_score(stderr), Some(94.532817));
+        assert_eq!(parse_vmaf_score("frame= 240 fps= 48"), None);
+    }
+
+    #[test]
+    fn playlist_key_uri_matches_the_server_template() {
+        // ffmpeg copies the keyinfo file's first line verbatim into the
+        // playlist's #EXT-X-KEY URI, so asserting on that line asserts on
+        // what players will fetch.
+        let uri = resolve_key_uri("https://keys.cinemafred.com/{slug}", "the-big-sleep-1946");
+        assert_eq!(uri, "https://keys.cinemafred.com/the-big-sleep-1946");
+        let info = key_info_contents(&uri, Path::new("/tmp/out/hls.key"));
+        assert_eq!(
+            info.lines().next(),
+            Some("https://keys.cinemafred.com/the-big-sleep-1946")
+        );
+        assert_eq!(info.lines().nth(1), Some("/tmp/out/hls.key"));
+    }
+
+    #[test]
+    fn key_material_is_sixteen_bytes_and_not_constant() {
+        let a = generate_key_bytes();
+        let b = generate_key_bytes();
+        assert_eq!(a.len(), 16);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn parses_bitrate_suffixes() {
+        assert_eq!(parse_bitrate("1400k"), Some(1_400_000));
+        assert_eq!(parse_bitrate("2M"), Some(2_000_000));
+        assert_eq!(parse_bitrate("800000"), Some(800_000));
+        assert_eq!(parse_bitrate("fast"), None);
+    }
+}
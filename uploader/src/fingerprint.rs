@@ -0,0 +1,220 @@
+//! Perceptual fingerprinting for duplicate detection.
+//!
+//! A fingerprint is a sequence of 64-bit difference hashes (dHash), one
+//! per sampled frame: each frame is reduced to 9x8 grayscale and every
+//! bit records whether a pixel is brighter than its right neighbour.
+//! dHash survives re-encodes, scaling and mild color shifts — exactly
+//! the transformations between two rips of the same film — while
+//! unrelated content diverges on roughly half the bits.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+use crate::error::{AppError, Result};
+
+/// Frames sampled across the film. Fixed rather than duration-scaled so a
+/// three-hour film costs the same handful of fast seeks as a short one.
+const SAMPLE_FRAMES: usize = 32;
+
+/// Sampling skips this fraction at each end of the film, where studio
+/// logos and credits make unrelated films look alike.
+const EDGE_SKIP_FRACTION: f64 = 0.05;
+
+/// Hash input dimensions: one extra column so every output bit has a
+/// right neighbour to compare against.
+const HASH_WIDTH: usize = 9;
+const HASH_HEIGHT: usize = 8;
+
+/// Prefix identifying the fingerprint format, so stored fingerprints can
+/// be migrated if the hash ever changes.
+const FORMAT_PREFIX: &str = "dhash-v1:";
+
+/// Two fingerprints at or above this similarity almost certainly show the
+/// same content; chance level for unrelated films is around 0.5.
+const DUPLICATE_THRESHOLD: f64 = 0.9;
+
+/// dHash of one 9x8 grayscale frame: bit (y*8+x) is set when pixel (x,y)
+/// is brighter than its right neighbour.
+fn dhash(pixels: &[u8]) -> u64 {
+    let mut hash = 0u64;
+    for y in 0..HASH_HEIGHT {
+        for x in 0..HASH_WIDTH - 1 {
+            hash <<= 1;
+            if pixels[y * HASH_WIDTH + x] > pixels[y * HASH_WIDTH + x + 1] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// The storable single-string form of a hash sequence.
+fn compact(hashes: &[u64]) -> String {
+    let hex: Vec<String> = hashes.iter().map(|h| format!("{h:016x}")).collect();
+    format!("{FORMAT_PREFIX}{}", hex.join("."))
+}
+
+/// Parse a fingerprint produced by [`compact`].
+fn parse_compact(s: &str) -> Result<Vec<u64>> {
+    let body = s.strip_prefix(FORMAT_PREFIX).ok_or_else(|| {
+        AppError::InvalidInput(format!(
+            "unrecognized fingerprint format; expected a {FORMAT_PREFIX}… string"
+        ))
+    })?;
+    body.split('.')
+        .map(|h| {
+            u64::from_str_radix(h, 16)
+                .map_err(|_| AppError::InvalidInput(format!("corrupt fingerprint entry {h:?}")))
+        })
+        .collect()
+}
+
+/// Similarity of two hash sequences in 0.0..=1.0: the mean fraction of
+/// matching bits, comparing frames at the same relative position. The
+/// sequences may differ in length (e.g. fingerprints from an older frame
+/// count); the shorter one is walked and each entry matched to the
+/// nearest relative position in the longer.
+fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let mut matching_bits = 0u64;
+    for (i, &hash) in short.iter().enumerate() {
+        let j = i * (long.len() - 1) / (short.len() - 1).max(1);
+        matching_bits += u64::from(64 - (hash ^ long[j]).count_ones());
+    }
+    matching_bits as f64 / (short.len() as f64 * 64.0)
+}
+
+/// What [`compute_video_fingerprint`] hands back for storage next to the
+/// movie (DB column or manifest field).
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoFingerprint {
+    pub frames_sampled: usize,
+    pub duration_seconds: f64,
+    /// Single-string form, compared later with [`compare_fingerprints`].
+    pub fingerprint: String,
+}
+
+/// Sample frames evenly across `input_path`, hash each, and return the
+/// combined fingerprint. Each sample is one fast seek plus a single-frame
+/// decode, so the cost is bounded regardless of the film's length.
+#[tauri::command]
+pub async fn compute_video_fingerprint(input_path: PathBuf) -> Result<VideoFingerprint> {
+    let input_path = crate::paths::normalize(&input_path.to_string_lossy())?;
+    let metadata = crate::ffmpeg::probe(&input_path).await?;
+    if metadata.duration_seconds <= 0.0 {
+        return Err(AppError::InvalidInput(
+            "source reports no duration; cannot sample frames".into(),
+        ));
+    }
+    let start = metadata.duration_seconds * EDGE_SKIP_FRACTION;
+    let span = metadata.duration_seconds * (1.0 - 2.0 * EDGE_SKIP_FRACTION);
+
+    let mut hashes = Vec::with_capacity(SAMPLE_FRAMES);
+    for i in 0..SAMPLE_FRAMES {
+        let timestamp = start + span * i as f64 / (SAMPLE_FRAMES - 1) as f64;
+        let output = Command::new("ffmpeg")
+            .args(["-v", "error"])
+            .args(["-ss", &format!("{timestamp:.3}")])
+            .arg("-i")
+            .arg(&input_path)
+            .args(["-frames:v", "1"])
+            .args(["-vf", &format!("scale={HASH_WIDTH}:{HASH_HEIGHT},format=gray")])
+            .args(["-f", "rawvideo", "-"])
+            .output()
+            .await
+            .map_err(crate::ffmpeg::spawn_error)?;
+        if !output.status.success() {
+            return Err(AppError::Ffmpeg(format!(
+                "frame sample at {timestamp:.1}s exited with {}",
+                output.status
+            )));
+        }
+        // A seek landing past the last frame produces no output; skip it
+        // rather than hashing garbage.
+        if output.stdout.len() < HASH_WIDTH * HASH_HEIGHT {
+            continue;
+        }
+        hashes.push(dhash(&output.stdout[..HASH_WIDTH * HASH_HEIGHT]));
+    }
+    if hashes.is_empty() {
+        return Err(AppError::Ffmpeg(
+            "no frames could be sampled from the source".into(),
+        ));
+    }
+    Ok(VideoFingerprint {
+        frames_sampled: hashes.len(),
+        duration_seconds: metadata.duration_seconds,
+        fingerprint: compact(&hashes),
+    })
+}
+
+/// Verdict from [`compare_fingerprints`].
+#[derive(Debug, Clone, Serialize)]
+pub struct FingerprintComparison {
+    /// 0.0..=1.0; around 0.5 is chance level, re-encodes of the same
+    /// content score well above 0.9.
+    pub similarity: f64,
+    pub likely_duplicate: bool,
+}
+
+/// Score how alike two stored fingerprints are, for pre-upload duplicate
+/// checks against the existing library.
+#[tauri::command]
+pub fn compare_fingerprints(a: String, b: String) -> Result<FingerprintComparison> {
+    let similarity = similarity(&parse_compact(&a)?, &parse_compact(&b)?);
+    Ok(FingerprintComparison {
+        similarity,
+        likely_duplicate: similarity >= DUPLICATE_THRESHOLD,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 9x8 frame whose rows all follow `row`.
+    fn frame_of_rows(row: [u8; HASH_WIDTH]) -> Vec<u8> {
+        row.repeat(HASH_HEIGHT)
+    }
+
+    #[test]
+    fn dhash_tracks_horizontal_gradients() {
+        // Brightness rising to the right: no pixel beats its neighbour.
+        let rising = frame_of_rows([0, 10, 20, 30, 40, 50, 60, 70, 80]);
+        assert_eq!(dhash(&rising), 0);
+        // Falling: every comparison fires.
+        let falling = frame_of_rows([80, 70, 60, 50, 40, 30, 20, 10, 0]);
+        assert_eq!(dhash(&falling), u64::MAX);
+    }
+
+    #[test]
+    fn similarity_separates_duplicates_from_unrelated_content() {
+        let a = vec![0xDEAD_BEEF_0123_4567u64; 16];
+        assert_eq!(similarity(&a, &a), 1.0);
+        // Complemented hashes share no bits.
+        let complement: Vec<u64> = a.iter().map(|h| !h).collect();
+        assert_eq!(similarity(&a, &complement), 0.0);
+        // Differing lengths compare at matching relative positions.
+        let longer = vec![0xDEAD_BEEF_0123_4567u64; 32];
+        assert_eq!(similarity(&a, &longer), 1.0);
+    }
+
+    #[test]
+    fn compact_form_round_trips_and_rejects_garbage() {
+        let hashes = vec![0, 42, u64::MAX];
+        assert_eq!(parse_compact(&compact(&hashes)).unwrap(), hashes);
+        assert!(matches!(
+            parse_compact("md5:abcdef"),
+            Err(AppError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            parse_compact("dhash-v1:nothex"),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+}
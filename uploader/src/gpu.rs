@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+/// Minimum NVIDIA driver major version required for nvenc, per codec.
+/// AV1 encoding arrived with the 530 series; H.264/HEVC have been stable
+/// since the 390 series.
+const NVENC_DRIVER_MINIMUMS: &[(&str, u32)] = &[("h264", 390), ("hevc", 390), ("av1", 530)];
+
+/// One detected GPU, as nvidia-smi enumerates them. The index is what
+/// `gpu_device_index` and nvenc's `-gpu` option address.
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuInfo {
+    pub index: u32,
+    pub name: String,
+    pub driver_version: Option<String>,
+}
+
+/// What the machine can (actually) encode with, reported to the frontend.
+/// `gpu_name`/`driver_version` describe the first GPU; multi-GPU machines
+/// list every device in `gpus`.
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuCapabilities {
+    pub gpu_name: Option<String>,
+    pub driver_version: Option<String>,
+    pub gpus: Vec<GpuInfo>,
+    pub nvenc_available: bool,
+    /// Per-codec nvenc support given the installed driver version.
+    pub codec_support: HashMap<String, bool>,
+    pub recommended_encoder: String,
+}
+
+/// Major component of an NVIDIA driver version string ("535.154.05" -> 535).
+fn parse_driver_major(version: &str) -> Option<u32> {
+    version.trim().split('.').next()?.parse().ok()
+}
+
+/// Per-codec nvenc support for a given driver version. A driver we can't
+/// parse supports nothing — better to fall back to CPU than recommend an
+/// encoder that fails at runtime.
+fn codec_support_for_driver(driver_version: Option<&str>) -> HashMap<String, bool> {
+    let major = driver_version.and_then(parse_driver_major);
+    NVENC_DRIVER_MINIMUMS
+        .iter()
+        .map(|(codec, minimum)| {
+            (codec.to_string(), major.map(|m| m >= *minimum).unwrap_or(false))
+        })
+        .collect()
+}
+
+/// Parse nvidia-smi's `index,name,driver_version` CSV, one GPU per line.
+fn parse_gpu_list(stdout: &str) -> Vec<GpuInfo> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',');
+            let index = fields.next()?.trim().parse().ok()?;
+            let name = fields.next()?.trim().to_string();
+            if name.is_empty() {
+                return None;
+            }
+            let driver_version = fields
+                .next()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty());
+            Some(GpuInfo {
+                index,
+                name,
+                driver_version,
+            })
+        })
+        .collect()
+}
+
+/// Detect the GPUs via nvidia-smi. Absence of the binary (or any failure)
+/// simply means no GPU encoding.
+pub async fn detect() -> GpuCapabilities {
+    let output = Command::new("nvidia-smi")
+        .args(["--query-gpu=index,name,driver_version", "--format=csv,noheader"])
+        .output()
+        .await;
+
+    let gpus = match output {
+        Ok(out) if out.status.success() => parse_gpu_list(&String::from_utf8_lossy(&out.stdout)),
+        _ => Vec::new(),
+    };
+    let gpu_name = gpus.first().map(|g| g.name.clone());
+    let driver_version = gpus.first().and_then(|g| g.driver_version.clone());
+
+    let codec_support = codec_support_for_driver(driver_version.as_deref());
+    let nvenc_available = gpu_name.is_some() && codec_support.values().any(|v| *v);
+
+    let mut capabilities = GpuCapabilities {
+        gpu_name,
+        driver_version,
+        gpus,
+        nvenc_available,
+        codec_support,
+        recommended_encoder: String::new(),
+    };
+    capabilities.recommended_encoder = encoder_for_codec(&capabilities, "h264");
+    capabilities
+}
+
+/// The encoder to use for `codec`, falling back to libx264 when the driver
+/// is missing or too old for nvenc on that codec.
+pub fn encoder_for_codec(capabilities: &GpuCapabilities, codec: &str) -> String {
+    if capabilities.nvenc_available && capabilities.codec_support.get(codec) == Some(&true) {
+        format!("{codec}_nvenc")
+    } else {
+        "libx264".to_string()
+    }
+}
+
+#[tauri::command]
+pub async fn test_gpu_capabilities() -> GpuCapabilities {
+    detect().await
+}
+
+/// Caps concurrent hardware-encoded jobs independently of the general job
+/// limiter, managed as tauri state.
+pub struct GpuLimiter {
+    permits: std::sync::Arc<tokio::sync::Semaphore>,
+}
+
+impl GpuLimiter {
+    pub fn new(max_gpu_jobs: usize) -> Self {
+        Self {
+            permits: std::sync::Arc::new(tokio::sync::Semaphore::new(max_gpu_jobs)),
+        }
+    }
+
+    pub async fn acquire(&self) -> tokio::sync::OwnedSemaphorePermit {
+        self.permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore never closed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_multi_gpu_listings() {
+        let gpus = parse_gpu_list(
+            "0, NVIDIA GeForce RTX 3090, 535.154.05\n1, NVIDIA GeForce RTX 3060, 535.154.05\n",
+        );
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].index, 0);
+        assert_eq!(gpus[1].name, "NVIDIA GeForce RTX 3060");
+        assert_eq!(gpus[1].driver_version.as_deref(), Some("535.154.05"));
+        assert!(parse_gpu_list("").is_empty());
+    }
+
+    #[test]
+    fn parses_driver_major_version() {
+        assert_eq!(parse_driver_major("535.154.05"), Some(535));
+        assert_eq!(parse_driver_major("driver"), None);
+    }
+
+    #[test]
+    fn old_driver_cannot_encode_av1() {
+        let support = codec_support_for_driver(Some("470.82.01"));
+        assert_eq!(support.get("h264"), Some(&true));
+        assert_eq!(support.get("av1"), Some(&false));
+    }
+
+    #[test]
+    fn unknown_driver_supports_nothing() {
+        let support = codec_support_for_driver(None);
+        assert!(support.values().all(|v| !*v));
+    }
+
+    #[test]
+    fn falls_back_to_cpu_when_codec_unsupported() {
+        let capabilities = GpuCapabilities {
+            gpu_name: Some("NVIDIA GeForce RTX 2080".into()),
+            driver_version: Some("470.82.01".into()),
+            gpus: Vec::new(),
+            nvenc_available: true,
+            codec_support: codec_support_for_driver(Some("470.82.01")),
+            recommended_encoder: "h264_nvenc".into(),
+        };
+        assert_eq!(encoder_for_codec(&capabilities, "av1"), "libx264");
+        assert_eq!(encoder_for_codec(&capabilities, "h264"), "h264_nvenc");
+    }
+}
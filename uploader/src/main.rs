@@ -0,0 +1,119 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+mod db;
+mod diagnostics;
+mod error;
+mod ffmpeg;
+mod fingerprint;
+mod gpu;
+mod paths;
+mod playlist;
+mod preview;
+mod queue;
+mod r2;
+mod scan;
+mod settings;
+mod subtitles;
+mod thumbnails;
+
+use queue::JobQueue;
+use settings::SettingsStore;
+
+fn main() {
+    let store = SettingsStore::load_or_default();
+    let startup = store.get();
+    tauri::Builder::default()
+        .manage(store)
+        .manage(JobQueue::new(startup.max_concurrent_jobs))
+        .manage(gpu::GpuLimiter::new(startup.max_gpu_jobs))
+        .manage(preview::PreviewServer::new())
+        .manage(scan::Scanner::new())
+        .manage(r2::BucketVerifier::new())
+        .manage(r2::PrefixDeleter::new())
+        .manage(r2::CdnWarmer::new())
+        .invoke_handler(tauri::generate_handler![
+            diagnostics::app_info,
+            diagnostics::check_for_updates,
+            diagnostics::health_check,
+            diagnostics::test_disk_throughput,
+            diagnostics::benchmark_pipeline,
+            diagnostics::dump_effective_config,
+            db::check_db_schema,
+            settings::get_settings,
+            settings::update_settings,
+            settings::patch_settings,
+            settings::clear_credentials,
+            r2::upload_to_r2,
+            r2::upload_folder_to_r2,
+            r2::download_r2_object,
+            r2::hls_to_mp4,
+            r2::list_r2_objects,
+            r2::head_r2_object,
+            r2::delete_r2_prefix,
+            r2::cancel_prefix_delete,
+            r2::compute_checksum,
+            r2::check_bucket_cors,
+            r2::apply_recommended_cors,
+            r2::set_object_expiry,
+            r2::clear_object_expiry,
+            r2::set_object_tags,
+            r2::get_object_tags,
+            r2::purge_cdn_cache,
+            r2::warm_cdn,
+            r2::cancel_cdn_warm,
+            r2::verify_uploaded_package,
+            r2::verify_bucket_integrity,
+            r2::cancel_bucket_verify,
+            r2::rebuild_master_playlist,
+            r2::read_manifest,
+            r2::reconcile_library,
+            r2::estimate_costs,
+            r2::list_incomplete_uploads,
+            r2::abort_incomplete_upload,
+            r2::abort_all_incomplete_uploads,
+            r2::validate_target,
+            r2::validate_all_targets,
+            ffmpeg::get_video_metadata,
+            ffmpeg::extract_chapters,
+            ffmpeg::probe_videos,
+            ffmpeg::estimate_output_size,
+            ffmpeg::check_realtime_capability,
+            ffmpeg::analyze_compatibility,
+            ffmpeg::ab_compare,
+            ffmpeg::build_ffmpeg_command,
+            ffmpeg::detect_crop,
+            ffmpeg::convert_video,
+            ffmpeg::generate_proxy,
+            ffmpeg::add_renditions,
+            fingerprint::compute_video_fingerprint,
+            fingerprint::compare_fingerprints,
+            gpu::test_gpu_capabilities,
+            queue::add_job,
+            queue::convert_and_upload_batch,
+            queue::import_batch,
+            queue::cancel_job,
+            queue::cancel_upload,
+            queue::set_uploads_paused,
+            queue::rollback_upload,
+            queue::retry_job,
+            queue::set_job_priority,
+            queue::reorder_queue,
+            queue::list_jobs,
+            queue::get_queue_stats,
+            queue::export_job_report,
+            scan::scan_video_files,
+            scan::cancel_scan,
+            thumbnails::generate_scrubbing_thumbnails,
+            thumbnails::extract_frame,
+            thumbnails::generate_animated_preview,
+            subtitles::extract_subtitles,
+            subtitles::upload_subtitles,
+            subtitles::convert_subtitle_to_vtt,
+            paths::normalize_input_path,
+            playlist::validate_playlist,
+            preview::preview_hls,
+            preview::stop_preview,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running cinemafred uploader");
+}
@@ -0,0 +1,127 @@
+//! Normalization of paths handed over from the frontend.
+//!
+//! Drag-and-drop delivers different shapes per platform: `file://` URIs
+//! with percent-encoding on macOS and Linux, UNC and `\\?\` verbatim
+//! prefixes on Windows. ffmpeg chokes on all of them, so file-accepting
+//! commands funnel their inputs through [`normalize`] instead of trusting
+//! the raw string.
+
+use std::path::PathBuf;
+
+use crate::error::{AppError, Result};
+
+/// Decode `%XX` percent-escapes; malformed escapes pass through untouched.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hi = (bytes[i + 1] as char).to_digit(16);
+            let lo = (bytes[i + 2] as char).to_digit(16);
+            if let (Some(hi), Some(lo)) = (hi, lo) {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// The decoded path inside a `file://` URI, or None when `raw` isn't one.
+/// A non-local host becomes a `//host/share` network path; the spurious
+/// slash in front of a Windows drive (`file:///C:/…`) is dropped.
+fn from_file_uri(raw: &str) -> Option<String> {
+    let rest = raw.strip_prefix("file://")?;
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    let decoded = percent_decode(rest);
+    if !decoded.starts_with('/') {
+        return Some(format!("//{decoded}"));
+    }
+    let bytes = decoded.as_bytes();
+    if bytes.len() >= 3 && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        return Some(decoded[1..].to_string());
+    }
+    Some(decoded)
+}
+
+/// Clean a frontend-supplied path: decode `file://` URIs, strip Windows'
+/// `\\?\` verbatim prefix, normalize separators to the platform's, resolve
+/// to an absolute canonical path when the file exists, and reject control
+/// characters that cannot survive ffmpeg's command line.
+pub fn normalize(raw: &str) -> Result<PathBuf> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::InvalidInput("empty path".into()));
+    }
+    let mut path = from_file_uri(trimmed).unwrap_or_else(|| trimmed.to_string());
+    if let Some(rest) = path.strip_prefix(r"\\?\") {
+        path = rest.to_string();
+    }
+    if let Some(c) = path.chars().find(|c| c.is_control()) {
+        return Err(AppError::InvalidInput(format!(
+            "path contains the control character {c:?}, which cannot be passed to ffmpeg; \
+             rename the file"
+        )));
+    }
+    if cfg!(windows) {
+        path = path.replace('/', "\\");
+    }
+    let path = PathBuf::from(path);
+    // Canonicalizing also resolves symlinks and relative components; a path
+    // that doesn't exist yet is kept cleaned-but-unresolved so the caller's
+    // own existence check produces the better error.
+    Ok(std::fs::canonicalize(&path).unwrap_or(path))
+}
+
+/// Normalize one frontend-supplied path (drag-and-drop URI, UNC prefix,
+/// percent-encoding) into what the other commands expect.
+#[tauri::command]
+pub fn normalize_input_path(path: String) -> Result<PathBuf> {
+    normalize(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_uris_decode_to_plain_paths() {
+        assert_eq!(
+            normalize("file:///Users/me/My%20Movie.mkv").unwrap(),
+            PathBuf::from("/Users/me/My Movie.mkv")
+        );
+        assert_eq!(
+            normalize("file://localhost/movies/a.mkv").unwrap(),
+            PathBuf::from("/movies/a.mkv")
+        );
+        // A remote host becomes a network path.
+        assert_eq!(
+            normalize("file://nas/share/a.mkv").unwrap(),
+            PathBuf::from("//nas/share/a.mkv")
+        );
+        // The extra slash in front of a Windows drive is dropped.
+        assert_eq!(
+            normalize("file:///C:/movies/a.mkv").unwrap().to_string_lossy(),
+            if cfg!(windows) { "C:\\movies\\a.mkv" } else { "C:/movies/a.mkv" }
+        );
+    }
+
+    #[test]
+    fn verbatim_prefixes_and_bad_escapes_are_handled() {
+        assert_eq!(
+            normalize(r"\\?\C:\movies\a.mkv").unwrap(),
+            PathBuf::from(r"C:\movies\a.mkv")
+        );
+        // Malformed escapes pass through rather than corrupting the path.
+        assert_eq!(percent_decode("50%25 off%2G"), "50% off%2G");
+        assert!(matches!(
+            normalize("movie\nwith-newline.mkv"),
+            Err(AppError::InvalidInput(_))
+        ));
+        assert!(matches!(normalize("   "), Err(AppError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::error::{AppError, Result};
+use crate::r2;
+use crate::settings::SettingsStore;
+
+/// Structured outcome of a playlist validation.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaylistReport {
+    pub is_master: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub ok: bool,
+}
+
+/// Syntax-level lint of a playlist plus the URIs it references, shared by
+/// the local-file and R2 paths.
+struct PlaylistLint {
+    is_master: bool,
+    uris: Vec<String>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+fn lint(content: &str) -> PlaylistLint {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    if content.lines().next().map(str::trim) != Some("#EXTM3U") {
+        errors.push("playlist does not start with #EXTM3U".into());
+    }
+    if !content.contains("#EXT-X-VERSION") {
+        warnings.push("missing #EXT-X-VERSION tag".into());
+    }
+
+    let is_master = content.contains("#EXT-X-STREAM-INF");
+    let uris: Vec<String> = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect();
+
+    if is_master {
+        if uris.is_empty() {
+            errors.push("master playlist references no variant streams".into());
+        }
+    } else {
+        if !content.contains("#EXT-X-TARGETDURATION") {
+            errors.push("media playlist is missing #EXT-X-TARGETDURATION".into());
+        }
+        if !content.contains("#EXT-X-ENDLIST") {
+            warnings.push("media playlist has no #EXT-X-ENDLIST (not VOD-finalized?)".into());
+        }
+        if uris.is_empty() {
+            errors.push("media playlist contains no segment URIs".into());
+        }
+    }
+
+    PlaylistLint {
+        is_master,
+        uris,
+        errors,
+        warnings,
+    }
+}
+
+/// Validate a master or media playlist before publishing: required tags are
+/// present and every referenced URI actually resolves. Accepts either a
+/// local file path or an R2 object key.
+#[tauri::command]
+pub async fn validate_playlist(
+    store: State<'_, SettingsStore>,
+    path_or_url: String,
+) -> Result<PlaylistReport> {
+    let local = Path::new(&path_or_url);
+    if local.is_file() {
+        let content = std::fs::read_to_string(local)?;
+        let mut result = lint(&content);
+        let base = local.parent().unwrap_or_else(|| Path::new("."));
+        for uri in &result.uris {
+            if uri.starts_with("http://") || uri.starts_with("https://") {
+                result
+                    .warnings
+                    .push(format!("{uri}: absolute URL not checked"));
+            } else if !base.join(uri).is_file() {
+                result.errors.push(format!("{uri}: referenced file missing"));
+            }
+        }
+        return Ok(report(result));
+    }
+
+    // Not a local file: treat it as an R2 object key.
+    let settings = store.get();
+    let client = r2::client(&settings)?;
+    let content = r2::get_string(&client, &settings, &path_or_url).await?;
+    let mut result = lint(&content);
+    let base = match path_or_url.rfind('/') {
+        Some(i) => &path_or_url[..=i],
+        None => "",
+    };
+    for uri in &result.uris {
+        if uri.starts_with("http://") || uri.starts_with("https://") {
+            result
+                .warnings
+                .push(format!("{uri}: absolute URL not checked"));
+            continue;
+        }
+        let key = format!("{base}{uri}");
+        let exists = client
+            .head_object()
+            .bucket(&settings.r2_bucket)
+            .key(&key)
+            .send()
+            .await;
+        match exists {
+            Ok(_) => {}
+            Err(e) if e.as_service_error().map(|e| e.is_not_found()) == Some(true) => {
+                result.errors.push(format!("{key}: referenced object missing"));
+            }
+            Err(e) => return Err(AppError::R2(format!("head {key}: {e}"))),
+        }
+    }
+    Ok(report(result))
+}
+
+fn report(result: PlaylistLint) -> PlaylistReport {
+    PlaylistReport {
+        is_master: result.is_master,
+        ok: result.errors.is_empty(),
+        errors: result.errors,
+        warnings: result.warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_missing_header() {
+        let result = lint("#EXT-X-VERSION:3\nsegment_000.ts\n");
+        assert!(result.errors.iter().any(|e| e.contains("#EXTM3U")));
+    }
+
+    #[test]
+    fn detects_master_playlists() {
+        let result = lint(
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-STREAM-INF:BANDWIDTH=1\n480p/playlist.m3u8\n",
+        );
+        assert!(result.is_master);
+        assert_eq!(result.uris, vec!["480p/playlist.m3u8"]);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn media_playlist_requires_target_duration_and_segments() {
+        let result = lint("#EXTM3U\n#EXT-X-VERSION:3\n");
+        assert!(result
+            .errors
+            .iter()
+            .any(|e| e.contains("#EXT-X-TARGETDURATION")));
+        assert!(result.errors.iter().any(|e| e.contains("no segment URIs")));
+    }
+}
@@ -0,0 +1,151 @@
+//! Local HLS preview server.
+//!
+//! Serves a conversion's output directory over plain HTTP on a random
+//! loopback port so the frontend can load it into an hls.js player before
+//! anything is uploaded. The protocol needs are tiny (GET, content type,
+//! CORS), so this is a hand-rolled handler on tokio rather than a web
+//! framework dependency.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::State;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::error::{AppError, Result};
+
+/// The running preview server (at most one), managed as tauri state.
+pub struct PreviewServer(Mutex<Option<RunningPreview>>);
+
+struct RunningPreview {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl PreviewServer {
+    pub fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+}
+
+/// Resolve an HTTP request target to a file under `root`, or None when the
+/// target tries to escape it. An empty target serves the master playlist.
+fn resolve_request_path(root: &Path, target: &str) -> Option<PathBuf> {
+    let relative = target
+        .trim_start_matches('/')
+        .split('?')
+        .next()
+        .unwrap_or("");
+    let relative = if relative.is_empty() {
+        "playlist.m3u8"
+    } else {
+        relative
+    };
+    if relative
+        .split('/')
+        .any(|component| component.is_empty() || component == "..")
+    {
+        return None;
+    }
+    Some(root.join(relative))
+}
+
+async fn serve_connection(mut stream: TcpStream, root: &Path) -> std::io::Result<()> {
+    let mut buf = [0u8; 4096];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+
+    let body = match resolve_request_path(root, target) {
+        Some(file) => match tokio::fs::read(&file).await {
+            Ok(body) => {
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+                    crate::r2::guess_content_type(&file),
+                    body.len()
+                );
+                stream.write_all(header.as_bytes()).await?;
+                return stream.write_all(&body).await;
+            }
+            Err(_) => &b"HTTP/1.1 404 Not Found"[..],
+        },
+        None => &b"HTTP/1.1 403 Forbidden"[..],
+    };
+    stream.write_all(body).await?;
+    stream
+        .write_all(b"\r\nContent-Length: 0\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n")
+        .await
+}
+
+/// Start serving `output_dir` on a random loopback port, replacing any
+/// previous preview, and return the master playlist URL to load into the
+/// player.
+#[tauri::command]
+pub async fn preview_hls(
+    server: State<'_, PreviewServer>,
+    output_dir: PathBuf,
+) -> Result<String> {
+    if !output_dir.join("playlist.m3u8").is_file() {
+        return Err(AppError::InvalidInput(format!(
+            "{} does not contain a playlist.m3u8",
+            output_dir.display()
+        )));
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    let addr = listener.local_addr()?;
+    let root = output_dir.clone();
+    let handle = tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else { break };
+            let root = root.clone();
+            tokio::spawn(async move {
+                let _ = serve_connection(stream, &root).await;
+            });
+        }
+    });
+
+    let mut running = server.0.lock().unwrap();
+    if let Some(previous) = running.take() {
+        previous.handle.abort();
+    }
+    *running = Some(RunningPreview { handle });
+    Ok(format!("http://{addr}/playlist.m3u8"))
+}
+
+/// Shut the preview server down. Returns whether one was running.
+#[tauri::command]
+pub fn stop_preview(server: State<'_, PreviewServer>) -> Result<bool> {
+    let mut running = server.0.lock().unwrap();
+    match running.take() {
+        Some(preview) => {
+            preview.handle.abort();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_paths_stay_inside_the_output_dir() {
+        let root = Path::new("/tmp/out");
+        assert_eq!(
+            resolve_request_path(root, "/"),
+            Some(root.join("playlist.m3u8"))
+        );
+        assert_eq!(
+            resolve_request_path(root, "/480p/segment_000.ts?x=1"),
+            Some(root.join("480p/segment_000.ts"))
+        );
+        assert_eq!(resolve_request_path(root, "/../secrets"), None);
+        assert_eq!(resolve_request_path(root, "/480p//segment.ts"), None);
+    }
+}
@@ -0,0 +1,1652 @@
+//! The conversion/upload job queue.
+//!
+//! Every queue transition is pushed to the frontend as a typed event, so
+//! the UI can render a live job list without polling `list_jobs`:
+//!
+//! - `job-enqueued`, `job-started`, `job-uploading`, `job-completed`,
+//!   `job-failed`, `job-cancelled`, `job-upload-cancelled` — payload is the
+//!   full [`Job`]; a failure message rides inside its `status`.
+//! - `job-progress` — [`JobProgress`] (`{ job_id, files_done,
+//!   files_total }`) as the upload phase walks the output folder.
+//!   Per-rendition encode progress stays on `conversion-progress` (see
+//!   [`crate::ffmpeg`]).
+//! - `job-converted` — the full [`ffmpeg::ConversionResult`] once the
+//!   encode finishes, before the upload phase starts.
+//! - `job-updated` — non-lifecycle changes, e.g. a priority bump.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Semaphore;
+
+use crate::error::{AppError, Result};
+use crate::settings::{Settings, SettingsStore};
+use crate::{ffmpeg, r2};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Converting,
+    Uploading,
+    Completed,
+    Failed { message: String },
+    Cancelled,
+    /// Only the upload phase was cancelled; the converted HLS output is
+    /// kept on disk so the upload can be retried (e.g. after fixing
+    /// credentials).
+    UploadCancelled,
+}
+
+/// Which phase of a job failed, so a retry can start from the right place.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPhase {
+    Converting,
+    Uploading,
+}
+
+/// Library metadata attached to a job, typically by a batch import
+/// manifest; direct `add_job` submissions carry none.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobMetadata {
+    pub title: Option<String>,
+    pub year: Option<u32>,
+    /// Object key prefix for the upload, overriding the default
+    /// `hls/{movie_id}`.
+    pub object_prefix: Option<String>,
+}
+
+/// One queued conversion+upload, as shown in the frontend job list.
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub movie_id: String,
+    pub input_path: PathBuf,
+    /// Higher runs first; equal priorities dispatch in insertion order.
+    pub priority: u8,
+    pub status: JobStatus,
+    /// How many times this job has been retried via `retry_job`.
+    pub retries: u32,
+    /// Set when the job fails, recording where it stopped.
+    pub failed_phase: Option<JobPhase>,
+    pub metadata: Option<JobMetadata>,
+    /// The batch submission this job belongs to, if any; `export_job_report`
+    /// groups by it.
+    pub batch_id: Option<u64>,
+    /// RFC 3339 timestamps bracketing the job's lifetime; `finished_at` is
+    /// set on the transition into any terminal status.
+    pub enqueued_at: String,
+    pub finished_at: Option<String>,
+    /// Filled in from the conversion result once the encode finishes.
+    pub encoder_used: Option<String>,
+    pub duration_seconds: Option<f64>,
+    pub output_bytes: Option<u64>,
+}
+
+/// The lifecycle event name announcing a transition into `status` (see the
+/// module docs for the full event contract).
+fn event_for_status(status: &JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued => "job-enqueued",
+        JobStatus::Converting => "job-started",
+        JobStatus::Uploading => "job-uploading",
+        JobStatus::Completed => "job-completed",
+        JobStatus::Failed { .. } => "job-failed",
+        JobStatus::Cancelled => "job-cancelled",
+        JobStatus::UploadCancelled => "job-upload-cancelled",
+    }
+}
+
+/// Upload-phase progress for one job, emitted on `job-progress`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub job_id: u64,
+    pub files_done: usize,
+    pub files_total: usize,
+}
+
+/// Index of the queued job that should run next: highest priority wins,
+/// insertion order breaks ties.
+fn next_queued_index(jobs: &[Job]) -> Option<usize> {
+    jobs.iter()
+        .enumerate()
+        .filter(|(_, j)| j.status == JobStatus::Queued)
+        .max_by_key(|(i, j)| (j.priority, std::cmp::Reverse(*i)))
+        .map(|(i, _)| i)
+}
+
+struct Inner {
+    next_id: u64,
+    next_batch_id: u64,
+    jobs: Vec<Job>,
+    cancel_flags: HashMap<u64, Arc<AtomicBool>>,
+    /// Separate from `cancel_flags`: tripping one of these stops only the
+    /// upload phase, keeping the converted output for a retry.
+    upload_cancel_flags: HashMap<u64, Arc<AtomicBool>>,
+    /// Keys each job's upload phase actually wrote (skips of pre-existing
+    /// objects are not recorded), kept so `rollback_upload` can delete
+    /// exactly what a failed job created and nothing else. Cleared on
+    /// completion.
+    uploaded_keys: HashMap<u64, Vec<String>>,
+}
+
+/// Shared job queue, managed as tauri state. Jobs run on the async runtime;
+/// conversions and uploads are bounded by separate semaphores (both sized
+/// from `max_concurrent_jobs`) so the two phases pipeline: a finished
+/// conversion frees its slot and uploads while the next file converts,
+/// keeping both the CPU/GPU and the network busy within a batch.
+/// How many finished jobs the throughput averages look back over.
+const RECENT_SAMPLE_JOBS: usize = 20;
+
+/// One finished phase's throughput measurement. `amount` is in the
+/// phase's unit — frames for conversions, bytes for uploads — and is None
+/// when it couldn't be measured (a source without a frame rate).
+#[derive(Debug, Clone, Copy)]
+struct PhaseSample {
+    amount: Option<f64>,
+    wall_seconds: f64,
+}
+
+/// Session-lifetime running counters behind `get_queue_stats`, updated as
+/// phases finish so the stats never need to walk job history.
+#[derive(Debug, Default)]
+struct SessionStats {
+    conversions: VecDeque<PhaseSample>,
+    uploads: VecDeque<PhaseSample>,
+    bytes_converted: u64,
+    bytes_uploaded: u64,
+}
+
+/// Keep the most recent [`RECENT_SAMPLE_JOBS`] samples.
+fn push_sample(samples: &mut VecDeque<PhaseSample>, sample: PhaseSample) {
+    if samples.len() == RECENT_SAMPLE_JOBS {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+/// Mean units-per-second across the samples that measured any units.
+fn average_rate(samples: &VecDeque<PhaseSample>) -> Option<f64> {
+    let mut units = 0.0;
+    let mut wall = 0.0;
+    for sample in samples {
+        if let Some(amount) = sample.amount {
+            units += amount;
+            wall += sample.wall_seconds;
+        }
+    }
+    if wall <= 0.0 {
+        return None;
+    }
+    Some(units / wall)
+}
+
+/// Seconds until the queue drains: unfinished jobs times the average wall
+/// time of recent jobs, divided across the parallel slots. Coarse —
+/// inputs differ in length — but it's the headline number for an
+/// overnight batch.
+fn eta_to_drain(
+    unfinished: usize,
+    conversions: &VecDeque<PhaseSample>,
+    uploads: &VecDeque<PhaseSample>,
+    parallelism: usize,
+) -> Option<f64> {
+    if unfinished == 0 {
+        return Some(0.0);
+    }
+    if conversions.is_empty() {
+        return None;
+    }
+    let mean = |samples: &VecDeque<PhaseSample>| {
+        if samples.is_empty() {
+            0.0
+        } else {
+            samples.iter().map(|s| s.wall_seconds).sum::<f64>() / samples.len() as f64
+        }
+    };
+    let per_job = mean(conversions) + mean(uploads);
+    Some(unfinished as f64 * per_job / parallelism.max(1) as f64)
+}
+
+pub struct JobQueue {
+    inner: Mutex<Inner>,
+    stats: Mutex<SessionStats>,
+    permits: Arc<Semaphore>,
+    upload_permits: Arc<Semaphore>,
+    /// Manual upload pause (`set_uploads_paused`); the metered-connection
+    /// gate in the upload phase honors it alongside auto-detection.
+    uploads_paused: AtomicBool,
+}
+
+impl JobQueue {
+    pub fn new(max_concurrent_jobs: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                next_id: 1,
+                next_batch_id: 1,
+                jobs: Vec::new(),
+                cancel_flags: HashMap::new(),
+                upload_cancel_flags: HashMap::new(),
+                uploaded_keys: HashMap::new(),
+            }),
+            stats: Mutex::new(SessionStats::default()),
+            permits: Arc::new(Semaphore::new(max_concurrent_jobs)),
+            upload_permits: Arc::new(Semaphore::new(max_concurrent_jobs)),
+            uploads_paused: AtomicBool::new(false),
+        }
+    }
+
+    fn set_status(&self, app: &AppHandle, job_id: u64, status: JobStatus) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(job) = inner.jobs.iter_mut().find(|j| j.id == job_id) {
+            job.status = status;
+            if matches!(
+                job.status,
+                JobStatus::Completed
+                    | JobStatus::Failed { .. }
+                    | JobStatus::Cancelled
+                    | JobStatus::UploadCancelled
+            ) {
+                job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            }
+            let _ = app.emit(event_for_status(&job.status), job.clone());
+        }
+    }
+
+    fn record_conversion(&self, job_id: u64, result: &ffmpeg::ConversionResult) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(job) = inner.jobs.iter_mut().find(|j| j.id == job_id) {
+            job.encoder_used = Some(result.encoder_used.clone());
+            job.duration_seconds = Some(result.duration_seconds);
+            job.output_bytes = Some(result.total_bytes);
+        }
+    }
+
+    fn record_conversion_sample(&self, result: &ffmpeg::ConversionResult, wall_seconds: f64) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.bytes_converted += result.total_bytes;
+        push_sample(
+            &mut stats.conversions,
+            PhaseSample {
+                amount: result
+                    .average_frame_rate
+                    .map(|fps| fps * result.duration_seconds),
+                wall_seconds,
+            },
+        );
+    }
+
+    fn record_upload_sample(&self, bytes: u64, wall_seconds: f64) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.bytes_uploaded += bytes;
+        push_sample(
+            &mut stats.uploads,
+            PhaseSample {
+                amount: Some(bytes as f64),
+                wall_seconds,
+            },
+        );
+    }
+
+    fn next_batch_id(&self) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.next_batch_id;
+        inner.next_batch_id += 1;
+        id
+    }
+
+    fn record_failure(&self, app: &AppHandle, job_id: u64, phase: JobPhase, message: String) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(job) = inner.jobs.iter_mut().find(|j| j.id == job_id) {
+                job.failed_phase = Some(phase);
+            }
+        }
+        self.set_status(app, job_id, JobStatus::Failed { message });
+    }
+
+    fn record_uploaded_key(&self, job_id: u64, key: String) {
+        self.inner
+            .lock()
+            .unwrap()
+            .uploaded_keys
+            .entry(job_id)
+            .or_default()
+            .push(key);
+    }
+
+    fn job(&self, job_id: u64) -> Option<Job> {
+        self.inner
+            .lock()
+            .unwrap()
+            .jobs
+            .iter()
+            .find(|j| j.id == job_id)
+            .cloned()
+    }
+}
+
+/// Wait for a free slot, then claim and run whichever queued job has the
+/// highest priority at that moment. One dispatcher is spawned per added job,
+/// so every queued job eventually gets a turn.
+async fn dispatch_next(app: AppHandle) {
+    let queue = app.state::<JobQueue>();
+    let permits = queue.permits.clone();
+    let _permit = permits.acquire().await.expect("semaphore never closed");
+
+    let job_id = {
+        let mut inner = queue.inner.lock().unwrap();
+        match next_queued_index(&inner.jobs) {
+            Some(i) => {
+                // Claim the job while still holding the lock so no other
+                // dispatcher can pick the same one.
+                inner.jobs[i].status = JobStatus::Converting;
+                inner.jobs[i].id
+            }
+            // Everything queued was cancelled in the meantime.
+            None => return,
+        }
+    };
+    run_job(app, job_id).await;
+}
+
+/// Remove one job's conversion output directory, leaving every other job's
+/// output untouched. A missing directory is fine (nothing was written yet).
+fn remove_output_dir(settings: &Settings, movie_id: &str) -> std::io::Result<()> {
+    let dir = settings.output_dir.join(movie_id);
+    match std::fs::remove_dir_all(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Best-effort cleanup of a cancelled or failed job's temp artifacts
+/// (partial segments, playlists). Honors `cleanup_hls_temp_files`; failures
+/// are surfaced as a warning event, never a panic.
+fn cleanup_job_output(app: &AppHandle, settings: &Settings, movie_id: &str) {
+    if !settings.cleanup_hls_temp_files {
+        return;
+    }
+    if let Err(e) = remove_output_dir(settings, movie_id) {
+        let _ = app.emit(
+            "job-warning",
+            format!("failed to clean up output for {movie_id}: {e}"),
+        );
+    }
+}
+
+async fn run_job(app: AppHandle, job_id: u64) {
+    let queue = app.state::<JobQueue>();
+
+    let cancelled = {
+        let inner = queue.inner.lock().unwrap();
+        inner.cancel_flags.get(&job_id).cloned()
+    };
+    let cancelled = match cancelled {
+        Some(flag) => flag,
+        None => return, // job was removed
+    };
+    if cancelled.load(Ordering::SeqCst) {
+        queue.set_status(&app, job_id, JobStatus::Cancelled);
+        return;
+    }
+
+    let Some(job) = queue.job(job_id) else { return };
+    let settings = app.state::<SettingsStore>().get();
+
+    queue.set_status(&app, job_id, JobStatus::Converting);
+    let convert_started = std::time::Instant::now();
+    let out_dir =
+        match ffmpeg::convert(&app, &settings, &job.movie_id, &job.input_path, None, None, false)
+            .await
+    {
+        Ok(mut result) => {
+            result.job_id = Some(job_id);
+            queue.record_conversion(job_id, &result);
+            queue.record_conversion_sample(&result, convert_started.elapsed().as_secs_f64());
+            let _ = app.emit("job-converted", result.clone());
+            result.output_dir
+        }
+        Err(e) => {
+            queue.record_failure(&app, job_id, JobPhase::Converting, e.to_string());
+            cleanup_job_output(&app, &settings, &job.movie_id);
+            return;
+        }
+    };
+
+    if cancelled.load(Ordering::SeqCst) {
+        queue.set_status(&app, job_id, JobStatus::Cancelled);
+        cleanup_job_output(&app, &settings, &job.movie_id);
+        return;
+    }
+
+    // Hand off to the upload phase rather than awaiting it: returning here
+    // releases the conversion slot, so the dispatcher can start converting
+    // the next file while this one uploads.
+    tauri::async_runtime::spawn(run_upload_phase(app, job_id, out_dir));
+}
+
+/// How often the metered probe re-runs while uploading or paused; a D-Bus
+/// query per segment would be pure overhead.
+const METERED_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// How often a paused upload rechecks whether it may resume.
+const PAUSE_POLL_SECS: u64 = 5;
+
+/// Caches the metered probe between files.
+struct MeteredGate {
+    metered: bool,
+    last_check: Option<std::time::Instant>,
+}
+
+impl MeteredGate {
+    fn new() -> Self {
+        Self {
+            metered: false,
+            last_check: None,
+        }
+    }
+
+    async fn is_metered(&mut self) -> bool {
+        if self
+            .last_check
+            .is_none_or(|t| t.elapsed() >= METERED_CHECK_INTERVAL)
+        {
+            self.metered = r2::connection_is_metered().await == Some(true);
+            self.last_check = Some(std::time::Instant::now());
+        }
+        self.metered
+    }
+}
+
+/// Block while uploads are paused — manually via `set_uploads_paused`, or
+/// automatically while `pause_on_metered` and the platform report a metered
+/// connection. Emits `paused-metered` once on entry and `upload-resumed`
+/// when clear. Returns the cancellation status when one fires mid-wait.
+async fn wait_while_paused(
+    app: &AppHandle,
+    queue: &JobQueue,
+    settings: &Settings,
+    cancelled: &AtomicBool,
+    upload_cancelled: &AtomicBool,
+    gate: &mut MeteredGate,
+) -> Option<JobStatus> {
+    let manual = queue.uploads_paused.load(Ordering::SeqCst);
+    if !(manual || (settings.pause_on_metered && gate.is_metered().await)) {
+        return None;
+    }
+    let _ = app.emit(
+        "paused-metered",
+        if manual {
+            "uploads paused manually"
+        } else {
+            "metered connection detected; uploads paused"
+        },
+    );
+    loop {
+        // Sleep in one-second slices so a cancel takes effect promptly.
+        for _ in 0..PAUSE_POLL_SECS {
+            if cancelled.load(Ordering::SeqCst) {
+                return Some(JobStatus::Cancelled);
+            }
+            if upload_cancelled.load(Ordering::SeqCst) {
+                return Some(JobStatus::UploadCancelled);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+        if !(queue.uploads_paused.load(Ordering::SeqCst)
+            || (settings.pause_on_metered && gate.is_metered().await))
+        {
+            let _ = app.emit("upload-resumed", "connection no longer metered or pause lifted");
+            return None;
+        }
+    }
+}
+
+/// Manually pause or resume the upload phase of every job. Conversions are
+/// unaffected; running uploads finish their current file, then wait.
+#[tauri::command]
+pub fn set_uploads_paused(queue: State<'_, JobQueue>, paused: bool) -> Result<()> {
+    queue.uploads_paused.store(paused, Ordering::SeqCst);
+    Ok(())
+}
+
+/// The upload half of a job, separated from the conversion so a retry can
+/// re-run it alone against existing HLS output, and so `run_job` can hand
+/// off to it and free its conversion slot. Bounded by the queue's upload
+/// semaphore, independent of the conversion one.
+async fn run_upload_phase(app: AppHandle, job_id: u64, out_dir: PathBuf) {
+    let queue = app.state::<JobQueue>();
+    let _permit = queue
+        .upload_permits
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("semaphore never closed");
+    let Some(job) = queue.job(job_id) else { return };
+    let settings = app.state::<SettingsStore>().get();
+
+    let cancelled = {
+        let inner = queue.inner.lock().unwrap();
+        inner.cancel_flags.get(&job_id).cloned()
+    }
+    .unwrap_or_else(|| Arc::new(AtomicBool::new(false)));
+
+    // Don't waste the finished conversion if the network is down: wait (with
+    // backoff) until R2 is reachable or the user cancels.
+    if !r2::wait_for_connectivity(&app, &settings, &cancelled).await {
+        queue.set_status(&app, job_id, JobStatus::Cancelled);
+        cleanup_job_output(&app, &settings, &job.movie_id);
+        return;
+    }
+
+    let upload_cancelled = {
+        let inner = queue.inner.lock().unwrap();
+        inner.upload_cancel_flags.get(&job_id).cloned()
+    }
+    .unwrap_or_else(|| Arc::new(AtomicBool::new(false)));
+
+    queue.set_status(&app, job_id, JobStatus::Uploading);
+    let upload = async {
+        let mut settings = settings.clone();
+        let client = r2::client(&settings)?;
+        let files = r2::collect_files(&out_dir)?;
+        // A manifest-supplied object_prefix overrides the key template.
+        let mut prefix = job
+            .metadata
+            .as_ref()
+            .and_then(|m| m.object_prefix.as_deref())
+            .map(|p| p.trim_end_matches('/').to_string());
+        // Where the package lands is decided once, against the master
+        // playlist key, so segments, playlists and manifest shift together.
+        let mut slug = job.movie_id.clone();
+        let master_key = match &prefix {
+            Some(prefix) => format!("{prefix}/playlist.m3u8"),
+            None => r2::resolve_object_key(
+                &settings.object_key_template,
+                &slug,
+                job.metadata.as_ref().and_then(|m| m.year),
+                Path::new("playlist.m3u8"),
+            ),
+        };
+        if let Some((size, last_modified)) =
+            r2::head_existing(&client, &settings, &master_key).await?
+        {
+            let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string();
+            let base = prefix.clone().unwrap_or_else(|| slug.clone());
+            match r2::resolve_collision(settings.collision_strategy, &base, &timestamp, |_| true) {
+                r2::CollisionResolution::Keep => {}
+                r2::CollisionResolution::Overwrite => settings.overwrite_existing = true,
+                r2::CollisionResolution::Renamed(renamed) => match &mut prefix {
+                    Some(prefix) => *prefix = renamed,
+                    None => slug = renamed,
+                },
+                r2::CollisionResolution::Refused => {
+                    return Err(AppError::ObjectExists {
+                        key: master_key,
+                        size,
+                        last_modified,
+                    });
+                }
+            }
+        }
+        let mut gate = MeteredGate::new();
+        for (i, (relative, absolute)) in files.iter().enumerate() {
+            if cancelled.load(Ordering::SeqCst) {
+                return Ok(JobStatus::Cancelled);
+            }
+            if upload_cancelled.load(Ordering::SeqCst) {
+                return Ok(JobStatus::UploadCancelled);
+            }
+            if let Some(status) = wait_while_paused(
+                &app,
+                &queue,
+                &settings,
+                &cancelled,
+                &upload_cancelled,
+                &mut gate,
+            )
+            .await
+            {
+                return Ok(status);
+            }
+            let key = match &prefix {
+                Some(prefix) => {
+                    format!("{prefix}/{}", relative.to_string_lossy().replace('\\', "/"))
+                }
+                None => r2::resolve_object_key(
+                    &settings.object_key_template,
+                    &slug,
+                    job.metadata.as_ref().and_then(|m| m.year),
+                    relative,
+                ),
+            };
+            let outcome = r2::upload_file_cancellable(
+                &app,
+                &client,
+                &settings,
+                absolute,
+                &key,
+                &r2::UploadOptions::default(),
+                Some(&upload_cancelled),
+            )
+            .await?;
+            if outcome == r2::UploadOutcome::Cancelled {
+                return Ok(JobStatus::UploadCancelled);
+            }
+            // Only keys this job actually wrote are eligible for rollback;
+            // a skip means the object pre-existed and isn't ours to delete.
+            if outcome == r2::UploadOutcome::Uploaded {
+                queue.record_uploaded_key(job_id, key);
+            }
+            let _ = app.emit(
+                "job-progress",
+                JobProgress {
+                    job_id,
+                    files_done: i + 1,
+                    files_total: files.len(),
+                },
+            );
+        }
+        // Written last, so the manifest's presence implies a complete
+        // package; composed from the local output so a retried upload
+        // regenerates it identically.
+        let audio_tracks = ffmpeg::probe(&job.input_path)
+            .await
+            .map(|m| m.audio_tracks)
+            .unwrap_or_default();
+        let manifest = r2::build_manifest(
+            &job.movie_id,
+            job.metadata.as_ref().and_then(|m| m.title.clone()),
+            job.metadata.as_ref().and_then(|m| m.year),
+            audio_tracks,
+            &out_dir,
+        )
+        .await?;
+        let manifest_key = match &prefix {
+            Some(prefix) => format!("{prefix}/manifest.json"),
+            None => r2::resolve_object_key(
+                &settings.object_key_template,
+                &slug,
+                job.metadata.as_ref().and_then(|m| m.year),
+                Path::new("manifest.json"),
+            ),
+        };
+        r2::put_manifest(&client, &settings, &manifest_key, &manifest).await?;
+        queue.record_uploaded_key(job_id, manifest_key);
+        Ok::<_, AppError>(JobStatus::Completed)
+    };
+    let upload_started = std::time::Instant::now();
+    match upload.await {
+        Ok(JobStatus::Cancelled) => {
+            queue.set_status(&app, job_id, JobStatus::Cancelled);
+            cleanup_job_output(&app, &settings, &job.movie_id);
+        }
+        // The converted output is deliberately kept so the upload can be
+        // retried without re-encoding.
+        Ok(status) => {
+            if status == JobStatus::Completed {
+                queue.inner.lock().unwrap().uploaded_keys.remove(&job_id);
+                if let Some(bytes) = job.output_bytes.filter(|b| *b > 0) {
+                    queue.record_upload_sample(bytes, upload_started.elapsed().as_secs_f64());
+                }
+            }
+            queue.set_status(&app, job_id, status);
+        }
+        Err(e) => {
+            // Likewise kept on failure: an upload failure is usually
+            // transient (network, credentials) and retry_job reuses the
+            // output.
+            queue.record_failure(&app, job_id, JobPhase::Uploading, e.to_string());
+            // Let the UI offer deleting the partial package alongside the
+            // retry option.
+            let uploaded_objects = {
+                let inner = queue.inner.lock().unwrap();
+                inner.uploaded_keys.get(&job_id).map_or(0, Vec::len)
+            };
+            if uploaded_objects > 0 {
+                let _ = app.emit(
+                    "rollback-available",
+                    RollbackOffer {
+                        job_id,
+                        uploaded_objects,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Emitted on `rollback-available` when a job's upload phase fails with
+/// objects already written, so the frontend can offer `rollback_upload`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RollbackOffer {
+    pub job_id: u64,
+    pub uploaded_objects: usize,
+}
+
+/// Outcome of rolling back a failed upload.
+#[derive(Debug, Clone, Serialize)]
+pub struct RollbackResult {
+    pub job_id: u64,
+    pub deleted: usize,
+}
+
+/// Delete every object a failed job's upload phase wrote, leaving anything
+/// that pre-existed under the same prefix untouched (skipped uploads are
+/// never recorded as this job's). Clears the record afterwards, so a
+/// rollback is not repeatable.
+#[tauri::command]
+pub async fn rollback_upload(
+    store: State<'_, SettingsStore>,
+    queue: State<'_, JobQueue>,
+    job_id: u64,
+) -> Result<RollbackResult> {
+    let job = queue
+        .job(job_id)
+        .ok_or_else(|| AppError::Job(format!("no job with id {job_id}")))?;
+    if !matches!(job.status, JobStatus::Failed { .. } | JobStatus::UploadCancelled) {
+        return Err(AppError::Job(format!(
+            "job {job_id} has no failed upload to roll back"
+        )));
+    }
+    let keys = {
+        let inner = queue.inner.lock().unwrap();
+        inner.uploaded_keys.get(&job_id).cloned().unwrap_or_default()
+    };
+    let deleted = r2::delete_objects(&store.get(), &keys).await?;
+    queue.inner.lock().unwrap().uploaded_keys.remove(&job_id);
+    Ok(RollbackResult { job_id, deleted })
+}
+
+/// Queue a movie for conversion and upload. Returns the new job id.
+#[tauri::command]
+pub async fn add_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    movie_id: String,
+    input_path: PathBuf,
+    priority: Option<u8>,
+) -> Result<u64> {
+    let input_path = crate::paths::normalize(&input_path.to_string_lossy())?;
+    if !input_path.is_file() {
+        return Err(AppError::InvalidInput(format!(
+            "{} is not a file",
+            input_path.display()
+        )));
+    }
+    Ok(enqueue(&app, &queue, movie_id, input_path, priority.unwrap_or(0), None, None))
+}
+
+/// Register a job and spawn a dispatcher for it.
+#[allow(clippy::too_many_arguments)]
+fn enqueue(
+    app: &AppHandle,
+    queue: &JobQueue,
+    movie_id: String,
+    input_path: PathBuf,
+    priority: u8,
+    metadata: Option<JobMetadata>,
+    batch_id: Option<u64>,
+) -> u64 {
+    let job_id = {
+        let mut inner = queue.inner.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        let job = Job {
+            id,
+            movie_id,
+            input_path,
+            priority,
+            status: JobStatus::Queued,
+            retries: 0,
+            failed_phase: None,
+            metadata,
+            batch_id,
+            enqueued_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            encoder_used: None,
+            duration_seconds: None,
+            output_bytes: None,
+        };
+        let _ = app.emit("job-enqueued", job.clone());
+        inner.jobs.push(job);
+        inner.cancel_flags.insert(id, Arc::new(AtomicBool::new(false)));
+        inner
+            .upload_cancel_flags
+            .insert(id, Arc::new(AtomicBool::new(false)));
+        id
+    };
+    tauri::async_runtime::spawn(dispatch_next(app.clone()));
+    job_id
+}
+
+/// One input of a batch submission.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchItem {
+    pub movie_id: String,
+    pub input_path: PathBuf,
+}
+
+/// Result of a batch submission: queued job ids, or — with `dry_run` — the
+/// full plan of what would have run, without touching ffmpeg or R2.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchSubmission {
+    pub job_ids: Vec<u64>,
+    /// Set when jobs were actually queued; `export_job_report` takes it.
+    pub batch_id: Option<u64>,
+    pub plans: Vec<ffmpeg::ConversionPlan>,
+}
+
+/// Queue a whole batch for conversion and upload. With `dry_run: true`,
+/// nothing is queued; instead every input is validated and probed and the
+/// exact ffmpeg commands and target object keys are returned for review.
+#[tauri::command]
+pub async fn convert_and_upload_batch(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    store: State<'_, SettingsStore>,
+    items: Vec<BatchItem>,
+    dry_run: Option<bool>,
+) -> Result<BatchSubmission> {
+    for item in &items {
+        if !item.input_path.is_file() {
+            return Err(AppError::InvalidInput(format!(
+                "{} is not a file",
+                item.input_path.display()
+            )));
+        }
+    }
+
+    let mut submission = BatchSubmission::default();
+    if dry_run.unwrap_or(false) {
+        let settings = store.get();
+        for item in &items {
+            submission
+                .plans
+                .push(ffmpeg::plan_conversion(&settings, &item.movie_id, &item.input_path).await?);
+        }
+    } else {
+        let batch_id = queue.next_batch_id();
+        submission.batch_id = Some(batch_id);
+        for item in items {
+            submission.job_ids.push(enqueue(
+                &app,
+                &queue,
+                item.movie_id,
+                item.input_path,
+                0,
+                None,
+                Some(batch_id),
+            ));
+        }
+    }
+    Ok(submission)
+}
+
+/// One row of an import manifest, before validation.
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestRow {
+    input_path: PathBuf,
+    title: Option<String>,
+    year: Option<u32>,
+    object_prefix: Option<String>,
+}
+
+/// A manifest row the import skipped, with its 1-based row number (counting
+/// the CSV header as row 1) and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedRow {
+    pub row: usize,
+    pub error: String,
+}
+
+/// Outcome of a batch import: what was queued and what was skipped.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub job_ids: Vec<u64>,
+    /// The batch the queued jobs belong to, for `export_job_report`.
+    pub batch_id: Option<u64>,
+    pub accepted: usize,
+    pub rejected: Vec<RejectedRow>,
+}
+
+/// Split one CSV line into fields, honoring double-quoted fields with
+/// doubled quotes as escapes — enough for titles containing commas.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Derive a URL-safe movie id from a row's title and year, falling back to
+/// the input's file stem when no title was given.
+fn movie_id_for_row(row: &ManifestRow) -> String {
+    let base = match (&row.title, row.year) {
+        (Some(title), Some(year)) => format!("{title} {year}"),
+        (Some(title), None) => title.clone(),
+        _ => row
+            .input_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "import".into()),
+    };
+    let mut slug = String::new();
+    for c in base.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// Parse a manifest into rows, each independently fallible so one bad row
+/// doesn't poison the rest. CSV needs a header naming at least `input_path`;
+/// JSON is an array of objects with the same fields.
+fn parse_manifest(
+    manifest_path: &std::path::Path,
+    contents: &str,
+) -> Result<Vec<std::result::Result<ManifestRow, String>>> {
+    match manifest_path.extension().and_then(|e| e.to_str()) {
+        Some("json") => {
+            let rows: Vec<serde_json::Value> = serde_json::from_str(contents)
+                .map_err(|e| AppError::InvalidInput(format!("unparseable JSON manifest: {e}")))?;
+            Ok(rows
+                .into_iter()
+                .map(|row| serde_json::from_value(row).map_err(|e| e.to_string()))
+                .collect())
+        }
+        Some("csv") => {
+            let mut lines = contents.lines();
+            let header: Vec<String> = lines
+                .next()
+                .map(split_csv_line)
+                .unwrap_or_default()
+                .iter()
+                .map(|h| h.trim().to_string())
+                .collect();
+            let column = |name: &str| header.iter().position(|h| h == name);
+            let input_path_col = column("input_path").ok_or_else(|| {
+                AppError::InvalidInput("CSV manifest header must include input_path".into())
+            })?;
+            let (title_col, year_col, prefix_col) =
+                (column("title"), column("year"), column("object_prefix"));
+
+            let field = |fields: &[String], col: Option<usize>| {
+                col.and_then(|c| fields.get(c))
+                    .map(|f| f.trim().to_string())
+                    .filter(|f| !f.is_empty())
+            };
+            Ok(lines
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| {
+                    let fields = split_csv_line(line);
+                    let input_path = field(&fields, Some(input_path_col))
+                        .ok_or_else(|| "missing input_path".to_string())?;
+                    let year = match field(&fields, year_col) {
+                        Some(year) => Some(
+                            year.parse()
+                                .map_err(|_| format!("unparseable year {year:?}"))?,
+                        ),
+                        None => None,
+                    };
+                    Ok(ManifestRow {
+                        input_path: PathBuf::from(input_path),
+                        title: field(&fields, title_col),
+                        year,
+                        object_prefix: field(&fields, prefix_col),
+                    })
+                })
+                .collect())
+        }
+        other => Err(AppError::InvalidInput(format!(
+            "unsupported manifest extension {other:?}; use .csv or .json"
+        ))),
+    }
+}
+
+/// Queue one job per row of a CSV or JSON manifest (columns: `input_path`,
+/// `title`, `year`, `object_prefix`), attaching the row's metadata to the
+/// job. Rows that fail validation are reported individually; the rest still
+/// import. Complements the per-file UI for library migrations.
+#[tauri::command]
+pub async fn import_batch(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    manifest_path: PathBuf,
+) -> Result<ImportSummary> {
+    let contents = tokio::fs::read_to_string(&manifest_path).await?;
+    let rows = parse_manifest(&manifest_path, &contents)?;
+
+    let mut summary = ImportSummary::default();
+    let batch_id = queue.next_batch_id();
+    summary.batch_id = Some(batch_id);
+    for (i, row) in rows.into_iter().enumerate() {
+        // Row 1 is the CSV header; JSON manifests just skip 1.
+        let row_number = i + 2;
+        let mut reject = |error: String| {
+            summary.rejected.push(RejectedRow {
+                row: row_number,
+                error,
+            })
+        };
+        let row = match row {
+            Ok(row) => row,
+            Err(error) => {
+                reject(error);
+                continue;
+            }
+        };
+        if !row.input_path.is_file() {
+            reject(format!("{} is not a file", row.input_path.display()));
+            continue;
+        }
+        if let Some(year) = row.year {
+            if !(1880..=2100).contains(&year) {
+                reject(format!("implausible year {year}"));
+                continue;
+            }
+        }
+        let movie_id = movie_id_for_row(&row);
+        if movie_id.is_empty() {
+            reject("row yields an empty movie id".into());
+            continue;
+        }
+        let metadata = JobMetadata {
+            title: row.title,
+            year: row.year,
+            object_prefix: row.object_prefix,
+        };
+        summary.job_ids.push(enqueue(
+            &app,
+            &queue,
+            movie_id,
+            row.input_path,
+            0,
+            Some(metadata),
+            Some(batch_id),
+        ));
+        summary.accepted += 1;
+    }
+    Ok(summary)
+}
+
+/// Output format for `export_job_report`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+/// Human-readable status for report rows; the failure message travels in
+/// its own column.
+fn status_label(status: &JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued => "queued",
+        JobStatus::Converting => "converting",
+        JobStatus::Uploading => "uploading",
+        JobStatus::Completed => "completed",
+        JobStatus::Failed { .. } => "failed",
+        JobStatus::Cancelled => "cancelled",
+        JobStatus::UploadCancelled => "upload_cancelled",
+    }
+}
+
+/// Quote a CSV field when it needs it (commas, quotes, newlines), doubling
+/// embedded quotes — the inverse of `split_csv_line`.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The CSV form of a job report, one row per job.
+fn report_csv(jobs: &[Job]) -> String {
+    let mut out = String::from(
+        "job_id,movie_id,input_path,status,error,encoder,duration_seconds,output_bytes,\
+         object_prefix,retries,enqueued_at,finished_at\n",
+    );
+    for job in jobs {
+        let error = match &job.status {
+            JobStatus::Failed { message } => message.as_str(),
+            _ => "",
+        };
+        let fields = [
+            job.id.to_string(),
+            job.movie_id.clone(),
+            job.input_path.to_string_lossy().into_owned(),
+            status_label(&job.status).to_string(),
+            error.to_string(),
+            job.encoder_used.clone().unwrap_or_default(),
+            job.duration_seconds.map(|d| format!("{d:.3}")).unwrap_or_default(),
+            job.output_bytes.map(|b| b.to_string()).unwrap_or_default(),
+            job.metadata
+                .as_ref()
+                .and_then(|m| m.object_prefix.clone())
+                .unwrap_or_default(),
+            job.retries.to_string(),
+            job.enqueued_at.clone(),
+            job.finished_at.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Write a report of every job in a batch — status, encoder, duration,
+/// output size, prefix, errors, timestamps — to `path`, as JSON or CSV, and
+/// return the written path. Meant to be run after the batch settles, but
+/// works mid-flight too (in-progress jobs simply show their current state).
+#[tauri::command]
+pub async fn export_job_report(
+    queue: State<'_, JobQueue>,
+    batch_id: u64,
+    format: ReportFormat,
+    path: PathBuf,
+) -> Result<PathBuf> {
+    let jobs: Vec<Job> = {
+        let inner = queue.inner.lock().unwrap();
+        inner
+            .jobs
+            .iter()
+            .filter(|j| j.batch_id == Some(batch_id))
+            .cloned()
+            .collect()
+    };
+    if jobs.is_empty() {
+        return Err(AppError::Job(format!("no jobs belong to batch {batch_id}")));
+    }
+    let contents = match format {
+        ReportFormat::Json => {
+            serde_json::to_string_pretty(&jobs).expect("jobs always serialize")
+        }
+        ReportFormat::Csv => report_csv(&jobs),
+    };
+    tokio::fs::write(&path, contents).await?;
+    Ok(path)
+}
+
+/// Rearrange the Queued portion of the queue to match `ordered_ids`
+/// (drag-to-reorder in the UI). Ids that are already running or finished are
+/// ignored; unknown ids are an error. Queued jobs not mentioned keep their
+/// current relative order after the reordered ones. The next dispatch picks
+/// from the updated order.
+#[tauri::command]
+pub fn reorder_queue(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    ordered_ids: Vec<u64>,
+) -> Result<()> {
+    let mut inner = queue.inner.lock().unwrap();
+    for id in &ordered_ids {
+        if !inner.jobs.iter().any(|j| j.id == *id) {
+            return Err(AppError::Job(format!("no job with id {id}")));
+        }
+    }
+
+    let queued_slots: Vec<usize> = inner
+        .jobs
+        .iter()
+        .enumerate()
+        .filter(|(_, j)| j.status == JobStatus::Queued)
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut desired: Vec<Job> = Vec::with_capacity(queued_slots.len());
+    for id in &ordered_ids {
+        if let Some(job) = inner
+            .jobs
+            .iter()
+            .find(|j| j.id == *id && j.status == JobStatus::Queued)
+        {
+            if !desired.iter().any(|d| d.id == job.id) {
+                desired.push(job.clone());
+            }
+        }
+    }
+    for slot in &queued_slots {
+        let job = &inner.jobs[*slot];
+        if !desired.iter().any(|d| d.id == job.id) {
+            desired.push(job.clone());
+        }
+    }
+    for (slot, job) in queued_slots.iter().zip(desired) {
+        inner.jobs[*slot] = job;
+    }
+
+    let _ = app.emit("queue-reordered", inner.jobs.clone());
+    Ok(())
+}
+
+/// Bump (or drop) a queued job's priority. Running jobs keep running; only
+/// the order of not-yet-dispatched jobs changes.
+#[tauri::command]
+pub fn set_job_priority(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    job_id: u64,
+    priority: u8,
+) -> Result<()> {
+    let mut inner = queue.inner.lock().unwrap();
+    let job = inner
+        .jobs
+        .iter_mut()
+        .find(|j| j.id == job_id)
+        .ok_or_else(|| AppError::Job(format!("no job with id {job_id}")))?;
+    if job.status != JobStatus::Queued {
+        return Err(AppError::Job(format!(
+            "job {job_id} is no longer queued; priority only affects queued jobs"
+        )));
+    }
+    job.priority = priority;
+    let _ = app.emit("job-updated", job.clone());
+    Ok(())
+}
+
+/// Flag a job as cancelled; running ffmpeg finishes its current rendition but
+/// nothing further is converted or uploaded.
+#[tauri::command]
+pub fn cancel_job(app: AppHandle, queue: State<'_, JobQueue>, job_id: u64) -> Result<()> {
+    let inner = queue.inner.lock().unwrap();
+    let flag = inner
+        .cancel_flags
+        .get(&job_id)
+        .ok_or_else(|| AppError::Job(format!("no job with id {job_id}")))?;
+    flag.store(true, Ordering::SeqCst);
+    drop(inner);
+    if let Some(job) = queue.job(job_id) {
+        if job.status == JobStatus::Queued {
+            queue.set_status(&app, job_id, JobStatus::Cancelled);
+        }
+    }
+    Ok(())
+}
+
+/// Cap on `retry_job` attempts per job, so a deterministic failure doesn't
+/// loop forever.
+const MAX_RETRIES: u32 = 3;
+
+/// Re-run a failed job from the phase that failed: a conversion failure
+/// requeues the whole job, while an upload failure (or a cancelled upload)
+/// re-runs only the upload against the HLS output still on disk. Returns
+/// the job's new status.
+#[tauri::command]
+pub async fn retry_job(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    queue: State<'_, JobQueue>,
+    job_id: u64,
+) -> Result<JobStatus> {
+    let job = queue
+        .job(job_id)
+        .ok_or_else(|| AppError::Job(format!("no job with id {job_id}")))?;
+    match job.status {
+        JobStatus::Failed { .. } | JobStatus::UploadCancelled => {}
+        _ => {
+            return Err(AppError::Job(format!(
+                "job {job_id} has not failed; only failed or upload-cancelled jobs can be retried"
+            )));
+        }
+    }
+    if job.retries >= MAX_RETRIES {
+        return Err(AppError::Job(format!(
+            "job {job_id} already retried {MAX_RETRIES} times"
+        )));
+    }
+
+    let settings = store.get();
+    let out_dir = settings.output_dir.join(&job.movie_id);
+    // Upload-only retry needs output to reuse; if cleanup removed it, fall
+    // back to a full re-run.
+    let upload_only = (job.status == JobStatus::UploadCancelled
+        || job.failed_phase == Some(JobPhase::Uploading))
+        && out_dir.join("playlist.m3u8").is_file();
+
+    {
+        let mut inner = queue.inner.lock().unwrap();
+        if let Some(flag) = inner.cancel_flags.get(&job_id) {
+            flag.store(false, Ordering::SeqCst);
+        }
+        if let Some(flag) = inner.upload_cancel_flags.get(&job_id) {
+            flag.store(false, Ordering::SeqCst);
+        }
+        if let Some(job) = inner.jobs.iter_mut().find(|j| j.id == job_id) {
+            job.retries += 1;
+            job.failed_phase = None;
+        }
+    }
+
+    if upload_only {
+        tauri::async_runtime::spawn(run_upload_phase(app.clone(), job_id, out_dir));
+        Ok(JobStatus::Uploading)
+    } else {
+        queue.set_status(&app, job_id, JobStatus::Queued);
+        tauri::async_runtime::spawn(dispatch_next(app.clone()));
+        Ok(JobStatus::Queued)
+    }
+}
+
+/// What `cancel_upload` stopped, for UI confirmation.
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelledUpload {
+    pub job_id: u64,
+    /// The local conversion output that was kept for a retry.
+    pub output_dir: PathBuf,
+}
+
+/// Stop only a job's upload phase, e.g. to fix credentials. Any in-flight
+/// multipart upload is aborted server-side, the job moves to
+/// `UploadCancelled`, and — unlike [`cancel_job`] — the converted HLS
+/// output stays on disk so the upload can be retried without re-encoding.
+#[tauri::command]
+pub fn cancel_upload(
+    store: State<'_, SettingsStore>,
+    queue: State<'_, JobQueue>,
+    job_id: u64,
+) -> Result<CancelledUpload> {
+    let job = queue
+        .job(job_id)
+        .ok_or_else(|| AppError::Job(format!("no job with id {job_id}")))?;
+    if job.status != JobStatus::Uploading {
+        return Err(AppError::Job(format!(
+            "job {job_id} is not uploading; use cancel_job for other phases"
+        )));
+    }
+    let inner = queue.inner.lock().unwrap();
+    let flag = inner
+        .upload_cancel_flags
+        .get(&job_id)
+        .ok_or_else(|| AppError::Job(format!("no job with id {job_id}")))?;
+    flag.store(true, Ordering::SeqCst);
+    Ok(CancelledUpload {
+        job_id,
+        output_dir: store.get().output_dir.join(&job.movie_id),
+    })
+}
+
+#[tauri::command]
+pub fn list_jobs(queue: State<'_, JobQueue>) -> Vec<Job> {
+    queue.inner.lock().unwrap().jobs.clone()
+}
+
+/// Job tallies per status.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatusCounts {
+    pub queued: usize,
+    pub converting: usize,
+    pub uploading: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    pub upload_cancelled: usize,
+}
+
+fn status_counts(jobs: &[Job]) -> StatusCounts {
+    let mut counts = StatusCounts::default();
+    for job in jobs {
+        match &job.status {
+            JobStatus::Queued => counts.queued += 1,
+            JobStatus::Converting => counts.converting += 1,
+            JobStatus::Uploading => counts.uploading += 1,
+            JobStatus::Completed => counts.completed += 1,
+            JobStatus::Failed { .. } => counts.failed += 1,
+            JobStatus::Cancelled => counts.cancelled += 1,
+            JobStatus::UploadCancelled => counts.upload_cancelled += 1,
+        }
+    }
+    counts
+}
+
+/// Dashboard-level view of the queue, distinct from per-job status.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStats {
+    pub counts: StatusCounts,
+    /// Mean encode rate over the last [`RECENT_SAMPLE_JOBS`] finished
+    /// conversions; None until one finishes with a known frame rate.
+    pub average_conversion_fps: Option<f64>,
+    /// Mean upload rate over the recent finished uploads, pauses included.
+    pub average_upload_mbps: Option<f64>,
+    pub session_bytes_converted: u64,
+    pub session_bytes_uploaded: u64,
+    /// Seconds until the queue drains at current throughput; None before
+    /// any conversion has finished this session.
+    pub eta_to_drain_seconds: Option<f64>,
+}
+
+/// Queue statistics and recent throughput, for a stats dashboard over
+/// large overnight batches.
+#[tauri::command]
+pub fn get_queue_stats(
+    store: State<'_, SettingsStore>,
+    queue: State<'_, JobQueue>,
+) -> Result<QueueStats> {
+    let counts = status_counts(&queue.inner.lock().unwrap().jobs);
+    let stats = queue.stats.lock().unwrap();
+    let unfinished = counts.queued + counts.converting + counts.uploading;
+    Ok(QueueStats {
+        average_conversion_fps: average_rate(&stats.conversions),
+        average_upload_mbps: average_rate(&stats.uploads).map(|bps| bps * 8.0 / 1_000_000.0),
+        session_bytes_converted: stats.bytes_converted,
+        session_bytes_uploaded: stats.bytes_uploaded,
+        eta_to_drain_seconds: eta_to_drain(
+            unfinished,
+            &stats.conversions,
+            &stats.uploads,
+            store.get().max_concurrent_jobs,
+        ),
+        counts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: u64, priority: u8, status: JobStatus) -> Job {
+        Job {
+            id,
+            movie_id: format!("movie-{id}"),
+            input_path: PathBuf::from("/tmp/in.mp4"),
+            priority,
+            status,
+            retries: 0,
+            failed_phase: None,
+            metadata: None,
+            batch_id: None,
+            enqueued_at: "2026-01-01T00:00:00+00:00".into(),
+            finished_at: None,
+            encoder_used: None,
+            duration_seconds: None,
+            output_bytes: None,
+        }
+    }
+
+    #[test]
+    fn csv_report_carries_outcomes_and_escapes_fields() {
+        let mut done = job(1, 0, JobStatus::Completed);
+        done.encoder_used = Some("libx264".into());
+        done.duration_seconds = Some(5400.0);
+        done.output_bytes = Some(123_456);
+        let failed = job(
+            2,
+            0,
+            JobStatus::Failed {
+                message: "put failed, sadly".into(),
+            },
+        );
+
+        let csv = report_csv(&[done, failed]);
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("job_id,movie_id,"));
+        let first = lines.next().unwrap();
+        assert!(first.contains("completed"));
+        assert!(first.contains("libx264"));
+        assert!(first.contains("5400.000"));
+        // The failure message contains a comma, so it must be quoted.
+        assert!(lines.next().unwrap().contains("\"put failed, sadly\""));
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a \"b\""), "\"a \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn high_priority_job_dispatches_before_earlier_low_priority_jobs() {
+        let jobs = vec![
+            job(1, 0, JobStatus::Queued),
+            job(2, 0, JobStatus::Queued),
+            job(3, 5, JobStatus::Queued),
+        ];
+        assert_eq!(next_queued_index(&jobs), Some(2));
+    }
+
+    #[test]
+    fn throughput_averages_and_drain_eta_come_from_recent_samples() {
+        let mut conversions = VecDeque::new();
+        // 2400 frames in 100s plus 3600 frames in 100s → 30 fps overall.
+        push_sample(
+            &mut conversions,
+            PhaseSample { amount: Some(2400.0), wall_seconds: 100.0 },
+        );
+        push_sample(
+            &mut conversions,
+            PhaseSample { amount: Some(3600.0), wall_seconds: 100.0 },
+        );
+        assert_eq!(average_rate(&conversions), Some(30.0));
+        // A sample without a frame count doesn't drag the rate down.
+        push_sample(
+            &mut conversions,
+            PhaseSample { amount: None, wall_seconds: 500.0 },
+        );
+        assert_eq!(average_rate(&conversions), Some(30.0));
+        assert_eq!(average_rate(&VecDeque::new()), None);
+
+        // ETA: (300s convert + 100s upload) per job, 4 jobs over 2 slots.
+        let conv: VecDeque<_> =
+            [PhaseSample { amount: None, wall_seconds: 300.0 }].into_iter().collect();
+        let up: VecDeque<_> =
+            [PhaseSample { amount: Some(1.0), wall_seconds: 100.0 }].into_iter().collect();
+        assert_eq!(eta_to_drain(4, &conv, &up, 2), Some(800.0));
+        assert_eq!(eta_to_drain(0, &conv, &up, 2), Some(0.0));
+        assert_eq!(eta_to_drain(3, &VecDeque::new(), &up, 2), None);
+
+        // The look-back window stays bounded.
+        let mut window = VecDeque::new();
+        for _ in 0..(RECENT_SAMPLE_JOBS + 5) {
+            push_sample(&mut window, PhaseSample { amount: Some(1.0), wall_seconds: 1.0 });
+        }
+        assert_eq!(window.len(), RECENT_SAMPLE_JOBS);
+
+        let jobs = vec![
+            job(1, 0, JobStatus::Queued),
+            job(2, 0, JobStatus::Completed),
+            job(3, 0, JobStatus::Failed { message: "x".into() }),
+        ];
+        let counts = status_counts(&jobs);
+        assert_eq!((counts.queued, counts.completed, counts.failed), (1, 1, 1));
+    }
+
+    #[test]
+    fn equal_priorities_dispatch_in_insertion_order() {
+        let jobs = vec![
+            job(1, 1, JobStatus::Completed),
+            job(2, 1, JobStatus::Queued),
+            job(3, 1, JobStatus::Queued),
+        ];
+        assert_eq!(next_queued_index(&jobs), Some(1));
+    }
+
+    #[test]
+    fn cancelled_job_cleanup_removes_only_its_own_directory() {
+        let base = std::env::temp_dir().join(format!("uploader-cleanup-test-{}", std::process::id()));
+        let settings = Settings {
+            output_dir: base.clone(),
+            ..Settings::default()
+        };
+        std::fs::create_dir_all(base.join("movie-a/480p")).unwrap();
+        std::fs::create_dir_all(base.join("movie-b/480p")).unwrap();
+
+        remove_output_dir(&settings, "movie-a").unwrap();
+        assert!(!base.join("movie-a").exists());
+        assert!(base.join("movie-b").exists());
+
+        // Idempotent: a second cleanup of the same job is not an error.
+        remove_output_dir(&settings, "movie-a").unwrap();
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn csv_fields_honor_quoting() {
+        assert_eq!(split_csv_line("a,b,c"), vec!["a", "b", "c"]);
+        assert_eq!(
+            split_csv_line(r#"/in/movie.mkv,"Comma, The",1999"#),
+            vec!["/in/movie.mkv", "Comma, The", "1999"]
+        );
+        assert_eq!(split_csv_line(r#""say ""hi""""#), vec![r#"say "hi""#]);
+    }
+
+    #[test]
+    fn movie_ids_slug_from_title_or_file_stem() {
+        let row = |title: Option<&str>, year| ManifestRow {
+            input_path: PathBuf::from("/in/The_Matrix (1999).mkv"),
+            title: title.map(String::from),
+            year,
+            object_prefix: None,
+        };
+        assert_eq!(movie_id_for_row(&row(Some("The Matrix"), Some(1999))), "the-matrix-1999");
+        assert_eq!(movie_id_for_row(&row(Some("The Matrix"), None)), "the-matrix");
+        assert_eq!(movie_id_for_row(&row(None, None)), "the-matrix-1999");
+    }
+
+    #[tokio::test]
+    async fn upload_slot_in_use_does_not_block_the_next_conversion() {
+        // With max_concurrent_jobs = 1, file two's conversion must still be
+        // able to claim its slot while file one's upload occupies the upload
+        // semaphore — that overlap is the whole point of the pipelining.
+        let queue = JobQueue::new(1);
+        let _uploading = queue
+            .upload_permits
+            .clone()
+            .acquire_owned()
+            .await
+            .unwrap();
+        let converting = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            queue.permits.clone().acquire_owned(),
+        )
+        .await;
+        assert!(converting.is_ok());
+    }
+
+    #[test]
+    fn non_queued_jobs_are_never_selected() {
+        let jobs = vec![
+            job(1, 9, JobStatus::Converting),
+            job(2, 9, JobStatus::Cancelled),
+        ];
+        assert_eq!(next_queued_index(&jobs), None);
+    }
+}
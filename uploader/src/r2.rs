@@ -0,0 +1,3724 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::AsyncReadExt;
+
+use crate::error::{AppError, Result};
+use crate::settings::{S3Target, Settings, SettingsStore};
+
+/// Whether a file was actually transferred, found to already be in R2, or
+/// abandoned because its upload was cancelled mid-flight.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadOutcome {
+    Uploaded,
+    Skipped,
+    Cancelled,
+}
+
+/// Emitted on the `upload-progress` channel while bytes move to R2.
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgress {
+    pub key: String,
+    pub bytes_uploaded: u64,
+    pub total_bytes: u64,
+}
+
+/// Build an S3 client pointed at the account's R2 endpoint.
+pub fn client(settings: &Settings) -> Result<Client> {
+    if settings.r2_account_id.is_empty() || settings.r2_access_key_id.is_empty() {
+        return Err(AppError::Settings("R2 credentials are not configured".into()));
+    }
+    let credentials = Credentials::new(
+        &settings.r2_access_key_id,
+        &settings.r2_secret_access_key,
+        None,
+        None,
+        "settings",
+    );
+    let config = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new("auto"))
+        .endpoint_url(format!(
+            "https://{}.r2.cloudflarestorage.com",
+            settings.r2_account_id
+        ))
+        .credentials_provider(credentials)
+        .build();
+    Ok(Client::from_conf(config))
+}
+
+/// Build an S3 client for a mirror target's own endpoint and credentials.
+pub fn target_client(target: &S3Target) -> Result<Client> {
+    if target.endpoint.is_empty() || target.bucket.is_empty() {
+        return Err(AppError::Settings(
+            "mirror target is missing an endpoint or bucket".into(),
+        ));
+    }
+    let credentials = Credentials::new(
+        &target.access_key_id,
+        &target.secret_access_key,
+        None,
+        None,
+        "mirror",
+    );
+    let config = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new("auto"))
+        .endpoint_url(&target.endpoint)
+        .credentials_provider(credentials)
+        .build();
+    Ok(Client::from_conf(config))
+}
+
+/// Map a file extension to the Content-Type R2 should serve it with.
+/// Unknown extensions fall back to `application/octet-stream`.
+pub fn guess_content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("m3u8") => "application/vnd.apple.mpegurl",
+        Some("ts") => "video/mp2t",
+        Some("m4s") => "video/iso.segment",
+        Some("mp4") => "video/mp4",
+        Some("mpd") => "application/dash+xml",
+        Some("vtt") => "text/vtt",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Per-upload extras callers can attach on top of the settings-driven
+/// defaults.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct UploadOptions {
+    /// Overrides the extension-based Content-Type guess.
+    pub content_type: Option<String>,
+    /// Sent as `x-amz-meta-*` user metadata (e.g. movie id/title).
+    pub metadata: Option<HashMap<String, String>>,
+    /// Applied as object tags (e.g. `tier=archive`) for lifecycle rules
+    /// and cost allocation. At most 10 per the S3 limit.
+    pub tags: Option<HashMap<String, String>>,
+}
+
+/// S3 limits user metadata to 2KB total; keys must be ASCII to survive the
+/// `x-amz-meta-` header encoding.
+fn validate_metadata(metadata: &HashMap<String, String>) -> Result<()> {
+    let mut total = 0;
+    for (key, value) in metadata {
+        if !key.is_ascii() {
+            return Err(AppError::InvalidInput(format!(
+                "metadata key {key:?} is not ASCII"
+            )));
+        }
+        total += key.len() + value.len();
+    }
+    if total > 2048 {
+        return Err(AppError::InvalidInput(format!(
+            "metadata totals {total} bytes, exceeding the 2KB S3 limit"
+        )));
+    }
+    Ok(())
+}
+
+/// S3 allows at most this many tags on one object.
+const MAX_OBJECT_TAGS: usize = 10;
+
+/// Characters S3 accepts in tag keys and values besides letters and digits.
+const TAG_EXTRA_CHARS: &str = " +-=._:/@";
+
+/// Enforce the S3 tag restrictions: at most [`MAX_OBJECT_TAGS`] tags, keys
+/// up to 128 characters, values up to 256, restricted to letters, digits
+/// and `+ - = . _ : / @` (plus space).
+fn validate_tags(tags: &HashMap<String, String>) -> Result<()> {
+    if tags.len() > MAX_OBJECT_TAGS {
+        return Err(AppError::InvalidInput(format!(
+            "{} tags given, but S3 allows at most {MAX_OBJECT_TAGS} per object",
+            tags.len()
+        )));
+    }
+    for (key, value) in tags {
+        if key.is_empty() {
+            return Err(AppError::InvalidInput("tag keys must not be empty".into()));
+        }
+        if key.chars().count() > 128 {
+            return Err(AppError::InvalidInput(format!(
+                "tag key {key:?} exceeds the 128-character S3 limit"
+            )));
+        }
+        if value.chars().count() > 256 {
+            return Err(AppError::InvalidInput(format!(
+                "tag value for {key:?} exceeds the 256-character S3 limit"
+            )));
+        }
+        if let Some(c) = key
+            .chars()
+            .chain(value.chars())
+            .find(|c| !c.is_alphanumeric() && !TAG_EXTRA_CHARS.contains(*c))
+        {
+            return Err(AppError::InvalidInput(format!(
+                "tag {key:?} contains {c:?}; S3 tags allow letters, digits, \
+                 space and `+ - = . _ : / @`"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The url-encoded `key=value&…` form the `Tagging` request parameter
+/// expects. Pairs are sorted by key so the output is stable.
+fn tagging_query(tags: &HashMap<String, String>) -> String {
+    fn encode(s: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        for b in s.bytes() {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                    out.push(b as char)
+                }
+                _ => out.push_str(&format!("%{b:02X}")),
+            }
+        }
+        out
+    }
+    let mut pairs: Vec<_> = tags.iter().collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", encode(k), encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Cache-Control value for `path`, if it's an asset type the CDN serves.
+/// Segments never change once published so they're marked immutable;
+/// manifests get a short max-age since re-encodes replace them in place.
+pub fn cache_control_for(path: &Path, settings: &Settings) -> Option<String> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("ts") | Some("m4s") | Some("mp4") => Some(format!(
+            "public, max-age={}, immutable",
+            settings.segment_cache_max_age
+        )),
+        Some("m3u8") | Some("mpd") => {
+            Some(format!("public, max-age={}", settings.playlist_cache_max_age))
+        }
+        _ => None,
+    }
+}
+
+/// Digest algorithms `compute_checksum` speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha256,
+}
+
+/// Hex digest of a local file, streamed in 1 MiB chunks so multi-gigabyte
+/// sources never get buffered whole.
+pub async fn file_checksum(path: &Path, algorithm: ChecksumAlgorithm) -> Result<String> {
+    use md5::Md5;
+    use sha2::{Digest, Sha256};
+
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut md5 = Md5::new();
+    let mut sha256 = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        match algorithm {
+            ChecksumAlgorithm::Md5 => md5.update(&buf[..n]),
+            ChecksumAlgorithm::Sha256 => sha256.update(&buf[..n]),
+        }
+    }
+    Ok(match algorithm {
+        ChecksumAlgorithm::Md5 => hex::encode(md5.finalize()),
+        ChecksumAlgorithm::Sha256 => hex::encode(sha256.finalize()),
+    })
+}
+
+/// Standalone checksum command backing the frontend's "verify" action.
+#[tauri::command]
+pub async fn compute_checksum(path: PathBuf, algorithm: ChecksumAlgorithm) -> Result<String> {
+    file_checksum(&path, algorithm).await
+}
+
+/// MD5 of a local file as a lowercase hex string, matching the ETag R2
+/// reports for single-part uploads.
+async fn local_md5(path: &Path) -> Result<String> {
+    file_checksum(path, ChecksumAlgorithm::Md5).await
+}
+
+/// HEAD `key` and classify the existing object relative to the local file.
+/// Single-part ETags are compared against the local MD5; multipart ETags
+/// (containing `-`) fall back to size-only comparison with a warning, since
+/// their ETag is not a plain content hash.
+async fn check_existing(
+    app: &AppHandle,
+    client: &Client,
+    settings: &Settings,
+    local_path: &Path,
+    key: &str,
+    total_bytes: u64,
+) -> Result<ExistingObject> {
+    let head = match client
+        .head_object()
+        .bucket(&settings.r2_bucket)
+        .key(key)
+        .send()
+        .await
+    {
+        Ok(head) => head,
+        Err(e) => {
+            if e.as_service_error().map(|e| e.is_not_found()) == Some(true) {
+                return Ok(ExistingObject::Missing);
+            }
+            return Err(AppError::R2(format!("head {key}: {e}")));
+        }
+    };
+    let size = head.content_length().unwrap_or(0);
+    let last_modified = head
+        .last_modified()
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| "unknown".into());
+    if size != total_bytes as i64 {
+        return Ok(ExistingObject::Differs { size, last_modified });
+    }
+    let etag = head.e_tag().unwrap_or("").trim_matches('"');
+    if etag.contains('-') {
+        // Multipart ETags aren't content hashes, but our own multipart
+        // uploads store a sha256 in object metadata — compare that when
+        // it's there.
+        if let Some(stored) = head.metadata().and_then(|m| m.get("sha256")) {
+            return if *stored == file_checksum(local_path, ChecksumAlgorithm::Sha256).await? {
+                Ok(ExistingObject::Matches)
+            } else {
+                Ok(ExistingObject::Differs { size, last_modified })
+            };
+        }
+        let _ = app.emit(
+            "upload-warning",
+            format!("{key}: multipart ETag, skipping based on size match only"),
+        );
+        return Ok(ExistingObject::Matches);
+    }
+    if etag == local_md5(local_path).await? {
+        Ok(ExistingObject::Matches)
+    } else {
+        Ok(ExistingObject::Differs { size, last_modified })
+    }
+}
+
+/// How an existing object at the target key compares to the local file.
+enum ExistingObject {
+    Missing,
+    Matches,
+    Differs { size: i64, last_modified: String },
+}
+
+/// Upload a single file to `key`, using multipart for anything larger than the
+/// configured part size. Progress is reported via `upload-progress` events.
+///
+/// Unless `overwrite_existing` is set, an object that already matches the
+/// local file is skipped so re-running a batch doesn't re-transfer it, and a
+/// *differing* object raises [`AppError::ObjectExists`] so the frontend can
+/// confirm before anything published gets clobbered.
+pub async fn upload_file(
+    app: &AppHandle,
+    client: &Client,
+    settings: &Settings,
+    local_path: &Path,
+    key: &str,
+    options: &UploadOptions,
+) -> Result<UploadOutcome> {
+    upload_file_cancellable(app, client, settings, local_path, key, options, None).await
+}
+
+/// [`upload_file`], but watching `cancel`: when the flag trips, any
+/// in-flight multipart upload is aborted server-side and
+/// [`UploadOutcome::Cancelled`] is returned instead of an error.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_file_cancellable(
+    app: &AppHandle,
+    client: &Client,
+    settings: &Settings,
+    local_path: &Path,
+    key: &str,
+    options: &UploadOptions,
+    cancel: Option<&std::sync::atomic::AtomicBool>,
+) -> Result<UploadOutcome> {
+    if upload_cancelled(cancel) {
+        return Ok(UploadOutcome::Cancelled);
+    }
+    let total_bytes = tokio::fs::metadata(local_path).await?.len();
+    let content_type = options
+        .content_type
+        .as_deref()
+        .unwrap_or_else(|| guess_content_type(local_path));
+    if let Some(metadata) = &options.metadata {
+        validate_metadata(metadata)?;
+    }
+    if let Some(tags) = &options.tags {
+        validate_tags(tags)?;
+    }
+
+    if !settings.overwrite_existing {
+        match check_existing(app, client, settings, local_path, key, total_bytes).await? {
+            ExistingObject::Missing => {}
+            ExistingObject::Matches => {
+                let _ = app.emit("upload-skipped", format!("{key}: skipped (already uploaded)"));
+                emit_progress(app, key, total_bytes, total_bytes);
+                return Ok(UploadOutcome::Skipped);
+            }
+            ExistingObject::Differs { size, last_modified } => {
+                return Err(AppError::ObjectExists {
+                    key: key.to_string(),
+                    size,
+                    last_modified,
+                });
+            }
+        }
+    }
+
+    let mut throttle = Throttle::new();
+    if total_bytes <= settings.upload_part_size {
+        let body = ByteStream::from_path(local_path)
+            .await
+            .map_err(|e| AppError::R2(format!("failed to read {}: {e}", local_path.display())))?;
+        client
+            .put_object()
+            .bucket(&settings.r2_bucket)
+            .key(key)
+            .content_type(content_type)
+            .set_cache_control(cache_control_for(local_path, settings))
+            .set_metadata(options.metadata.clone())
+            .set_tagging(options.tags.as_ref().map(tagging_query))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("put {key}: {e}")))?;
+        emit_progress(app, key, total_bytes, total_bytes);
+        throttle.pace(settings, total_bytes).await;
+        mirror_file(app, settings, local_path, key, content_type).await?;
+        return Ok(UploadOutcome::Uploaded);
+    }
+
+    let completed = upload_file_multipart(
+        app,
+        client,
+        settings,
+        local_path,
+        key,
+        total_bytes,
+        content_type,
+        options,
+        &mut throttle,
+        cancel,
+    )
+    .await?;
+    if !completed {
+        return Ok(UploadOutcome::Cancelled);
+    }
+    mirror_file(app, settings, local_path, key, content_type).await?;
+    Ok(UploadOutcome::Uploaded)
+}
+
+fn upload_cancelled(cancel: Option<&std::sync::atomic::AtomicBool>) -> bool {
+    cancel
+        .map(|c| c.load(std::sync::atomic::Ordering::SeqCst))
+        .unwrap_or(false)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn upload_file_multipart(
+    app: &AppHandle,
+    client: &Client,
+    settings: &Settings,
+    local_path: &Path,
+    key: &str,
+    total_bytes: u64,
+    content_type: &str,
+    options: &UploadOptions,
+    throttle: &mut Throttle,
+    cancel: Option<&std::sync::atomic::AtomicBool>,
+) -> Result<bool> {
+    // Multipart ETags can't be compared against local content, so store a
+    // sha256 in metadata for check_existing to use on later runs.
+    let mut metadata = options.metadata.clone().unwrap_or_default();
+    metadata.insert(
+        "sha256".into(),
+        file_checksum(local_path, ChecksumAlgorithm::Sha256).await?,
+    );
+    let multipart = client
+        .create_multipart_upload()
+        .bucket(&settings.r2_bucket)
+        .key(key)
+        .content_type(content_type)
+        .set_cache_control(cache_control_for(local_path, settings))
+        .set_metadata(Some(metadata))
+        .set_tagging(options.tags.as_ref().map(tagging_query))
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("create multipart {key}: {e}")))?;
+    let upload_id = multipart
+        .upload_id()
+        .ok_or_else(|| AppError::R2(format!("no upload id returned for {key}")))?
+        .to_string();
+
+    let mut file = tokio::fs::File::open(local_path).await?;
+    let mut completed_parts = Vec::new();
+    let mut bytes_uploaded: u64 = 0;
+    let mut part_number: i32 = 1;
+
+    loop {
+        // An upload cancel aborts the multipart server-side so the partial
+        // parts don't linger, but leaves the local file for a retry.
+        if upload_cancelled(cancel) {
+            let _ = client
+                .abort_multipart_upload()
+                .bucket(&settings.r2_bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+                .await;
+            return Ok(false);
+        }
+        let mut buf = vec![0u8; settings.upload_part_size as usize];
+        let mut filled = 0;
+        while filled < buf.len() {
+            let n = file.read(&mut buf[filled..]).await?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        if filled == 0 {
+            break;
+        }
+        buf.truncate(filled);
+
+        let part = client
+            .upload_part()
+            .bucket(&settings.r2_bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(buf))
+            .send()
+            .await;
+
+        let part = match part {
+            Ok(part) => part,
+            Err(e) => {
+                // Best-effort abort so the partial upload doesn't linger.
+                let _ = client
+                    .abort_multipart_upload()
+                    .bucket(&settings.r2_bucket)
+                    .key(key)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await;
+                return Err(AppError::R2(format!("upload part {part_number} of {key}: {e}")));
+            }
+        };
+
+        completed_parts.push(
+            aws_sdk_s3::types::CompletedPart::builder()
+                .part_number(part_number)
+                .set_e_tag(part.e_tag().map(String::from))
+                .build(),
+        );
+        bytes_uploaded += filled as u64;
+        emit_progress(app, key, bytes_uploaded, total_bytes);
+        throttle.pace(settings, filled as u64).await;
+        part_number += 1;
+    }
+
+    client
+        .complete_multipart_upload()
+        .bucket(&settings.r2_bucket)
+        .key(key)
+        .upload_id(&upload_id)
+        .multipart_upload(
+            aws_sdk_s3::types::CompletedMultipartUpload::builder()
+                .set_parts(Some(completed_parts))
+                .build(),
+        )
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("complete multipart {key}: {e}")))?;
+    Ok(true)
+}
+
+/// The bandwidth limit applicable at local `hour`, in Mbps. Schedule
+/// windows take precedence over the global cap; a window with equal start
+/// and end hours never matches; windows may wrap midnight. None means
+/// unlimited.
+fn applicable_limit_mbps(settings: &Settings, hour: u8) -> Option<f64> {
+    for window in &settings.bandwidth_schedule {
+        let matches = match window.start_hour.cmp(&window.end_hour) {
+            std::cmp::Ordering::Less => (window.start_hour..window.end_hour).contains(&hour),
+            std::cmp::Ordering::Greater => hour >= window.start_hour || hour < window.end_hour,
+            std::cmp::Ordering::Equal => false,
+        };
+        if matches {
+            return Some(window.max_mbps);
+        }
+    }
+    settings.max_upload_mbps
+}
+
+/// Paces upload writes to the currently applicable bandwidth limit. The
+/// limit is re-read on every call, so a long upload crossing a schedule
+/// window boundary adjusts its rate live.
+struct Throttle {
+    last: std::time::Instant,
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Self {
+            last: std::time::Instant::now(),
+        }
+    }
+
+    /// Record `bytes` sent and sleep off whatever of their transmission
+    /// time at the current limit hasn't already elapsed.
+    async fn pace(&mut self, settings: &Settings, bytes: u64) {
+        use chrono::Timelike;
+
+        let hour = chrono::Local::now().hour() as u8;
+        let Some(limit_mbps) = applicable_limit_mbps(settings, hour) else {
+            self.last = std::time::Instant::now();
+            return;
+        };
+        if limit_mbps <= 0.0 {
+            return;
+        }
+        let need =
+            std::time::Duration::from_secs_f64(bytes as f64 * 8.0 / (limit_mbps * 1_000_000.0));
+        let elapsed = self.last.elapsed();
+        if need > elapsed {
+            tokio::time::sleep(need - elapsed).await;
+        }
+        self.last = std::time::Instant::now();
+    }
+}
+
+/// Outcome of one mirror copy, emitted on `mirror-result`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MirrorResult {
+    pub endpoint: String,
+    pub bucket: String,
+    pub key: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Copy one file to a single mirror target. Everything we upload is
+/// segment-sized (the original is split into HLS segments before it gets
+/// here), so a plain put suffices — no multipart.
+async fn mirror_put(
+    target: &S3Target,
+    local_path: &Path,
+    key: &str,
+    content_type: &str,
+    cache_control: Option<String>,
+    metadata: Option<HashMap<String, String>>,
+) -> Result<()> {
+    let client = target_client(target)?;
+    let body = ByteStream::from_path(local_path)
+        .await
+        .map_err(|e| AppError::R2(format!("failed to read {}: {e}", local_path.display())))?;
+    client
+        .put_object()
+        .bucket(&target.bucket)
+        .key(key)
+        .content_type(content_type)
+        .set_cache_control(cache_control)
+        .set_metadata(metadata)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("mirror put {key} to {}: {e}", target.bucket)))?;
+    Ok(())
+}
+
+/// Fan a freshly uploaded file out to every configured mirror target,
+/// concurrently. Per-target outcomes are emitted on `mirror-result`; a
+/// failed copy is a warning unless the target is marked `required`, in
+/// which case it fails the upload.
+async fn mirror_file(
+    app: &AppHandle,
+    settings: &Settings,
+    local_path: &Path,
+    key: &str,
+    content_type: &str,
+) -> Result<()> {
+    if settings.mirror_targets.is_empty() {
+        return Ok(());
+    }
+    let mut handles = Vec::with_capacity(settings.mirror_targets.len());
+    for target in settings.mirror_targets.clone() {
+        let local_path = local_path.to_path_buf();
+        let key = key.to_string();
+        let content_type = content_type.to_string();
+        let cache_control = cache_control_for(&local_path, settings);
+        handles.push(tokio::spawn(async move {
+            let outcome =
+                mirror_put(&target, &local_path, &key, &content_type, cache_control, None).await;
+            (target, outcome)
+        }));
+    }
+
+    let mut required_failures = Vec::new();
+    for handle in handles {
+        let (target, outcome) = handle.await.expect("mirror task panicked");
+        let error = outcome.as_ref().err().map(|e| e.to_string());
+        let _ = app.emit(
+            "mirror-result",
+            MirrorResult {
+                endpoint: target.endpoint.clone(),
+                bucket: target.bucket.clone(),
+                key: key.to_string(),
+                ok: outcome.is_ok(),
+                error: error.clone(),
+            },
+        );
+        if let Some(error) = error {
+            if target.required {
+                required_failures.push(error);
+            }
+        }
+    }
+    if !required_failures.is_empty() {
+        return Err(AppError::R2(format!(
+            "required mirror copy failed: {}",
+            required_failures.join("; ")
+        )));
+    }
+    Ok(())
+}
+
+fn emit_progress(app: &AppHandle, key: &str, bytes_uploaded: u64, total_bytes: u64) {
+    let _ = app.emit(
+        "upload-progress",
+        UploadProgress {
+            key: key.to_string(),
+            bytes_uploaded,
+            total_bytes,
+        },
+    );
+}
+
+/// Files a conversion writes for local use only, never uploaded with the
+/// package: the AES key and keyinfo file must stay out of the bucket —
+/// players fetch the key from the configured key server instead.
+fn is_local_only(path: &Path) -> bool {
+    matches!(
+        path.file_name().and_then(|n| n.to_str()),
+        Some(crate::ffmpeg::HLS_KEY_FILE) | Some(crate::ffmpeg::HLS_KEY_INFO_FILE)
+    )
+}
+
+/// Recursively collect every file under `dir`, returning paths relative to it
+/// alongside the absolute path. Local-only files (encryption key material)
+/// are excluded.
+pub fn collect_files(dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut out = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if !is_local_only(&path) {
+                let relative = path
+                    .strip_prefix(dir)
+                    .expect("entry is under the scanned dir")
+                    .to_path_buf();
+                out.push((relative, path));
+            }
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// Per-folder upload tally reported back to the frontend.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FolderUploadSummary {
+    pub uploaded: usize,
+    pub skipped: usize,
+}
+
+#[tauri::command]
+pub async fn upload_to_r2(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    local_path: PathBuf,
+    key: String,
+    overwrite: Option<bool>,
+    options: Option<UploadOptions>,
+) -> Result<UploadOutcome> {
+    let mut settings = store.get();
+    if let Some(overwrite) = overwrite {
+        settings.overwrite_existing = overwrite;
+    }
+    let client = client(&settings)?;
+    let options = options.unwrap_or_default();
+    upload_file(&app, &client, &settings, &local_path, &key, &options).await
+}
+
+/// Upload a whole conversion output folder under `prefix`, preserving the
+/// relative layout (renditions, playlists, segments). Every file — master
+/// playlist, rendition playlists and segments alike — goes through the same
+/// overwrite check; pass `overwrite: true` after the user confirms.
+#[tauri::command]
+pub async fn upload_folder_to_r2(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    folder: PathBuf,
+    prefix: String,
+    overwrite: Option<bool>,
+) -> Result<FolderUploadSummary> {
+    let mut settings = store.get();
+    if let Some(overwrite) = overwrite {
+        settings.overwrite_existing = overwrite;
+    }
+    let client = client(&settings)?;
+    let files = collect_files(&folder)?;
+    let prefix = prefix.trim_end_matches('/');
+    let mut summary = FolderUploadSummary::default();
+    for (relative, absolute) in &files {
+        let key = format!("{prefix}/{}", relative.to_string_lossy().replace('\\', "/"));
+        match upload_file(&app, &client, &settings, absolute, &key, &UploadOptions::default())
+            .await?
+        {
+            UploadOutcome::Uploaded => summary.uploaded += 1,
+            UploadOutcome::Skipped => summary.skipped += 1,
+            // No cancel flag is passed here, so this arm never fires.
+            UploadOutcome::Cancelled => break,
+        }
+    }
+    Ok(summary)
+}
+
+/// Put a small in-memory document (playlist, manifest) directly, bypassing
+/// the skip/overwrite checks used for file uploads.
+pub async fn put_string(
+    client: &Client,
+    settings: &Settings,
+    key: &str,
+    body: &str,
+    content_type: &str,
+) -> Result<()> {
+    client
+        .put_object()
+        .bucket(&settings.r2_bucket)
+        .key(key)
+        .content_type(content_type)
+        .cache_control(format!("public, max-age={}", settings.playlist_cache_max_age))
+        .body(ByteStream::from(body.as_bytes().to_vec()))
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("put {key}: {e}")))?;
+    Ok(())
+}
+
+/// Fetch a small text object (playlist, manifest) into memory.
+/// Fetch one object's bytes into memory. Meant for segment-sized objects;
+/// large downloads go through `download_r2_object` instead.
+async fn get_bytes(client: &Client, settings: &Settings, key: &str) -> Result<Vec<u8>> {
+    let resp = client
+        .get_object()
+        .bucket(&settings.r2_bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("get {key}: {e}")))?;
+    Ok(resp
+        .body
+        .collect()
+        .await
+        .map_err(|e| AppError::R2(format!("read body of {key}: {e}")))?
+        .into_bytes()
+        .to_vec())
+}
+
+pub async fn get_string(client: &Client, settings: &Settings, key: &str) -> Result<String> {
+    let resp = client
+        .get_object()
+        .bucket(&settings.r2_bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("get {key}: {e}")))?;
+    let bytes = resp
+        .body
+        .collect()
+        .await
+        .map_err(|e| AppError::R2(format!("read body of {key}: {e}")))?
+        .into_bytes();
+    String::from_utf8(bytes.to_vec())
+        .map_err(|_| AppError::R2(format!("{key} is not valid UTF-8")))
+}
+
+/// Listing/HEAD info surfaced to the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub size: i64,
+    pub last_modified: Option<String>,
+    /// User metadata (`x-amz-meta-*`). Only populated by HEAD; S3 listings
+    /// don't carry metadata.
+    pub metadata: HashMap<String, String>,
+}
+
+/// List objects under `prefix` (metadata not included; use
+/// `head_r2_object` for that).
+#[tauri::command]
+pub async fn list_r2_objects(
+    store: State<'_, SettingsStore>,
+    prefix: String,
+) -> Result<Vec<ObjectInfo>> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let mut objects = Vec::new();
+    let mut continuation: Option<String> = None;
+    loop {
+        let resp = client
+            .list_objects_v2()
+            .bucket(&settings.r2_bucket)
+            .prefix(&prefix)
+            .set_continuation_token(continuation.clone())
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("list {prefix}: {e}")))?;
+        for object in resp.contents() {
+            objects.push(ObjectInfo {
+                key: object.key().unwrap_or_default().to_string(),
+                size: object.size().unwrap_or(0),
+                last_modified: object.last_modified().map(|t| t.to_string()),
+                metadata: HashMap::new(),
+            });
+        }
+        if resp.is_truncated() == Some(true) {
+            continuation = resp.next_continuation_token().map(String::from);
+        } else {
+            break;
+        }
+    }
+    Ok(objects)
+}
+
+/// HEAD a single object, including its user metadata.
+#[tauri::command]
+pub async fn head_r2_object(store: State<'_, SettingsStore>, key: String) -> Result<ObjectInfo> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let head = client
+        .head_object()
+        .bucket(&settings.r2_bucket)
+        .key(&key)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("head {key}: {e}")))?;
+    Ok(ObjectInfo {
+        key,
+        size: head.content_length().unwrap_or(0),
+        last_modified: head.last_modified().map(|t| t.to_string()),
+        metadata: head.metadata().cloned().unwrap_or_default(),
+    })
+}
+
+/// Stream an object back down to `local_path`, e.g. to verify or re-encode
+/// it. A partial file left by an interrupted download is resumed with a
+/// Range request. Progress reuses the upload event shape on
+/// `download-progress`. Returns the final local path.
+#[tauri::command]
+pub async fn download_r2_object(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    key: String,
+    local_path: PathBuf,
+) -> Result<PathBuf> {
+    use tokio::io::AsyncWriteExt;
+
+    let settings = store.get();
+    let client = client(&settings)?;
+    let head = client
+        .head_object()
+        .bucket(&settings.r2_bucket)
+        .key(&key)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("head {key}: {e}")))?;
+    let total_bytes = head.content_length().unwrap_or(0) as u64;
+    let etag = head.e_tag().unwrap_or("").trim_matches('"').to_string();
+
+    // A partial download's bytes only belong to the object version they were
+    // read from. The sidecar records that version's ETag; when the remote
+    // changed underneath the partial, start over instead of appending.
+    let sidecar = etag_sidecar(&local_path);
+    let stored_etag = tokio::fs::read_to_string(&sidecar).await.ok();
+    let etag_changed = stored_etag.as_deref().is_some_and(|stored| stored != etag);
+
+    let mut start = 0u64;
+    if !etag_changed {
+        if let Ok(meta) = tokio::fs::metadata(&local_path).await {
+            match meta.len() {
+                len if len == total_bytes => {
+                    let _ = tokio::fs::remove_file(&sidecar).await;
+                    emit_download_progress(&app, &key, total_bytes, total_bytes);
+                    return Ok(local_path);
+                }
+                len if len < total_bytes => start = len,
+                // Local file is larger than the object: start over.
+                _ => {}
+            }
+        }
+    }
+
+    let mut request = client.get_object().bucket(&settings.r2_bucket).key(&key);
+    if start > 0 {
+        // If-Match guards the window between the HEAD above and this GET:
+        // a changed object answers 412 rather than serving mismatched bytes.
+        request = request
+            .range(format!("bytes={start}-"))
+            .if_match(format!("\"{etag}\""));
+    }
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("get {key}: {e}")))?;
+
+    if let Some(parent) = local_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(start == 0)
+        .append(start > 0)
+        .open(&local_path)
+        .await?;
+    tokio::fs::write(&sidecar, &etag).await?;
+
+    let mut body = resp.body;
+    let mut written = start;
+    while let Some(chunk) = body
+        .try_next()
+        .await
+        .map_err(|e| AppError::R2(format!("read body of {key}: {e}")))?
+    {
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+        emit_download_progress(&app, &key, written, total_bytes);
+    }
+    file.flush().await?;
+
+    if written != total_bytes {
+        return Err(AppError::R2(format!(
+            "{key}: downloaded {written} bytes but Content-Length was {total_bytes}"
+        )));
+    }
+    verify_downloaded(&head, &key, &local_path).await?;
+    let _ = tokio::fs::remove_file(&sidecar).await;
+    Ok(local_path)
+}
+
+/// Sidecar file recording which object ETag a partial download belongs to.
+fn etag_sidecar(local_path: &Path) -> PathBuf {
+    let mut name = local_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(".etag");
+    local_path.with_file_name(name)
+}
+
+/// Verify a completed download against the remote content hash: the ETag
+/// itself for single-part objects, or the sha256 our multipart uploads store
+/// in object metadata. Multipart objects without that metadata can only be
+/// size-checked, which the caller has already done.
+async fn verify_downloaded(
+    head: &aws_sdk_s3::operation::head_object::HeadObjectOutput,
+    key: &str,
+    local_path: &Path,
+) -> Result<()> {
+    let etag = head.e_tag().unwrap_or("").trim_matches('"');
+    if etag.contains('-') {
+        if let Some(stored) = head.metadata().and_then(|m| m.get("sha256")) {
+            let local = file_checksum(local_path, ChecksumAlgorithm::Sha256).await?;
+            if *stored != local {
+                return Err(AppError::R2(format!(
+                    "{key}: downloaded sha256 {local} does not match stored {stored}"
+                )));
+            }
+        }
+        return Ok(());
+    }
+    if !etag.is_empty() {
+        let local = local_md5(local_path).await?;
+        if etag != local {
+            return Err(AppError::R2(format!(
+                "{key}: downloaded md5 {local} does not match ETag {etag}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn emit_download_progress(app: &AppHandle, key: &str, bytes: u64, total_bytes: u64) {
+    let _ = app.emit(
+        "download-progress",
+        UploadProgress {
+            key: key.to_string(),
+            bytes_uploaded: bytes,
+            total_bytes,
+        },
+    );
+}
+
+/// Quick reachability check (DNS resolve + TCP connect with a short timeout)
+/// against the R2 endpoint, so batches don't convert for an hour and then
+/// fail at the first PUT.
+pub async fn check_connectivity(settings: &Settings) -> bool {
+    let addr = format!("{}.r2.cloudflarestorage.com:443", settings.r2_account_id);
+    matches!(
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            tokio::net::TcpStream::connect(addr),
+        )
+        .await,
+        Ok(Ok(_))
+    )
+}
+
+/// NetworkManager's `Metered` property values: 1 metered, 2 not metered,
+/// 3 guessed metered, 4 guessed not metered, 0 unknown.
+fn parse_metered_property(value: &str) -> Option<bool> {
+    let value = value.trim();
+    match value.strip_prefix("u ").unwrap_or(value).trim().parse::<u32>().ok()? {
+        1 | 3 => Some(true),
+        2 | 4 => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether the active connection is metered, where the platform exposes it.
+/// Linux asks NetworkManager over D-Bus; elsewhere (and when the query
+/// fails) this reports unknown and the manual pause toggle is the fallback.
+pub async fn connection_is_metered() -> Option<bool> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let output = tokio::process::Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_metered_property(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Block until the R2 endpoint is reachable, emitting `no-connectivity`
+/// events and backing off between attempts. Returns false if `cancelled` is
+/// set while waiting.
+pub async fn wait_for_connectivity(
+    app: &AppHandle,
+    settings: &Settings,
+    cancelled: &std::sync::atomic::AtomicBool,
+) -> bool {
+    use std::sync::atomic::Ordering;
+
+    let mut delay_secs = 2u64;
+    loop {
+        if check_connectivity(settings).await {
+            return true;
+        }
+        let _ = app.emit(
+            "no-connectivity",
+            format!("cannot reach R2 endpoint, retrying in {delay_secs}s"),
+        );
+        // Sleep in one-second slices so a cancel takes effect promptly.
+        for _ in 0..delay_secs {
+            if cancelled.load(Ordering::SeqCst) {
+                return false;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+        delay_secs = (delay_secs * 2).min(60);
+    }
+}
+
+/// Result of validating one upload destination, primary or mirror.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetValidation {
+    pub endpoint: String,
+    pub bucket: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Exercise endpoint, credentials and bucket name in one request — the
+/// cheapest listing the destination supports. A TCP-level connectivity
+/// check can't tell a typo'd bucket from a working one; this can.
+async fn probe_bucket(client: &Client, bucket: &str) -> Result<()> {
+    client
+        .list_objects_v2()
+        .bucket(bucket)
+        .max_keys(1)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("list {bucket}: {e}")))?;
+    Ok(())
+}
+
+/// Validate a single mirror target's endpoint, credentials and bucket.
+#[tauri::command]
+pub async fn validate_target(target: S3Target) -> Result<TargetValidation> {
+    let outcome = match target_client(&target) {
+        Ok(client) => probe_bucket(&client, &target.bucket).await,
+        Err(e) => Err(e),
+    };
+    Ok(TargetValidation {
+        endpoint: target.endpoint,
+        bucket: target.bucket,
+        ok: outcome.is_ok(),
+        error: outcome.err().map(|e| e.to_string()),
+    })
+}
+
+/// Validate the primary R2 bucket plus every configured mirror target,
+/// concurrently, and report each result separately — so a broken mirror
+/// shows up before a batch rather than as mid-upload warnings.
+#[tauri::command]
+pub async fn validate_all_targets(store: State<'_, SettingsStore>) -> Result<Vec<TargetValidation>> {
+    let settings = store.get();
+
+    let primary = settings.clone();
+    let mut handles = vec![tokio::spawn(async move {
+        let outcome = match client(&primary) {
+            Ok(client) => probe_bucket(&client, &primary.r2_bucket).await,
+            Err(e) => Err(e),
+        };
+        TargetValidation {
+            endpoint: format!("https://{}.r2.cloudflarestorage.com", primary.r2_account_id),
+            bucket: primary.r2_bucket,
+            ok: outcome.is_ok(),
+            error: outcome.err().map(|e| e.to_string()),
+        }
+    })];
+    for target in settings.mirror_targets.clone() {
+        handles.push(tokio::spawn(async move {
+            validate_target(target)
+                .await
+                .expect("validate_target is infallible")
+        }));
+    }
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.expect("validation task panicked"));
+    }
+    Ok(results)
+}
+
+/// Headers the HLS player needs the bucket to expose cross-origin.
+const CORS_EXPOSE_HEADERS: &[&str] = &["Content-Length", "Content-Range", "ETag"];
+
+/// What `check_bucket_cors` found, so the UI can explain exactly what's
+/// missing instead of playback failing silently.
+#[derive(Debug, Clone, Serialize)]
+pub struct CorsReport {
+    /// False when the bucket has no CORS configuration at all.
+    pub configured: bool,
+    pub missing_origins: Vec<String>,
+    pub missing_methods: Vec<String>,
+    pub missing_expose_headers: Vec<String>,
+    pub ok: bool,
+}
+
+/// Fetch the bucket's CORS rules and check they allow GET/HEAD from the
+/// configured origins and expose the headers HLS playback needs.
+///
+/// Note: R2 also lets CORS be managed from the dashboard; when the API token
+/// can't read it, GetBucketCors fails and we surface that as an R2 error
+/// rather than guessing.
+#[tauri::command]
+pub async fn check_bucket_cors(store: State<'_, SettingsStore>) -> Result<CorsReport> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let rules = match client
+        .get_bucket_cors()
+        .bucket(&settings.r2_bucket)
+        .send()
+        .await
+    {
+        Ok(resp) => resp.cors_rules().to_vec(),
+        Err(e) => {
+            // R2 returns NoSuchCORSConfiguration when nothing is set.
+            let not_configured = e
+                .as_service_error()
+                .and_then(|e| e.meta().code())
+                .map(|code| code.contains("NoSuchCORSConfiguration"))
+                .unwrap_or(false);
+            if not_configured {
+                return Ok(CorsReport {
+                    configured: false,
+                    missing_origins: settings.cors_origins.clone(),
+                    missing_methods: vec!["GET".into(), "HEAD".into()],
+                    missing_expose_headers: CORS_EXPOSE_HEADERS
+                        .iter()
+                        .map(|h| h.to_string())
+                        .collect(),
+                    ok: false,
+                });
+            }
+            return Err(AppError::R2(format!("get bucket cors: {e}")));
+        }
+    };
+
+    let origin_allowed = |origin: &str| {
+        rules.iter().any(|rule| {
+            rule.allowed_origins()
+                .iter()
+                .any(|o| o == "*" || o == origin)
+                && rule.allowed_methods().iter().any(|m| m == "GET")
+        })
+    };
+    let method_allowed = |method: &str| {
+        rules
+            .iter()
+            .any(|rule| rule.allowed_methods().iter().any(|m| m == method))
+    };
+    let header_exposed = |header: &str| {
+        rules.iter().any(|rule| {
+            rule.expose_headers()
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case(header))
+        })
+    };
+
+    let missing_origins: Vec<String> = settings
+        .cors_origins
+        .iter()
+        .filter(|o| !origin_allowed(o))
+        .cloned()
+        .collect();
+    let missing_methods: Vec<String> = ["GET", "HEAD"]
+        .iter()
+        .filter(|m| !method_allowed(m))
+        .map(|m| m.to_string())
+        .collect();
+    let missing_expose_headers: Vec<String> = CORS_EXPOSE_HEADERS
+        .iter()
+        .filter(|h| !header_exposed(h))
+        .map(|h| h.to_string())
+        .collect();
+
+    let ok = missing_origins.is_empty()
+        && missing_methods.is_empty()
+        && missing_expose_headers.is_empty();
+    Ok(CorsReport {
+        configured: true,
+        missing_origins,
+        missing_methods,
+        missing_expose_headers,
+        ok,
+    })
+}
+
+/// Overwrite the bucket's CORS configuration with the policy playback needs:
+/// GET/HEAD from the configured origins with the streaming headers exposed.
+#[tauri::command]
+pub async fn apply_recommended_cors(store: State<'_, SettingsStore>) -> Result<()> {
+    use aws_sdk_s3::types::{CorsConfiguration, CorsRule};
+
+    let settings = store.get();
+    if settings.cors_origins.is_empty() {
+        return Err(AppError::Settings(
+            "cors_origins is empty; configure the player origin first".into(),
+        ));
+    }
+    let client = client(&settings)?;
+    let rule = CorsRule::builder()
+        .set_allowed_origins(Some(settings.cors_origins.clone()))
+        .allowed_methods("GET")
+        .allowed_methods("HEAD")
+        .allowed_headers("*")
+        .set_expose_headers(Some(
+            CORS_EXPOSE_HEADERS.iter().map(|h| h.to_string()).collect(),
+        ))
+        .max_age_seconds(3600)
+        .build()
+        .map_err(|e| AppError::R2(format!("build cors rule: {e}")))?;
+    let config = CorsConfiguration::builder()
+        .cors_rules(rule)
+        .build()
+        .map_err(|e| AppError::R2(format!("build cors configuration: {e}")))?;
+    client
+        .put_bucket_cors()
+        .bucket(&settings.r2_bucket)
+        .cors_configuration(config)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("put bucket cors: {e}")))?;
+    Ok(())
+}
+
+/// Lifecycle rule id this app manages for `prefix`, namespaced so we never
+/// touch rules configured elsewhere.
+fn expiry_rule_id(prefix: &str) -> String {
+    format!("cinemafred-uploader-expiry:{prefix}")
+}
+
+/// The bucket's current lifecycle rules; a bucket with none configured
+/// yields an empty list.
+async fn lifecycle_rules(
+    client: &Client,
+    settings: &Settings,
+) -> Result<Vec<aws_sdk_s3::types::LifecycleRule>> {
+    match client
+        .get_bucket_lifecycle_configuration()
+        .bucket(&settings.r2_bucket)
+        .send()
+        .await
+    {
+        Ok(resp) => Ok(resp.rules().to_vec()),
+        Err(e) => {
+            let not_configured = e
+                .as_service_error()
+                .and_then(|e| e.meta().code())
+                .map(|code| code.contains("NoSuchLifecycleConfiguration"))
+                .unwrap_or(false);
+            if not_configured {
+                Ok(Vec::new())
+            } else {
+                Err(AppError::R2(format!("get bucket lifecycle: {e}")))
+            }
+        }
+    }
+}
+
+/// Write `rules` back, or delete the configuration entirely when none
+/// remain. R2's lifecycle support differs from AWS in places, so a rejected
+/// configuration is surfaced explicitly instead of as a generic error.
+async fn put_lifecycle_rules(
+    client: &Client,
+    settings: &Settings,
+    rules: Vec<aws_sdk_s3::types::LifecycleRule>,
+) -> Result<()> {
+    if rules.is_empty() {
+        client
+            .delete_bucket_lifecycle()
+            .bucket(&settings.r2_bucket)
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("delete bucket lifecycle: {e}")))?;
+        return Ok(());
+    }
+    let config = aws_sdk_s3::types::BucketLifecycleConfiguration::builder()
+        .set_rules(Some(rules))
+        .build()
+        .map_err(|e| AppError::R2(format!("build lifecycle configuration: {e}")))?;
+    client
+        .put_bucket_lifecycle_configuration()
+        .bucket(&settings.r2_bucket)
+        .lifecycle_configuration(config)
+        .send()
+        .await
+        .map_err(|e| {
+            let rejected = e
+                .as_service_error()
+                .is_some();
+            if rejected {
+                AppError::R2(format!(
+                    "the storage backend rejected the lifecycle configuration \
+                     (R2's lifecycle support differs from AWS S3): {e}"
+                ))
+            } else {
+                AppError::R2(format!("put bucket lifecycle: {e}"))
+            }
+        })?;
+    Ok(())
+}
+
+/// Expire objects under `prefix` after `days` days, e.g. for preview or
+/// test uploads that shouldn't accumulate. Replaces any expiry rule this
+/// app previously set for the same prefix; rules managed elsewhere are
+/// preserved.
+#[tauri::command]
+pub async fn set_object_expiry(
+    store: State<'_, SettingsStore>,
+    prefix: String,
+    days: u32,
+) -> Result<()> {
+    use aws_sdk_s3::types::{ExpirationStatus, LifecycleExpiration, LifecycleRule, LifecycleRuleFilter};
+
+    if days == 0 {
+        return Err(AppError::InvalidInput("expiry days must be at least 1".into()));
+    }
+    let settings = store.get();
+    let client = client(&settings)?;
+    let rule_id = expiry_rule_id(&prefix);
+
+    let rule = LifecycleRule::builder()
+        .id(&rule_id)
+        .status(ExpirationStatus::Enabled)
+        .filter(LifecycleRuleFilter::builder().prefix(&prefix).build())
+        .expiration(LifecycleExpiration::builder().days(days as i32).build())
+        .build()
+        .map_err(|e| AppError::R2(format!("build lifecycle rule: {e}")))?;
+
+    let mut rules = lifecycle_rules(&client, &settings).await?;
+    rules.retain(|r| r.id() != Some(rule_id.as_str()));
+    rules.push(rule);
+    put_lifecycle_rules(&client, &settings, rules).await
+}
+
+/// Remove the expiry rule `set_object_expiry` created for `prefix`.
+/// Returns false when no such rule existed.
+#[tauri::command]
+pub async fn clear_object_expiry(store: State<'_, SettingsStore>, prefix: String) -> Result<bool> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let rule_id = expiry_rule_id(&prefix);
+
+    let rules = lifecycle_rules(&client, &settings).await?;
+    let remaining: Vec<_> = rules
+        .iter()
+        .filter(|r| r.id() != Some(rule_id.as_str()))
+        .cloned()
+        .collect();
+    if remaining.len() == rules.len() {
+        return Ok(false);
+    }
+    put_lifecycle_rules(&client, &settings, remaining).await?;
+    Ok(true)
+}
+
+/// Replace the tag set on `key` with `tags` (at most 10, per the S3
+/// limit), e.g. `tier=archive` to drive a lifecycle rule or cost report
+/// without restructuring the key hierarchy.
+#[tauri::command]
+pub async fn set_object_tags(
+    store: State<'_, SettingsStore>,
+    key: String,
+    tags: HashMap<String, String>,
+) -> Result<()> {
+    use aws_sdk_s3::types::{Tag, Tagging};
+
+    validate_tags(&tags)?;
+    let settings = store.get();
+    let client = client(&settings)?;
+    let mut tag_set = Vec::with_capacity(tags.len());
+    for (k, v) in &tags {
+        tag_set.push(
+            Tag::builder()
+                .key(k)
+                .value(v)
+                .build()
+                .map_err(|e| AppError::R2(format!("build tag {k}: {e}")))?,
+        );
+    }
+    let tagging = Tagging::builder()
+        .set_tag_set(Some(tag_set))
+        .build()
+        .map_err(|e| AppError::R2(format!("build tag set: {e}")))?;
+    client
+        .put_object_tagging()
+        .bucket(&settings.r2_bucket)
+        .key(&key)
+        .tagging(tagging)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("put tags on {key}: {e}")))?;
+    Ok(())
+}
+
+/// The current tag set of `key`.
+#[tauri::command]
+pub async fn get_object_tags(
+    store: State<'_, SettingsStore>,
+    key: String,
+) -> Result<HashMap<String, String>> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let resp = client
+        .get_object_tagging()
+        .bucket(&settings.r2_bucket)
+        .key(&key)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("get tags on {key}: {e}")))?;
+    Ok(resp
+        .tag_set()
+        .iter()
+        .map(|t| (t.key().to_string(), t.value().to_string()))
+        .collect())
+}
+
+/// Cancel flag of the prefix delete currently running (at most one),
+/// managed as tauri state.
+pub struct PrefixDeleter(std::sync::Mutex<Option<std::sync::Arc<std::sync::atomic::AtomicBool>>>);
+
+impl PrefixDeleter {
+    pub fn new() -> Self {
+        Self(std::sync::Mutex::new(None))
+    }
+}
+
+/// DeleteObjects takes at most this many keys per request.
+const DELETE_BATCH_SIZE: usize = 1000;
+
+/// Emitted on `delete-progress` as each batch lands.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeleteProgress {
+    pub prefix: String,
+    pub deleted: usize,
+    pub total: usize,
+}
+
+/// Outcome of [`delete_r2_prefix`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletePrefixReport {
+    pub prefix: String,
+    pub dry_run: bool,
+    pub keys_listed: usize,
+    pub deleted: usize,
+    /// Successful deletions per batch, in batch order.
+    pub batch_counts: Vec<usize>,
+    /// Per-key failures as `key: message`.
+    pub errors: Vec<String>,
+    pub cancelled: bool,
+    /// The keys that would be deleted; only populated on a dry run.
+    pub keys: Vec<String>,
+}
+
+/// Delete one batch of keys via DeleteObjects, returning how many went
+/// through and any per-key failures.
+pub(crate) async fn delete_batch(
+    client: &Client,
+    settings: &Settings,
+    keys: Vec<String>,
+) -> Result<(usize, Vec<String>)> {
+    use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+
+    let requested = keys.len();
+    let objects: Vec<ObjectIdentifier> = keys
+        .into_iter()
+        .map(|key| {
+            ObjectIdentifier::builder()
+                .key(key)
+                .build()
+                .map_err(|e| AppError::R2(format!("build delete entry: {e}")))
+        })
+        .collect::<Result<_>>()?;
+    let delete = Delete::builder()
+        .set_objects(Some(objects))
+        .build()
+        .map_err(|e| AppError::R2(format!("build delete request: {e}")))?;
+    let resp = client
+        .delete_objects()
+        .bucket(&settings.r2_bucket)
+        .delete(delete)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("delete batch: {e}")))?;
+    let errors: Vec<String> = resp
+        .errors()
+        .iter()
+        .map(|e| {
+            format!(
+                "{}: {}",
+                e.key().unwrap_or("<unknown key>"),
+                e.message().unwrap_or("unspecified error")
+            )
+        })
+        .collect();
+    Ok((requested - errors.len(), errors))
+}
+
+/// Delete every object under `prefix` (e.g. when removing a movie), batched
+/// 1000 keys per DeleteObjects request with `delete_concurrency` batches in
+/// flight. Progress streams on `delete-progress`; `cancel_prefix_delete`
+/// stops between batches (anything already sent stays deleted). `dry_run`
+/// only lists what would go.
+#[tauri::command]
+pub async fn delete_r2_prefix(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    deleter: State<'_, PrefixDeleter>,
+    prefix: String,
+    dry_run: bool,
+) -> Result<DeletePrefixReport> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
+
+    let settings = store.get();
+    let client = client(&settings)?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = deleter.0.lock().unwrap().replace(cancel.clone()) {
+        previous.store(true, Ordering::SeqCst);
+    }
+
+    let mut keys: Vec<String> = Vec::new();
+    let mut continuation: Option<String> = None;
+    loop {
+        let resp = client
+            .list_objects_v2()
+            .bucket(&settings.r2_bucket)
+            .prefix(&prefix)
+            .set_continuation_token(continuation.clone())
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("list {prefix}: {e}")))?;
+        keys.extend(resp.contents().iter().filter_map(|o| o.key().map(String::from)));
+        if resp.is_truncated() == Some(true) {
+            continuation = resp.next_continuation_token().map(String::from);
+        } else {
+            break;
+        }
+    }
+
+    let keys_listed = keys.len();
+    if dry_run {
+        return Ok(DeletePrefixReport {
+            prefix,
+            dry_run: true,
+            keys_listed,
+            deleted: 0,
+            batch_counts: Vec::new(),
+            errors: Vec::new(),
+            cancelled: false,
+            keys,
+        });
+    }
+
+    let settings = Arc::new(settings);
+    let permits = Arc::new(Semaphore::new(settings.delete_concurrency));
+    let mut handles = Vec::new();
+    for batch in keys.chunks(DELETE_BATCH_SIZE) {
+        let batch = batch.to_vec();
+        let permits = permits.clone();
+        let client = client.clone();
+        let settings = settings.clone();
+        let cancel = cancel.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = permits.acquire().await.expect("semaphore never closed");
+            if cancel.load(Ordering::SeqCst) {
+                return Ok(None);
+            }
+            delete_batch(&client, &settings, batch).await.map(Some)
+        }));
+    }
+
+    let mut deleted = 0;
+    let mut batch_counts = Vec::new();
+    let mut errors = Vec::new();
+    for handle in handles {
+        let Some((count, batch_errors)) = handle.await.expect("delete task panicked")? else {
+            continue;
+        };
+        deleted += count;
+        batch_counts.push(count);
+        errors.extend(batch_errors);
+        let _ = app.emit(
+            "delete-progress",
+            DeleteProgress {
+                prefix: prefix.clone(),
+                deleted,
+                total: keys_listed,
+            },
+        );
+    }
+    Ok(DeletePrefixReport {
+        prefix,
+        dry_run: false,
+        keys_listed,
+        deleted,
+        batch_counts,
+        errors,
+        cancelled: cancel.load(Ordering::SeqCst),
+        keys: Vec::new(),
+    })
+}
+
+/// Stop the running prefix delete between batches. Returns whether one was
+/// running.
+#[tauri::command]
+pub fn cancel_prefix_delete(deleter: State<'_, PrefixDeleter>) -> Result<bool> {
+    use std::sync::atomic::Ordering;
+    match deleter.0.lock().unwrap().take() {
+        Some(cancel) => {
+            cancel.store(true, Ordering::SeqCst);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Delete an explicit list of keys (e.g. rolling back the objects a failed
+/// job uploaded), returning how many were deleted. Unlike
+/// `delete_r2_prefix` this touches nothing beyond the given keys.
+pub async fn delete_objects(settings: &Settings, keys: &[String]) -> Result<usize> {
+    let client = client(settings)?;
+    let mut deleted = 0;
+    for key in keys {
+        client
+            .delete_object()
+            .bucket(&settings.r2_bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("delete {key}: {e}")))?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// The placeholders `object_key_template` understands.
+const KEY_TEMPLATE_PLACEHOLDERS: &[&str] = &["slug", "year", "rendition", "segment"];
+
+/// Reject a key template that could produce unsafe or colliding keys:
+/// it must carry `{slug}` and `{segment}` (or two movies, or two segments
+/// of one movie, would overwrite each other), reference only known
+/// placeholders, and use only key-safe literal characters.
+pub fn validate_key_template(template: &str) -> Result<()> {
+    for required in ["{slug}", "{segment}"] {
+        if !template.contains(required) {
+            return Err(AppError::Settings(format!(
+                "object_key_template must contain {required}, or keys would collide"
+            )));
+        }
+    }
+    for fragment in template.split('{').skip(1) {
+        let placeholder = fragment.split('}').next().unwrap_or(fragment);
+        if !KEY_TEMPLATE_PLACEHOLDERS.contains(&placeholder) {
+            return Err(AppError::Settings(format!(
+                "unknown placeholder {{{placeholder}}} in object_key_template"
+            )));
+        }
+    }
+    let mut literals = template.to_string();
+    for placeholder in KEY_TEMPLATE_PLACEHOLDERS {
+        literals = literals.replace(&format!("{{{placeholder}}}"), "");
+    }
+    if literals.contains(['{', '}']) {
+        return Err(AppError::Settings(
+            "unbalanced braces in object_key_template".into(),
+        ));
+    }
+    if !literals
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+    {
+        return Err(AppError::Settings(
+            "object_key_template may only contain alphanumerics, '-', '_', '.' and '/'".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Expand the key template for one uploaded file. `relative` is the file's
+/// path within the conversion output (`480p/segment_000.ts`, or just
+/// `playlist.m3u8` for the master); its directory part fills `{rendition}`
+/// and its file name `{segment}`. Empty placeholders (no year, the master's
+/// missing rendition) collapse rather than leaving double slashes.
+pub fn resolve_object_key(
+    template: &str,
+    slug: &str,
+    year: Option<u32>,
+    relative: &Path,
+) -> String {
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    let (rendition, segment) = match relative.rsplit_once('/') {
+        Some((rendition, segment)) => (rendition.to_string(), segment.to_string()),
+        None => (String::new(), relative.clone()),
+    };
+    let expanded = template
+        .replace("{slug}", slug)
+        .replace("{year}", &year.map(|y| y.to_string()).unwrap_or_default())
+        .replace("{rendition}", &rendition)
+        .replace("{segment}", &segment);
+    expanded
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// What [`resolve_collision`] decided for a package whose destination may
+/// be occupied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollisionResolution {
+    /// Upload under the original name.
+    Keep,
+    /// Upload under the original name, replacing what's there.
+    Overwrite,
+    /// Upload everything under this fresh name instead.
+    Renamed(String),
+    /// Refuse the upload.
+    Refused,
+}
+
+/// Apply `collision_strategy` to a package's slug (or prefix). `exists`
+/// answers whether a candidate name is already taken — the upload phase
+/// HEADs the master playlist; tests use an in-memory bucket. The timestamped
+/// name is taken as fresh without a second probe: two jobs landing on the
+/// same second-resolution suffix would have collided as duplicates anyway.
+pub fn resolve_collision(
+    strategy: crate::settings::CollisionStrategy,
+    base: &str,
+    timestamp: &str,
+    exists: impl Fn(&str) -> bool,
+) -> CollisionResolution {
+    use crate::settings::CollisionStrategy;
+
+    if !exists(base) {
+        return CollisionResolution::Keep;
+    }
+    match strategy {
+        CollisionStrategy::Fail => CollisionResolution::Refused,
+        CollisionStrategy::Overwrite => CollisionResolution::Overwrite,
+        CollisionStrategy::AppendTimestamp => {
+            CollisionResolution::Renamed(format!("{base}-{timestamp}"))
+        }
+    }
+}
+
+/// HEAD `key`, returning its size and last-modified when it exists and
+/// None on a 404.
+pub async fn head_existing(
+    client: &Client,
+    settings: &Settings,
+    key: &str,
+) -> Result<Option<(i64, String)>> {
+    match client
+        .head_object()
+        .bucket(&settings.r2_bucket)
+        .key(key)
+        .send()
+        .await
+    {
+        Ok(head) => Ok(Some((
+            head.content_length().unwrap_or(0),
+            head.last_modified()
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "unknown".into()),
+        ))),
+        Err(e) if e.as_service_error().map(|e| e.is_not_found()) == Some(true) => Ok(None),
+        Err(e) => Err(AppError::R2(format!("head {key}: {e}"))),
+    }
+}
+
+/// Resolve a playlist-relative URI against the key of the playlist that
+/// references it (`hls/movie/playlist.m3u8` + `480p/playlist.m3u8` →
+/// `hls/movie/480p/playlist.m3u8`).
+fn resolve_key(base_key: &str, uri: &str) -> String {
+    match base_key.rsplit_once('/') {
+        Some((dir, _)) => format!("{dir}/{}", uri.trim()),
+        None => uri.trim().to_string(),
+    }
+}
+
+/// The non-comment lines of a playlist: the URIs it references.
+fn playlist_uris(playlist: &str) -> Vec<&str> {
+    playlist
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect()
+}
+
+/// The `URI="…"` attribute value of a playlist tag line.
+fn uri_attribute(line: &str) -> Option<&str> {
+    line.split("URI=\"").nth(1)?.split('"').next()
+}
+
+/// The highest-bandwidth variant URI of a master playlist.
+fn top_variant_uri(master: &str) -> Option<String> {
+    let mut best: Option<(u64, String)> = None;
+    let mut lines = master.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if !line.starts_with("#EXT-X-STREAM-INF") {
+            continue;
+        }
+        let bandwidth: u64 = line
+            .split("BANDWIDTH=")
+            .nth(1)
+            .and_then(|rest| rest.split(',').next())
+            .and_then(|digits| digits.trim().parse().ok())
+            .unwrap_or(0);
+        // The variant URI is the next non-comment line.
+        if let Some(uri) = lines.by_ref().find(|l| !l.is_empty() && !l.starts_with('#')) {
+            if best.as_ref().map(|(b, _)| bandwidth > *b).unwrap_or(true) {
+                best = Some((bandwidth, uri.to_string()));
+            }
+        }
+    }
+    best.map(|(_, uri)| uri)
+}
+
+/// Rewrite a media playlist for local remuxing: point `EXT-X-KEY` at the
+/// user-supplied key file, since the published playlist references the key
+/// server and the key itself never lives in R2. Fails when the playlist is
+/// encrypted and no key file was given.
+fn localize_media_playlist(playlist: &str, key_file: Option<&Path>) -> Result<String> {
+    let mut out = Vec::new();
+    for line in playlist.lines() {
+        if line.starts_with("#EXT-X-KEY") && !line.contains("METHOD=NONE") {
+            let key_file = key_file.ok_or_else(|| {
+                AppError::InvalidInput(
+                    "the package is encrypted; pass the key file to remux it".into(),
+                )
+            })?;
+            let uri = uri_attribute(line)
+                .ok_or_else(|| AppError::R2("EXT-X-KEY carries no URI".into()))?;
+            out.push(line.replace(
+                &format!("URI=\"{uri}\""),
+                &format!("URI=\"{}\"", key_file.display()),
+            ));
+        } else {
+            out.push(line.to_string());
+        }
+    }
+    Ok(out.join("\n") + "\n")
+}
+
+/// Emitted on `remux-progress` as segments download.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemuxProgress {
+    pub key: String,
+    pub downloaded: usize,
+    pub total: usize,
+}
+
+/// Result of [`hls_to_mp4`].
+#[derive(Debug, Clone, Serialize)]
+pub struct RemuxResult {
+    pub output_path: PathBuf,
+    /// The rendition playlist the MP4 was built from.
+    pub rendition_key: String,
+    pub segments: usize,
+    pub duration_seconds: f64,
+    pub bytes: u64,
+}
+
+/// Pull a published package back into one playable MP4: download the
+/// chosen rendition's playlist and segments, stream-copy them through
+/// ffmpeg, and probe the result to confirm it plays. `key` may be the
+/// master playlist (the highest-bandwidth variant is chosen) or a
+/// rendition playlist directly. Encrypted packages need `key_file`.
+#[tauri::command]
+pub async fn hls_to_mp4(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    key: String,
+    output_path: PathBuf,
+    key_file: Option<PathBuf>,
+) -> Result<RemuxResult> {
+    let settings = store.get();
+    let client = client(&settings)?;
+
+    let playlist = get_string(&client, &settings, &key).await?;
+    let (rendition_key, media) = if playlist.contains("#EXT-X-STREAM-INF") {
+        let uri = top_variant_uri(&playlist)
+            .ok_or_else(|| AppError::R2(format!("{key} lists no variants")))?;
+        let rendition_key = resolve_key(&key, &uri);
+        let media = get_string(&client, &settings, &rendition_key).await?;
+        (rendition_key, media)
+    } else {
+        (key.clone(), playlist)
+    };
+
+    let scratch = std::env::temp_dir().join(format!("uploader-remux-{}", std::process::id()));
+    let outcome = async {
+        tokio::fs::create_dir_all(&scratch).await?;
+        let local_playlist = scratch.join("playlist.m3u8");
+        tokio::fs::write(
+            &local_playlist,
+            localize_media_playlist(&media, key_file.as_deref())?,
+        )
+        .await?;
+
+        // The init segment (fMP4 packages) plus every media segment.
+        let mut uris: Vec<String> = Vec::new();
+        for line in media.lines().map(str::trim) {
+            if line.starts_with("#EXT-X-MAP") {
+                if let Some(uri) = uri_attribute(line) {
+                    uris.push(uri.to_string());
+                }
+            } else if !line.is_empty() && !line.starts_with('#') {
+                uris.push(line.to_string());
+            }
+        }
+        let total = uris.len();
+        for (i, uri) in uris.iter().enumerate() {
+            let segment_key = resolve_key(&rendition_key, uri);
+            let local = scratch.join(uri);
+            if let Some(parent) = local.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&local, get_bytes(&client, &settings, &segment_key).await?).await?;
+            let _ = app.emit(
+                "remux-progress",
+                RemuxProgress {
+                    key: rendition_key.clone(),
+                    downloaded: i + 1,
+                    total,
+                },
+            );
+        }
+
+        if let Some(parent) = output_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let output = tokio::process::Command::new("ffmpeg")
+            .arg("-y")
+            .args(["-protocol_whitelist", "file,crypto,data"])
+            .args(["-allowed_extensions", "ALL"])
+            .arg("-i")
+            .arg(&local_playlist)
+            .args(["-map", "0", "-c", "copy", "-movflags", "+faststart"])
+            .arg(&output_path)
+            .output()
+            .await
+            .map_err(crate::ffmpeg::spawn_error)?;
+        if !output.status.success() {
+            return Err(AppError::Ffmpeg(format!(
+                "remux exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .last()
+                    .unwrap_or("")
+            )));
+        }
+
+        // The quick probe is the playability check: a truncated or
+        // mis-muxed MP4 fails it.
+        let metadata = crate::ffmpeg::probe(&output_path).await?;
+        Ok(RemuxResult {
+            bytes: tokio::fs::metadata(&output_path).await?.len(),
+            output_path: output_path.clone(),
+            rendition_key: rendition_key.clone(),
+            segments: total,
+            duration_seconds: metadata.duration_seconds,
+        })
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&scratch).await;
+    outcome
+}
+
+/// How many problem keys a verification report lists before truncating.
+const VERIFY_REPORT_LIMIT: usize = 10;
+/// Concurrent HEAD requests while verifying a package.
+const VERIFY_CONCURRENCY: usize = 16;
+
+/// Report from checking an uploaded HLS package against its playlists.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageVerification {
+    pub master_key: String,
+    pub playlists_checked: usize,
+    pub segments_checked: usize,
+    /// Keys referenced by a playlist but absent from the bucket, truncated
+    /// to the first [`VERIFY_REPORT_LIMIT`]; `missing_total` has the count.
+    pub missing: Vec<String>,
+    pub missing_total: usize,
+    /// Keys that exist but are empty — almost certainly interrupted uploads.
+    pub zero_byte: Vec<String>,
+    pub complete: bool,
+}
+
+/// Walk an uploaded package from its master playlist — every media playlist
+/// and every segment they reference — and HEAD each object, reporting
+/// anything missing or zero-byte. Catches partial uploads that would
+/// otherwise surface as mid-playback failures.
+#[tauri::command]
+pub async fn verify_uploaded_package(
+    store: State<'_, SettingsStore>,
+    master_key: String,
+) -> Result<PackageVerification> {
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
+
+    let settings = store.get();
+    let client = client(&settings)?;
+    let master = get_string(&client, &settings, &master_key).await?;
+
+    let mut playlists_checked = 1;
+    let mut missing: Vec<String> = Vec::new();
+    let mut segment_keys: Vec<String> = Vec::new();
+    for uri in playlist_uris(&master) {
+        let playlist_key = resolve_key(&master_key, uri);
+        playlists_checked += 1;
+        match get_string(&client, &settings, &playlist_key).await {
+            Ok(playlist) => {
+                for segment in playlist_uris(&playlist) {
+                    segment_keys.push(resolve_key(&playlist_key, segment));
+                }
+            }
+            Err(_) => missing.push(playlist_key),
+        }
+    }
+
+    let permits = Arc::new(Semaphore::new(VERIFY_CONCURRENCY));
+    let mut handles = Vec::with_capacity(segment_keys.len());
+    for key in segment_keys {
+        let permits = permits.clone();
+        let client = client.clone();
+        let bucket = settings.r2_bucket.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = permits.acquire().await.expect("semaphore never closed");
+            let length = client
+                .head_object()
+                .bucket(&bucket)
+                .key(&key)
+                .send()
+                .await
+                .ok()
+                .and_then(|head| head.content_length());
+            (key, length)
+        }));
+    }
+
+    let mut segments_checked = 0;
+    let mut zero_byte = Vec::new();
+    for handle in handles {
+        let (key, length) = handle.await.expect("verify task panicked");
+        segments_checked += 1;
+        match length {
+            None => missing.push(key),
+            Some(0) => zero_byte.push(key),
+            Some(_) => {}
+        }
+    }
+
+    let missing_total = missing.len();
+    missing.truncate(VERIFY_REPORT_LIMIT);
+    zero_byte.truncate(VERIFY_REPORT_LIMIT);
+    Ok(PackageVerification {
+        complete: missing_total == 0 && zero_byte.is_empty(),
+        master_key,
+        playlists_checked,
+        segments_checked,
+        missing,
+        missing_total,
+        zero_byte,
+    })
+}
+
+/// One variant entry of a rebuilt master playlist, with the corrected
+/// attributes the caller wants published.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RenditionSpec {
+    /// Rendition directory under the prefix (e.g. "480p").
+    pub name: String,
+    /// Peak bits per second for the BANDWIDTH attribute.
+    pub bandwidth: u64,
+    pub width: u32,
+    pub height: u32,
+    /// RFC 6381 codec string (e.g. "avc1.640028,mp4a.40.2"), omitted from
+    /// the playlist when None.
+    pub codecs: Option<String>,
+}
+
+/// The master playlist text for a set of rendition specs. Kept separate
+/// from the upload so the formatting is testable.
+fn master_playlist_for_specs(settings: &Settings, specs: &[RenditionSpec]) -> String {
+    let mut master = format!("#EXTM3U\n#EXT-X-VERSION:{}\n", settings.hls_version);
+    if settings.independent_segments {
+        master.push_str("#EXT-X-INDEPENDENT-SEGMENTS\n");
+    }
+    for spec in specs {
+        let codecs = spec
+            .codecs
+            .as_deref()
+            .map(|c| format!(",CODECS=\"{c}\""))
+            .unwrap_or_default();
+        master.push_str(&format!(
+            "#EXT-X-STREAM-INF:BANDWIDTH={},RESOLUTION={}x{}{codecs}\n{}/playlist.m3u8\n",
+            spec.bandwidth, spec.width, spec.height, spec.name
+        ));
+    }
+    master
+}
+
+/// Rewrite the master playlist of an already-uploaded package in place,
+/// referencing the existing media playlists with corrected BANDWIDTH,
+/// RESOLUTION and CODECS attributes — for fixing packages published before
+/// those attributes were accurate, without re-uploading any segments. Each
+/// referenced media playlist is verified to exist first. Returns the key
+/// of the rewritten master.
+#[tauri::command]
+pub async fn rebuild_master_playlist(
+    store: State<'_, SettingsStore>,
+    prefix: String,
+    renditions: Vec<RenditionSpec>,
+) -> Result<String> {
+    if renditions.is_empty() {
+        return Err(AppError::InvalidInput(
+            "a master playlist needs at least one rendition".into(),
+        ));
+    }
+    let settings = store.get();
+    let client = client(&settings)?;
+    let prefix = prefix.trim_end_matches('/');
+
+    for spec in &renditions {
+        let key = format!("{prefix}/{}/playlist.m3u8", spec.name);
+        client
+            .head_object()
+            .bucket(&settings.r2_bucket)
+            .key(&key)
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("media playlist {key} is missing: {e}")))?;
+    }
+
+    let master = master_playlist_for_specs(&settings, &renditions);
+    let master_key = format!("{prefix}/playlist.m3u8");
+    client
+        .put_object()
+        .bucket(&settings.r2_bucket)
+        .key(&master_key)
+        .content_type("application/vnd.apple.mpegurl")
+        .set_cache_control(cache_control_for(Path::new("playlist.m3u8"), &settings))
+        .body(ByteStream::from(master.into_bytes()))
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("put {master_key}: {e}")))?;
+    Ok(master_key)
+}
+
+/// Layout version written into every manifest; bump when fields change
+/// meaning so consumers can tell old packages apart.
+pub const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
+/// One rendition as described by a package manifest.
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct ManifestRendition {
+    pub name: String,
+    /// Parsed from the rendition name ("480p", "original-1080p"); 0 when
+    /// the name carries no height.
+    pub height: u32,
+    /// Playlist key relative to the package prefix.
+    pub playlist: String,
+    pub segment_count: usize,
+    pub bytes: u64,
+}
+
+/// The self-description written alongside an uploaded package as
+/// `manifest.json`, so the backend and player can consume one object
+/// instead of introspecting the bucket.
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct PackageManifest {
+    pub schema_version: u32,
+    pub movie_id: String,
+    pub title: Option<String>,
+    pub year: Option<u32>,
+    /// RFC 3339, set when the manifest was composed.
+    pub generated_at: String,
+    pub master_playlist: String,
+    pub renditions: Vec<ManifestRendition>,
+    pub audio_tracks: Vec<crate::ffmpeg::AudioTrack>,
+    /// Subtitle files in the package root, relative to the prefix.
+    pub subtitles: Vec<String>,
+    pub poster: Option<String>,
+    /// sha256 of each playlist, keyed by prefix-relative path. Segments
+    /// already carry sha256 in their object metadata from upload.
+    pub playlist_checksums: HashMap<String, String>,
+    pub total_bytes: u64,
+}
+
+/// Trailing "<digits>p" of a rendition name ("480p", "original-1080p").
+fn height_from_rendition_name(name: &str) -> u32 {
+    name.rsplit(['-', '_'])
+        .next()
+        .and_then(|part| part.strip_suffix('p'))
+        .and_then(|digits| digits.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Compose the manifest for a finished conversion from its local output
+/// directory, so a retried upload regenerates it identically.
+pub async fn build_manifest(
+    movie_id: &str,
+    title: Option<String>,
+    year: Option<u32>,
+    audio_tracks: Vec<crate::ffmpeg::AudioTrack>,
+    out_dir: &Path,
+) -> Result<PackageManifest> {
+    let mut renditions = Vec::new();
+    let mut subtitles = Vec::new();
+    let mut poster = None;
+    let mut playlist_checksums = HashMap::new();
+    let mut total_bytes = 0u64;
+
+    for (relative, absolute) in collect_files(out_dir)? {
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        total_bytes += tokio::fs::metadata(&absolute).await?.len();
+        match Path::new(&relative).extension().and_then(|e| e.to_str()) {
+            Some("m3u8") => {
+                playlist_checksums.insert(
+                    relative.clone(),
+                    file_checksum(&absolute, ChecksumAlgorithm::Sha256).await?,
+                );
+                if let Some((rendition, _)) = relative.rsplit_once('/') {
+                    let playlist = tokio::fs::read_to_string(&absolute).await?;
+                    let mut bytes = 0u64;
+                    let uris = playlist_uris(&playlist);
+                    for uri in &uris {
+                        if let Ok(meta) =
+                            tokio::fs::metadata(out_dir.join(rendition).join(uri)).await
+                        {
+                            bytes += meta.len();
+                        }
+                    }
+                    renditions.push(ManifestRendition {
+                        name: rendition.to_string(),
+                        height: height_from_rendition_name(rendition),
+                        playlist: relative,
+                        segment_count: uris.len(),
+                        bytes,
+                    });
+                }
+            }
+            Some("vtt") | Some("srt") => subtitles.push(relative),
+            Some("jpg") | Some("jpeg") | Some("png") | Some("webp")
+                if relative.starts_with("poster") =>
+            {
+                poster = Some(relative);
+            }
+            _ => {}
+        }
+    }
+    renditions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(PackageManifest {
+        schema_version: MANIFEST_SCHEMA_VERSION,
+        movie_id: movie_id.to_string(),
+        title,
+        year,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        master_playlist: "playlist.m3u8".into(),
+        renditions,
+        audio_tracks,
+        subtitles,
+        poster,
+        playlist_checksums,
+        total_bytes,
+    })
+}
+
+/// Put the manifest at `key` (normally `<prefix>/manifest.json`).
+pub async fn put_manifest(
+    client: &Client,
+    settings: &Settings,
+    key: &str,
+    manifest: &PackageManifest,
+) -> Result<()> {
+    let body = serde_json::to_string_pretty(manifest)
+        .map_err(|e| AppError::R2(format!("serialize manifest: {e}")))?;
+    client
+        .put_object()
+        .bucket(&settings.r2_bucket)
+        .key(key)
+        .content_type("application/json")
+        .set_cache_control(cache_control_for(Path::new("playlist.m3u8"), settings))
+        .body(ByteStream::from(body.into_bytes()))
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("put {key}: {e}")))?;
+    Ok(())
+}
+
+/// Fetch a package's `manifest.json` back from R2.
+#[tauri::command]
+pub async fn read_manifest(
+    store: State<'_, SettingsStore>,
+    prefix: String,
+) -> Result<PackageManifest> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let key = format!("{}/manifest.json", prefix.trim_end_matches('/'));
+    let resp = client
+        .get_object()
+        .bucket(&settings.r2_bucket)
+        .key(&key)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("get {key}: {e}")))?;
+    let bytes = resp
+        .body
+        .collect()
+        .await
+        .map_err(|e| AppError::R2(format!("read {key}: {e}")))?
+        .into_bytes();
+    serde_json::from_slice(&bytes)
+        .map_err(|e| AppError::R2(format!("{key} is not a valid manifest: {e}")))
+}
+
+/// Projected monthly bill for one package, at the configured rates. All
+/// figures are estimates for budgeting, not an invoice: real egress depends
+/// on how much of the film each viewer actually streams.
+#[derive(Debug, Clone, Serialize)]
+pub struct CostEstimate {
+    pub prefix: String,
+    pub total_bytes: u64,
+    pub size_gb: f64,
+    pub expected_monthly_views: u32,
+    pub storage_cost_per_month: f64,
+    /// Full-package transfers per month: views × package size.
+    pub egress_gb_per_month: f64,
+    pub egress_cost_per_month: f64,
+    pub total_cost_per_month: f64,
+}
+
+/// The arithmetic behind [`estimate_costs`], separated so it can be tested
+/// without a bucket.
+fn cost_breakdown(
+    rates: &crate::settings::CostRates,
+    prefix: String,
+    total_bytes: u64,
+    expected_monthly_views: u32,
+) -> CostEstimate {
+    let size_gb = total_bytes as f64 / 1_000_000_000.0;
+    let storage_cost_per_month = size_gb * rates.storage_per_gb_month;
+    let egress_gb_per_month = size_gb * expected_monthly_views as f64;
+    let egress_cost_per_month = egress_gb_per_month * rates.egress_per_gb;
+    CostEstimate {
+        prefix,
+        total_bytes,
+        size_gb,
+        expected_monthly_views,
+        storage_cost_per_month,
+        egress_gb_per_month,
+        egress_cost_per_month,
+        total_cost_per_month: storage_cost_per_month + egress_cost_per_month,
+    }
+}
+
+/// Sum the package under `prefix` and project its monthly storage and
+/// egress cost at the rates in settings.
+#[tauri::command]
+pub async fn estimate_costs(
+    store: State<'_, SettingsStore>,
+    prefix: String,
+    expected_monthly_views: u32,
+) -> Result<CostEstimate> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let mut total_bytes = 0u64;
+    let mut continuation: Option<String> = None;
+    loop {
+        let resp = client
+            .list_objects_v2()
+            .bucket(&settings.r2_bucket)
+            .prefix(&prefix)
+            .set_continuation_token(continuation.clone())
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("list {prefix}: {e}")))?;
+        for object in resp.contents() {
+            total_bytes += object.size().unwrap_or(0).max(0) as u64;
+        }
+        if resp.is_truncated() == Some(true) {
+            continuation = resp.next_continuation_token().map(String::from);
+        } else {
+            break;
+        }
+    }
+    Ok(cost_breakdown(
+        &settings.cost_rates,
+        prefix,
+        total_bytes,
+        expected_monthly_views,
+    ))
+}
+
+/// Cancel flag of the bucket-wide integrity pass currently running (at
+/// most one), managed as tauri state.
+pub struct BucketVerifier(std::sync::Mutex<Option<std::sync::Arc<std::sync::atomic::AtomicBool>>>);
+
+impl BucketVerifier {
+    pub fn new() -> Self {
+        Self(std::sync::Mutex::new(None))
+    }
+}
+
+/// Emitted on `verify-progress` as each package finishes.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyBucketProgress {
+    pub packages_done: usize,
+    pub packages_total: usize,
+    pub prefix: String,
+}
+
+/// Integrity findings for one package.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageIntegrity {
+    pub prefix: String,
+    pub playlists_checked: usize,
+    pub segments_checked: usize,
+    pub missing: Vec<String>,
+    pub zero_byte: Vec<String>,
+    /// Playlists whose bytes no longer match the sha256 their manifest
+    /// recorded.
+    pub checksum_mismatches: Vec<String>,
+    pub ok: bool,
+}
+
+/// Outcome of [`verify_bucket_integrity`], grouped by movie package.
+#[derive(Debug, Clone, Serialize)]
+pub struct BucketIntegrityReport {
+    pub objects_listed: usize,
+    pub packages: Vec<PackageIntegrity>,
+    pub cancelled: bool,
+    pub ok: bool,
+}
+
+/// Concurrent package verifications during a bucket-wide pass. Each one is
+/// a handful of playlist GETs; the heavy segment checks run against the
+/// in-memory listing.
+const BUCKET_VERIFY_CONCURRENCY: usize = 4;
+
+/// The package prefixes in a bucket listing: directories holding a master
+/// `playlist.m3u8` (one whose grandparent holds none — otherwise it's a
+/// rendition playlist) or a `manifest.json`.
+fn package_prefixes(keys: &HashMap<String, i64>) -> Vec<String> {
+    let mut prefixes: Vec<String> = keys
+        .keys()
+        .filter_map(|key| {
+            let prefix = key.strip_suffix("/playlist.m3u8")?;
+            match prefix.rsplit_once('/') {
+                Some((parent, _)) if keys.contains_key(&format!("{parent}/playlist.m3u8")) => None,
+                _ => Some(prefix.to_string()),
+            }
+        })
+        .chain(
+            keys.keys()
+                .filter_map(|key| key.strip_suffix("/manifest.json").map(str::to_string)),
+        )
+        .collect();
+    prefixes.sort();
+    prefixes.dedup();
+    prefixes
+}
+
+/// Verify one package against the listing: every playlist fetched, every
+/// referenced segment present and non-empty, and playlist checksums
+/// compared where a manifest records them.
+async fn verify_package(
+    client: &Client,
+    settings: &Settings,
+    prefix: &str,
+    sizes: &HashMap<String, i64>,
+    cancel: &std::sync::atomic::AtomicBool,
+) -> PackageIntegrity {
+    use sha2::{Digest, Sha256};
+    use std::sync::atomic::Ordering;
+
+    let mut report = PackageIntegrity {
+        prefix: prefix.to_string(),
+        playlists_checked: 0,
+        segments_checked: 0,
+        missing: Vec::new(),
+        zero_byte: Vec::new(),
+        checksum_mismatches: Vec::new(),
+        ok: false,
+    };
+    // Relative playlist path → fetched text, kept for checksum comparison.
+    let mut playlists: HashMap<String, String> = HashMap::new();
+
+    let master_key = format!("{prefix}/playlist.m3u8");
+    let master = match get_string(client, settings, &master_key).await {
+        Ok(master) => master,
+        Err(_) => {
+            report.missing.push(master_key);
+            return report;
+        }
+    };
+    report.playlists_checked += 1;
+    let media_uris: Vec<String> = playlist_uris(&master)
+        .into_iter()
+        .filter(|uri| uri.ends_with(".m3u8"))
+        .map(str::to_string)
+        .collect();
+    playlists.insert("playlist.m3u8".into(), master);
+
+    for uri in media_uris {
+        if cancel.load(Ordering::SeqCst) {
+            return report;
+        }
+        let key = format!("{prefix}/{uri}");
+        let media = match get_string(client, settings, &key).await {
+            Ok(media) => media,
+            Err(_) => {
+                report.missing.push(key);
+                continue;
+            }
+        };
+        report.playlists_checked += 1;
+        let rendition_dir = uri.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+        for segment in playlist_uris(&media) {
+            let segment_key = if rendition_dir.is_empty() {
+                format!("{prefix}/{segment}")
+            } else {
+                format!("{prefix}/{rendition_dir}/{segment}")
+            };
+            report.segments_checked += 1;
+            match sizes.get(&segment_key) {
+                None => report.missing.push(segment_key),
+                Some(0) => report.zero_byte.push(segment_key),
+                Some(_) => {}
+            }
+        }
+        playlists.insert(uri, media);
+    }
+
+    if sizes.contains_key(&format!("{prefix}/manifest.json")) {
+        if let Ok(manifest) = get_string(client, settings, &format!("{prefix}/manifest.json")).await
+        {
+            if let Ok(manifest) = serde_json::from_str::<PackageManifest>(&manifest) {
+                for (relative, expected) in &manifest.playlist_checksums {
+                    let Some(text) = playlists.get(relative) else { continue };
+                    let actual = hex::encode(Sha256::digest(text.as_bytes()));
+                    if &actual != expected {
+                        report.checksum_mismatches.push(format!("{prefix}/{relative}"));
+                    }
+                }
+            }
+        }
+    }
+
+    report.ok = report.missing.is_empty()
+        && report.zero_byte.is_empty()
+        && report.checksum_mismatches.is_empty();
+    report
+}
+
+/// Walk every package in the bucket and report anything missing, empty or
+/// corrupted, grouped by movie. Progress streams on `verify-progress`;
+/// `cancel_bucket_verify` stops the pass early with the packages done so
+/// far. Segment presence/size comes from one full listing rather than
+/// per-object HEADs, and package verifications run a few at a time.
+#[tauri::command]
+pub async fn verify_bucket_integrity(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    verifier: State<'_, BucketVerifier>,
+) -> Result<BucketIntegrityReport> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
+
+    let settings = store.get();
+    let client = client(&settings)?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = verifier.0.lock().unwrap().replace(cancel.clone()) {
+        previous.store(true, Ordering::SeqCst);
+    }
+
+    let mut sizes: HashMap<String, i64> = HashMap::new();
+    let mut continuation: Option<String> = None;
+    loop {
+        let resp = client
+            .list_objects_v2()
+            .bucket(&settings.r2_bucket)
+            .set_continuation_token(continuation.clone())
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("list bucket: {e}")))?;
+        for object in resp.contents() {
+            if let Some(key) = object.key() {
+                sizes.insert(key.to_string(), object.size().unwrap_or(0));
+            }
+        }
+        if resp.is_truncated() == Some(true) {
+            continuation = resp.next_continuation_token().map(String::from);
+        } else {
+            break;
+        }
+    }
+
+    let prefixes = package_prefixes(&sizes);
+    let packages_total = prefixes.len();
+    let sizes = Arc::new(sizes);
+    let settings = Arc::new(settings);
+    let permits = Arc::new(Semaphore::new(BUCKET_VERIFY_CONCURRENCY));
+    let mut handles = Vec::with_capacity(packages_total);
+    for prefix in prefixes {
+        let permits = permits.clone();
+        let client = client.clone();
+        let settings = settings.clone();
+        let sizes = sizes.clone();
+        let cancel = cancel.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = permits.acquire().await.expect("semaphore never closed");
+            verify_package(&client, &settings, &prefix, &sizes, &cancel).await
+        }));
+    }
+
+    let mut packages = Vec::with_capacity(packages_total);
+    for handle in handles {
+        let package = handle.await.expect("verify task panicked");
+        let _ = app.emit(
+            "verify-progress",
+            VerifyBucketProgress {
+                packages_done: packages.len() + 1,
+                packages_total,
+                prefix: package.prefix.clone(),
+            },
+        );
+        packages.push(package);
+    }
+    let cancelled = cancel.load(Ordering::SeqCst);
+    Ok(BucketIntegrityReport {
+        objects_listed: sizes.len(),
+        ok: !cancelled && packages.iter().all(|p| p.ok),
+        packages,
+        cancelled,
+    })
+}
+
+/// Stop the running bucket-wide integrity pass. Returns whether one was
+/// running.
+#[tauri::command]
+pub fn cancel_bucket_verify(verifier: State<'_, BucketVerifier>) -> Result<bool> {
+    use std::sync::atomic::Ordering;
+    match verifier.0.lock().unwrap().take() {
+        Some(cancel) => {
+            cancel.store(true, Ordering::SeqCst);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// One `movies` row as the frontend reads it from the site's database. The
+/// uploader has no database connection of its own (see `db`), so
+/// reconciliation takes the rows as input the same way `check_db_schema`
+/// takes introspected columns.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DbMovieRow {
+    pub id: String,
+    pub title: String,
+    pub r2_hls_path: Option<String>,
+    pub hls_ready: bool,
+}
+
+/// A movie present in both the database and the bucket, but broken. The
+/// title is carried along so the report identifies rows by more than a UUID.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconcileProblem {
+    pub movie_id: String,
+    pub title: String,
+    pub prefix: String,
+    pub problem: String,
+}
+
+/// Outcome of [`reconcile_library`]: how the database and the bucket have
+/// drifted apart, plus advisory SQL when fixes were requested.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconcileReport {
+    pub db_movies: usize,
+    pub bucket_packages: usize,
+    /// Movie ids whose HLS prefix holds no objects at all.
+    pub missing_from_bucket: Vec<String>,
+    /// Package prefixes no database row points at.
+    pub missing_from_db: Vec<String>,
+    pub inconsistent: Vec<ReconcileProblem>,
+    /// `INSERT` statements registering orphaned packages, when
+    /// `register_orphans` was set. Advisory: run them against the site's
+    /// database and fill in real metadata afterwards.
+    pub register_sql: Vec<String>,
+    /// `UPDATE` statements un-flagging dangling rows, when `flag_dangling`
+    /// was set. Advisory, like `register_sql`.
+    pub flag_sql: Vec<String>,
+}
+
+/// The package prefix a `r2_hls_path` column points at.
+fn hls_path_prefix(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    path.strip_suffix("/playlist.m3u8")
+        .unwrap_or_else(|| path.trim_end_matches('/'))
+        .to_string()
+}
+
+/// Escape a string for a single-quoted SQL literal.
+fn sql_literal(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+/// Compare database rows against a bucket listing. Pure so the set logic is
+/// testable without a bucket; the command wraps it with a real listing.
+fn reconcile_report(
+    movies: &[DbMovieRow],
+    sizes: &HashMap<String, i64>,
+    register_orphans: bool,
+    flag_dangling: bool,
+) -> ReconcileReport {
+    let bucket_prefixes = package_prefixes(sizes);
+    let mut claimed: Vec<String> = Vec::new();
+    let mut missing_from_bucket = Vec::new();
+    let mut inconsistent = Vec::new();
+    let mut flag_sql = Vec::new();
+
+    for movie in movies {
+        let Some(path) = movie.r2_hls_path.as_deref().filter(|p| !p.is_empty()) else {
+            if movie.hls_ready {
+                inconsistent.push(ReconcileProblem {
+                    movie_id: movie.id.clone(),
+                    title: movie.title.clone(),
+                    prefix: String::new(),
+                    problem: "hls_ready is set but r2_hls_path is empty".into(),
+                });
+            }
+            continue;
+        };
+        let prefix = hls_path_prefix(path);
+        claimed.push(prefix.clone());
+        let has_objects = sizes.keys().any(|key| {
+            key.strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+        });
+        if !has_objects {
+            missing_from_bucket.push(movie.id.clone());
+            if flag_dangling && movie.hls_ready {
+                flag_sql.push(format!(
+                    "UPDATE movies SET hls_ready = false WHERE id = '{}';",
+                    sql_literal(&movie.id)
+                ));
+            }
+        } else if !sizes.contains_key(&format!("{prefix}/playlist.m3u8")) {
+            inconsistent.push(ReconcileProblem {
+                movie_id: movie.id.clone(),
+                title: movie.title.clone(),
+                prefix,
+                problem: "package has objects but no master playlist".into(),
+            });
+        }
+    }
+
+    // A bucket prefix is accounted for if a row points at it or at an
+    // ancestor (a broken package's rendition dirs read as packages of their
+    // own; those are already reported as inconsistent, not as orphans).
+    let missing_from_db: Vec<String> = bucket_prefixes
+        .iter()
+        .filter(|prefix| {
+            !claimed.iter().any(|p| {
+                *prefix == p
+                    || prefix
+                        .strip_prefix(p.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+        })
+        .cloned()
+        .collect();
+
+    let register_sql = if register_orphans {
+        missing_from_db
+            .iter()
+            .map(|prefix| {
+                let title = prefix.rsplit('/').next().unwrap_or(prefix);
+                format!(
+                    "INSERT INTO movies (id, title, r2_hls_path, hls_ready) \
+                     VALUES (gen_random_uuid(), '{}', '{}/playlist.m3u8', true);",
+                    sql_literal(title),
+                    sql_literal(prefix)
+                )
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    ReconcileReport {
+        db_movies: movies.len(),
+        bucket_packages: bucket_prefixes.len(),
+        missing_from_bucket,
+        missing_from_db,
+        inconsistent,
+        register_sql,
+        flag_sql,
+    }
+}
+
+/// Reconcile the site's `movies` rows against the bucket: which movies lost
+/// their package, which packages lost their row, and which are present on
+/// both sides but broken. The fix flags only generate advisory SQL — this
+/// tool never touches the database and never deletes objects.
+#[tauri::command]
+pub async fn reconcile_library(
+    store: State<'_, SettingsStore>,
+    movies: Vec<DbMovieRow>,
+    register_orphans: bool,
+    flag_dangling: bool,
+) -> Result<ReconcileReport> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let mut sizes: HashMap<String, i64> = HashMap::new();
+    let mut continuation: Option<String> = None;
+    loop {
+        let resp = client
+            .list_objects_v2()
+            .bucket(&settings.r2_bucket)
+            .set_continuation_token(continuation.clone())
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("list bucket: {e}")))?;
+        for object in resp.contents() {
+            if let Some(key) = object.key() {
+                sizes.insert(key.to_string(), object.size().unwrap_or(0));
+            }
+        }
+        if resp.is_truncated() == Some(true) {
+            continuation = resp.next_continuation_token().map(String::from);
+        } else {
+            break;
+        }
+    }
+    Ok(reconcile_report(&movies, &sizes, register_orphans, flag_dangling))
+}
+
+/// One incomplete multipart upload left behind in the bucket.
+#[derive(Debug, Clone, Serialize)]
+pub struct IncompleteUpload {
+    pub key: String,
+    pub upload_id: String,
+    pub initiated: Option<String>,
+}
+
+/// Internal listing shared by the incomplete-upload commands, paginated for
+/// buckets that have accumulated many orphans.
+async fn incomplete_uploads(client: &Client, settings: &Settings) -> Result<Vec<IncompleteUpload>> {
+    let mut uploads = Vec::new();
+    let mut key_marker: Option<String> = None;
+    let mut upload_id_marker: Option<String> = None;
+    loop {
+        let resp = client
+            .list_multipart_uploads()
+            .bucket(&settings.r2_bucket)
+            .set_key_marker(key_marker.clone())
+            .set_upload_id_marker(upload_id_marker.clone())
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("list multipart uploads: {e}")))?;
+        for upload in resp.uploads() {
+            let (Some(key), Some(upload_id)) = (upload.key(), upload.upload_id()) else {
+                continue;
+            };
+            uploads.push(IncompleteUpload {
+                key: key.to_string(),
+                upload_id: upload_id.to_string(),
+                initiated: upload.initiated().map(|d| d.to_string()),
+            });
+        }
+        if resp.is_truncated() == Some(true) {
+            key_marker = resp.next_key_marker().map(String::from);
+            upload_id_marker = resp.next_upload_id_marker().map(String::from);
+        } else {
+            break;
+        }
+    }
+    Ok(uploads)
+}
+
+/// List multipart uploads that were started but never completed or aborted.
+/// These are invisible in object listings yet still billed for the parts
+/// they hold — typically leftovers from crashes mid-upload.
+#[tauri::command]
+pub async fn list_incomplete_uploads(
+    store: State<'_, SettingsStore>,
+) -> Result<Vec<IncompleteUpload>> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    incomplete_uploads(&client, &settings).await
+}
+
+/// Abort one incomplete multipart upload, freeing its stored parts.
+#[tauri::command]
+pub async fn abort_incomplete_upload(
+    store: State<'_, SettingsStore>,
+    key: String,
+    upload_id: String,
+) -> Result<()> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    client
+        .abort_multipart_upload()
+        .bucket(&settings.r2_bucket)
+        .key(&key)
+        .upload_id(&upload_id)
+        .send()
+        .await
+        .map_err(|e| AppError::R2(format!("abort multipart upload for {key}: {e}")))?;
+    Ok(())
+}
+
+/// Abort every incomplete multipart upload in the bucket; returns how many
+/// were aborted. A job that is actively uploading when this runs would lose
+/// its upload, so it's meant for cleanup between sessions.
+#[tauri::command]
+pub async fn abort_all_incomplete_uploads(store: State<'_, SettingsStore>) -> Result<usize> {
+    let settings = store.get();
+    let client = client(&settings)?;
+    let uploads = incomplete_uploads(&client, &settings).await?;
+    let aborted = uploads.len();
+    for upload in uploads {
+        client
+            .abort_multipart_upload()
+            .bucket(&settings.r2_bucket)
+            .key(&upload.key)
+            .upload_id(&upload.upload_id)
+            .send()
+            .await
+            .map_err(|e| AppError::R2(format!("abort multipart upload for {}: {e}", upload.key)))?;
+    }
+    Ok(aborted)
+}
+
+/// Cloudflare's purge endpoint accepts at most 30 files per call.
+const CDN_PURGE_BATCH: usize = 30;
+
+/// Outcome of purging one URL from the CDN cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct PurgeResult {
+    pub url: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Purge specific object URLs from Cloudflare's cache, so a re-uploaded
+/// playlist is visible immediately instead of after the cache TTL. Requires
+/// `cloudflare_zone_id` and `cloudflare_api_token` in settings. URLs are
+/// purged in API-sized batches; a failed batch marks each of its URLs
+/// failed without aborting the rest.
+#[tauri::command]
+pub async fn purge_cdn_cache(
+    store: State<'_, SettingsStore>,
+    urls: Vec<String>,
+) -> Result<Vec<PurgeResult>> {
+    let settings = store.get();
+    let zone = settings
+        .cloudflare_zone_id
+        .filter(|z| !z.is_empty())
+        .ok_or_else(|| AppError::Settings("cloudflare_zone_id is not configured".into()))?;
+    let token = settings
+        .cloudflare_api_token
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| AppError::Settings("cloudflare_api_token is not configured".into()))?;
+    if urls.is_empty() {
+        return Err(AppError::InvalidInput("no URLs to purge".into()));
+    }
+
+    let endpoint = format!("https://api.cloudflare.com/client/v4/zones/{zone}/purge_cache");
+    let http = reqwest::Client::new();
+    let mut results = Vec::with_capacity(urls.len());
+    for batch in urls.chunks(CDN_PURGE_BATCH) {
+        let outcome: std::result::Result<(), String> = async {
+            let resp = http
+                .post(&endpoint)
+                .bearer_auth(&token)
+                .json(&serde_json::json!({ "files": batch }))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let status = resp.status();
+            let body: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+            if status.is_success() && body.get("success").and_then(|s| s.as_bool()) == Some(true) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "purge API returned {status}: {}",
+                    body.get("errors").cloned().unwrap_or_default()
+                ))
+            }
+        }
+        .await;
+        for url in batch {
+            results.push(PurgeResult {
+                url: url.clone(),
+                ok: outcome.is_ok(),
+                error: outcome.as_ref().err().cloned(),
+            });
+        }
+    }
+    Ok(results)
+}
+
+/// Cancel flag of the CDN warm currently running (at most one), managed as
+/// tauri state.
+pub struct CdnWarmer(std::sync::Mutex<Option<std::sync::Arc<std::sync::atomic::AtomicBool>>>);
+
+impl CdnWarmer {
+    pub fn new() -> Self {
+        Self(std::sync::Mutex::new(None))
+    }
+}
+
+/// Concurrent warm requests in flight at once.
+const WARM_CONCURRENCY: usize = 8;
+
+/// Segments fetched per rendition when warming by prefix — roughly what a
+/// player buffers before playback starts.
+const WARM_SEGMENTS_PER_RENDITION: usize = 3;
+
+/// Outcome of warming one URL.
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmResult {
+    pub url: String,
+    /// HTTP status, when the request got a response at all.
+    pub status: Option<u16>,
+    pub elapsed_ms: u64,
+    pub bytes: u64,
+    /// Cloudflare's `cf-cache-status` header (HIT once the edge has it).
+    pub cache_status: Option<String>,
+    pub error: Option<String>,
+    pub cancelled: bool,
+}
+
+/// The URIs a player fetches in its first seconds of one rendition: the
+/// init segment when present, plus the first `limit` media segments.
+fn startup_uris(media: &str, limit: usize) -> Vec<String> {
+    let mut uris = Vec::new();
+    let mut segments = 0;
+    for line in media.lines().map(str::trim) {
+        if line.starts_with("#EXT-X-MAP") {
+            if let Some(uri) = uri_attribute(line) {
+                uris.push(uri.to_string());
+            }
+        } else if !line.is_empty() && !line.starts_with('#') && segments < limit {
+            uris.push(line.to_string());
+            segments += 1;
+        }
+    }
+    uris
+}
+
+/// Pull a package's startup assets through the public CDN so the first
+/// viewer hits a warm edge cache: the master playlist, each media playlist
+/// and the first few segments of every rendition. Pass either explicit
+/// `urls` or a package `prefix` to derive them from (needs `cdn_base_url`).
+/// Distinct from `purge_cdn_cache` — this fills the cache, that empties it.
+#[tauri::command]
+pub async fn warm_cdn(
+    store: State<'_, SettingsStore>,
+    warmer: State<'_, CdnWarmer>,
+    urls: Option<Vec<String>>,
+    prefix: Option<String>,
+) -> Result<Vec<WarmResult>> {
+    use std::sync::atomic::Ordering;
+
+    let settings = store.get();
+    let urls = match (urls, prefix) {
+        (Some(urls), None) => urls,
+        (None, Some(prefix)) => {
+            let base = settings
+                .cdn_base_url
+                .clone()
+                .filter(|b| !b.is_empty())
+                .ok_or_else(|| {
+                    AppError::Settings(
+                        "cdn_base_url is not configured; set it or pass explicit URLs".into(),
+                    )
+                })?;
+            let base = base.trim_end_matches('/').to_string();
+            let client = client(&settings)?;
+            let master_key = format!("{}/playlist.m3u8", prefix.trim_matches('/'));
+            let master = get_string(&client, &settings, &master_key).await?;
+            let mut keys = vec![master_key.clone()];
+            for uri in playlist_uris(&master) {
+                let media_key = resolve_key(&master_key, uri);
+                let media = get_string(&client, &settings, &media_key).await?;
+                for segment in startup_uris(&media, WARM_SEGMENTS_PER_RENDITION) {
+                    keys.push(resolve_key(&media_key, &segment));
+                }
+                keys.push(media_key);
+            }
+            keys.into_iter().map(|k| format!("{base}/{k}")).collect()
+        }
+        _ => {
+            return Err(AppError::InvalidInput(
+                "pass either urls or a package prefix, not both".into(),
+            ))
+        }
+    };
+    if urls.is_empty() {
+        return Err(AppError::InvalidInput("no URLs to warm".into()));
+    }
+
+    let cancelled = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    *warmer.0.lock().unwrap() = Some(cancelled.clone());
+
+    let http = reqwest::Client::new();
+    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(WARM_CONCURRENCY));
+    let mut handles = Vec::with_capacity(urls.len());
+    for url in urls {
+        let semaphore = semaphore.clone();
+        let http = http.clone();
+        let cancelled = cancelled.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire().await.expect("semaphore closed");
+            if cancelled.load(Ordering::SeqCst) {
+                return WarmResult {
+                    url,
+                    status: None,
+                    elapsed_ms: 0,
+                    bytes: 0,
+                    cache_status: None,
+                    error: None,
+                    cancelled: true,
+                };
+            }
+            let started = std::time::Instant::now();
+            let outcome = async {
+                let resp = http.get(&url).send().await.map_err(|e| e.to_string())?;
+                let status = resp.status().as_u16();
+                let cache_status = resp
+                    .headers()
+                    .get("cf-cache-status")
+                    .and_then(|v| v.to_str().ok())
+                    .map(String::from);
+                // Reading the whole body is what actually populates the
+                // edge cache.
+                let bytes = resp.bytes().await.map_err(|e| e.to_string())?.len() as u64;
+                Ok::<_, String>((status, cache_status, bytes))
+            }
+            .await;
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            match outcome {
+                Ok((status, cache_status, bytes)) => WarmResult {
+                    url,
+                    status: Some(status),
+                    elapsed_ms,
+                    bytes,
+                    cache_status,
+                    error: None,
+                    cancelled: false,
+                },
+                Err(error) => WarmResult {
+                    url,
+                    status: None,
+                    elapsed_ms,
+                    bytes: 0,
+                    cache_status: None,
+                    error: Some(error),
+                    cancelled: false,
+                },
+            }
+        }));
+    }
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.expect("warm task panicked"));
+    }
+    *warmer.0.lock().unwrap() = None;
+    Ok(results)
+}
+
+/// Stop the running CDN warm. Requests already in flight finish; queued
+/// ones come back marked cancelled. Returns whether one was running.
+#[tauri::command]
+pub fn cancel_cdn_warm(warmer: State<'_, CdnWarmer>) -> Result<bool> {
+    use std::sync::atomic::Ordering;
+    match warmer.0.lock().unwrap().take() {
+        Some(cancel) => {
+            cancel.store(true, Ordering::SeqCst);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_validation_enforces_the_s3_limits() {
+        let one = HashMap::from([("tier".to_string(), "archive".to_string())]);
+        assert!(validate_tags(&one).is_ok());
+        assert_eq!(tagging_query(&one), "tier=archive");
+
+        // Spaces and slashes are legal in tags but must be url-encoded in
+        // the Tagging request parameter.
+        let spaced = HashMap::from([("release year".to_string(), "1972/05".to_string())]);
+        assert!(validate_tags(&spaced).is_ok());
+        assert_eq!(tagging_query(&spaced), "release%20year=1972%2F05");
+
+        let eleven: HashMap<_, _> = (0..11).map(|i| (format!("k{i}"), String::new())).collect();
+        assert!(matches!(validate_tags(&eleven), Err(AppError::InvalidInput(_))));
+        assert!(matches!(
+            validate_tags(&HashMap::from([("bad\"quote".to_string(), "x".to_string())])),
+            Err(AppError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            validate_tags(&HashMap::from([("k".repeat(129), "v".to_string())])),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn key_template_expands_per_object() {
+        let template = "hls/{slug}/{rendition}/{segment}";
+        assert_eq!(
+            resolve_object_key(template, "the-matrix", None, Path::new("480p/segment_000.ts")),
+            "hls/the-matrix/480p/segment_000.ts"
+        );
+        // The master playlist has no rendition directory; the empty
+        // placeholder collapses instead of leaving a double slash.
+        assert_eq!(
+            resolve_object_key(template, "the-matrix", None, Path::new("playlist.m3u8")),
+            "hls/the-matrix/playlist.m3u8"
+        );
+        assert_eq!(
+            resolve_object_key(
+                "movies/{year}/{slug}/{rendition}/{segment}",
+                "the-matrix",
+                Some(1999),
+                Path::new("480p/segment_000.ts")
+            ),
+            "movies/1999/the-matrix/480p/segment_000.ts"
+        );
+    }
+
+    #[test]
+    fn key_template_validation_rejects_collision_prone_templates() {
+        assert!(validate_key_template("hls/{slug}/{rendition}/{segment}").is_ok());
+        assert!(validate_key_template("hls/{slug}/{rendition}").is_err());
+        assert!(validate_key_template("{segment}").is_err());
+        assert!(validate_key_template("hls/{slug}/{typo}/{segment}").is_err());
+        assert!(validate_key_template("hls/{slug}/a b/{segment}").is_err());
+    }
+
+    #[test]
+    fn resolves_playlist_relative_keys() {
+        assert_eq!(
+            resolve_key("hls/movie/playlist.m3u8", "480p/playlist.m3u8"),
+            "hls/movie/480p/playlist.m3u8"
+        );
+        assert_eq!(
+            resolve_key("hls/movie/480p/playlist.m3u8", "segment_000.ts"),
+            "hls/movie/480p/segment_000.ts"
+        );
+        assert_eq!(resolve_key("playlist.m3u8", "a.ts"), "a.ts");
+        assert_eq!(
+            playlist_uris("#EXTM3U\n#EXTINF:6.0,\nsegment_000.ts\n\n#EXT-X-ENDLIST\n"),
+            vec!["segment_000.ts"]
+        );
+    }
+
+    #[test]
+    fn package_prefixes_spot_masters_but_not_rendition_playlists() {
+        let sizes: HashMap<String, i64> = [
+            ("hls/alpha/playlist.m3u8", 100),
+            ("hls/alpha/480p/playlist.m3u8", 100),
+            ("hls/alpha/480p/segment_000.ts", 4096),
+            ("hls/beta/playlist.m3u8", 100),
+            ("hls/beta/manifest.json", 100),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        assert_eq!(package_prefixes(&sizes), vec!["hls/alpha", "hls/beta"]);
+    }
+
+    #[test]
+    fn metered_property_values_map_to_yes_no_unknown() {
+        // busctl prints "u 4"; a bare value should parse too.
+        assert_eq!(parse_metered_property("u 1\n"), Some(true));
+        assert_eq!(parse_metered_property("u 3"), Some(true));
+        assert_eq!(parse_metered_property("u 2"), Some(false));
+        assert_eq!(parse_metered_property("4"), Some(false));
+        assert_eq!(parse_metered_property("u 0"), None);
+        assert_eq!(parse_metered_property("garbage"), None);
+    }
+
+    #[test]
+    fn collision_strategies_resolve_against_a_mock_bucket() {
+        use crate::settings::CollisionStrategy;
+
+        let bucket: std::collections::HashSet<&str> = ["movie"].into_iter().collect();
+        let exists = |name: &str| bucket.contains(name);
+
+        // A free destination is kept regardless of strategy.
+        for strategy in [
+            CollisionStrategy::Fail,
+            CollisionStrategy::AppendTimestamp,
+            CollisionStrategy::Overwrite,
+        ] {
+            assert_eq!(
+                resolve_collision(strategy, "fresh", "20260901-120000", exists),
+                CollisionResolution::Keep
+            );
+        }
+        assert_eq!(
+            resolve_collision(CollisionStrategy::Fail, "movie", "20260901-120000", exists),
+            CollisionResolution::Refused
+        );
+        assert_eq!(
+            resolve_collision(CollisionStrategy::Overwrite, "movie", "20260901-120000", exists),
+            CollisionResolution::Overwrite
+        );
+        assert_eq!(
+            resolve_collision(
+                CollisionStrategy::AppendTimestamp,
+                "movie",
+                "20260901-120000",
+                exists
+            ),
+            CollisionResolution::Renamed("movie-20260901-120000".into())
+        );
+    }
+
+    #[test]
+    fn reconciliation_sorts_drift_into_the_three_sets() {
+        let sizes: HashMap<String, i64> = [
+            ("hls/alpha/playlist.m3u8", 100),
+            ("hls/alpha/480p/playlist.m3u8", 100),
+            ("hls/beta/playlist.m3u8", 100),
+            ("hls/gamma/480p/playlist.m3u8", 100),
+            ("hls/gamma/480p/segment_000.ts", 4096),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        let movies = vec![
+            DbMovieRow {
+                id: "a".into(),
+                title: "Alpha".into(),
+                r2_hls_path: Some("hls/alpha/playlist.m3u8".into()),
+                hls_ready: true,
+            },
+            DbMovieRow {
+                id: "g".into(),
+                title: "Gamma".into(),
+                r2_hls_path: Some("hls/gamma".into()),
+                hls_ready: true,
+            },
+            DbMovieRow {
+                id: "d".into(),
+                title: "Deleted".into(),
+                r2_hls_path: Some("hls/deleted".into()),
+                hls_ready: true,
+            },
+        ];
+        let report = reconcile_report(&movies, &sizes, true, true);
+        // Deleted's prefix holds nothing; gamma has segments but no master;
+        // beta has a master but no row.
+        assert_eq!(report.missing_from_bucket, vec!["d"]);
+        assert_eq!(report.missing_from_db, vec!["hls/beta"]);
+        assert_eq!(report.inconsistent.len(), 1);
+        assert_eq!(report.inconsistent[0].movie_id, "g");
+        assert_eq!(report.inconsistent[0].title, "Gamma");
+        assert_eq!(
+            report.flag_sql,
+            vec!["UPDATE movies SET hls_ready = false WHERE id = 'd';"]
+        );
+        assert_eq!(report.register_sql.len(), 1);
+        assert!(report.register_sql[0].contains("'hls/beta/playlist.m3u8'"));
+    }
+
+    #[test]
+    fn cost_breakdown_scales_egress_with_views() {
+        let rates = crate::settings::CostRates {
+            storage_per_gb_month: 0.015,
+            egress_per_gb: 0.05,
+        };
+        // 4 GB package, 100 views/month.
+        let estimate = cost_breakdown(&rates, "hls/movie".into(), 4_000_000_000, 100);
+        assert!((estimate.size_gb - 4.0).abs() < 1e-9);
+        assert!((estimate.storage_cost_per_month - 0.06).abs() < 1e-9);
+        assert!((estimate.egress_gb_per_month - 400.0).abs() < 1e-9);
+        assert!((estimate.egress_cost_per_month - 20.0).abs() < 1e-9);
+        assert!((estimate.total_cost_per_month - 20.06).abs() < 1e-9);
+    }
+
+    #[test]
+    fn manifest_heights_come_from_rendition_names() {
+        assert_eq!(height_from_rendition_name("480p"), 480);
+        assert_eq!(height_from_rendition_name("original-1080p"), 1080);
+        assert_eq!(height_from_rendition_name("audio"), 0);
+    }
+
+    #[test]
+    fn rebuilt_master_lists_each_rendition_with_its_attributes() {
+        let settings = Settings::default();
+        let specs = vec![
+            RenditionSpec {
+                name: "480p".into(),
+                bandwidth: 1_528_000,
+                width: 854,
+                height: 480,
+                codecs: Some("avc1.64001f,mp4a.40.2".into()),
+            },
+            RenditionSpec {
+                name: "original-1080p".into(),
+                bandwidth: 5_128_000,
+                width: 1920,
+                height: 1080,
+                codecs: None,
+            },
+        ];
+        let master = master_playlist_for_specs(&settings, &specs);
+        assert!(master.starts_with("#EXTM3U\n"));
+        assert!(master.contains(
+            "#EXT-X-STREAM-INF:BANDWIDTH=1528000,RESOLUTION=854x480,CODECS=\"avc1.64001f,mp4a.40.2\"\n480p/playlist.m3u8\n"
+        ));
+        assert!(master.contains(
+            "#EXT-X-STREAM-INF:BANDWIDTH=5128000,RESOLUTION=1920x1080\noriginal-1080p/playlist.m3u8\n"
+        ));
+    }
+
+    #[test]
+    fn key_material_never_leaves_the_machine() {
+        assert!(is_local_only(Path::new("/out/movie/hls.key")));
+        assert!(is_local_only(Path::new("/out/movie/hls.keyinfo")));
+        assert!(!is_local_only(Path::new("/out/movie/480p/segment_000.ts")));
+    }
+
+    #[test]
+    fn etag_sidecar_sits_next_to_the_download() {
+        assert_eq!(
+            etag_sidecar(Path::new("/downloads/movie.mp4")),
+            Path::new("/downloads/movie.mp4.etag")
+        );
+    }
+
+    #[test]
+    fn remux_helpers_pick_variants_and_require_keys() {
+        let master = "#EXTM3U\n\
+                      #EXT-X-STREAM-INF:BANDWIDTH=1528000,RESOLUTION=854x480\n480p/playlist.m3u8\n\
+                      #EXT-X-STREAM-INF:BANDWIDTH=5128000,RESOLUTION=1920x1080\n1080p/playlist.m3u8\n";
+        assert_eq!(top_variant_uri(master).as_deref(), Some("1080p/playlist.m3u8"));
+        assert_eq!(top_variant_uri("#EXTM3U\n"), None);
+
+        let clear = "#EXTM3U\n#EXTINF:6.0,\nsegment_000.ts\n";
+        assert_eq!(localize_media_playlist(clear, None).unwrap(), clear);
+
+        let encrypted = "#EXTM3U\n\
+                         #EXT-X-KEY:METHOD=AES-128,URI=\"https://keys.example/movie\",IV=0x0\n\
+                         #EXTINF:6.0,\nsegment_000.ts\n";
+        assert!(matches!(
+            localize_media_playlist(encrypted, None),
+            Err(AppError::InvalidInput(_))
+        ));
+        let localized =
+            localize_media_playlist(encrypted, Some(Path::new("/tmp/hls.key"))).unwrap();
+        assert!(localized.contains("URI=\"/tmp/hls.key\""));
+        assert!(!localized.contains("keys.example"));
+    }
+
+    #[test]
+    fn warming_targets_the_init_segment_and_the_first_media_segments() {
+        let media = "#EXTM3U\n\
+                     #EXT-X-MAP:URI=\"init.mp4\"\n\
+                     #EXTINF:6.0,\nsegment_000.m4s\n\
+                     #EXTINF:6.0,\nsegment_001.m4s\n\
+                     #EXTINF:6.0,\nsegment_002.m4s\n\
+                     #EXTINF:6.0,\nsegment_003.m4s\n";
+        assert_eq!(
+            startup_uris(media, 3),
+            vec!["init.mp4", "segment_000.m4s", "segment_001.m4s", "segment_002.m4s"]
+        );
+        assert_eq!(startup_uris("#EXTM3U\n", 3), Vec::<String>::new());
+    }
+
+    #[test]
+    fn content_type_mapping_covers_hls_extensions() {
+        assert_eq!(guess_content_type(Path::new("playlist.m3u8")), "application/vnd.apple.mpegurl");
+        assert_eq!(guess_content_type(Path::new("segment_000.ts")), "video/mp2t");
+        assert_eq!(guess_content_type(Path::new("init.m4s")), "video/iso.segment");
+        assert_eq!(guess_content_type(Path::new("movie.mp4")), "video/mp4");
+        assert_eq!(guess_content_type(Path::new("manifest.mpd")), "application/dash+xml");
+        assert_eq!(guess_content_type(Path::new("subs.vtt")), "text/vtt");
+        assert_eq!(guess_content_type(Path::new("poster.jpg")), "image/jpeg");
+        assert_eq!(guess_content_type(Path::new("poster.png")), "image/png");
+    }
+
+    #[test]
+    fn cache_control_distinguishes_segments_from_manifests() {
+        let settings = Settings::default();
+        let segment = cache_control_for(Path::new("segment_000.ts"), &settings).unwrap();
+        assert!(segment.contains("immutable"));
+        assert!(segment.contains(&settings.segment_cache_max_age.to_string()));
+        let playlist = cache_control_for(Path::new("playlist.m3u8"), &settings).unwrap();
+        assert!(!playlist.contains("immutable"));
+        assert!(playlist.contains(&settings.playlist_cache_max_age.to_string()));
+        assert_eq!(cache_control_for(Path::new("poster.jpg"), &settings), None);
+    }
+
+    #[tokio::test]
+    async fn checksums_match_known_digests() {
+        let path = std::env::temp_dir().join(format!("uploader-checksum-test-{}", std::process::id()));
+        tokio::fs::write(&path, b"abc").await.unwrap();
+        assert_eq!(
+            file_checksum(&path, ChecksumAlgorithm::Md5).await.unwrap(),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            file_checksum(&path, ChecksumAlgorithm::Sha256).await.unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[test]
+    fn schedule_windows_override_global_bandwidth_cap() {
+        use crate::settings::BandwidthWindow;
+
+        let mut settings = Settings::default();
+        settings.max_upload_mbps = Some(100.0);
+        settings.bandwidth_schedule = vec![BandwidthWindow {
+            start_hour: 9,
+            end_hour: 17,
+            max_mbps: 10.0,
+        }];
+        assert_eq!(applicable_limit_mbps(&settings, 12), Some(10.0));
+        assert_eq!(applicable_limit_mbps(&settings, 20), Some(100.0));
+    }
+
+    #[test]
+    fn schedule_window_wraps_midnight() {
+        use crate::settings::BandwidthWindow;
+
+        let mut settings = Settings::default();
+        settings.bandwidth_schedule = vec![BandwidthWindow {
+            start_hour: 22,
+            end_hour: 6,
+            max_mbps: 50.0,
+        }];
+        assert_eq!(applicable_limit_mbps(&settings, 23), Some(50.0));
+        assert_eq!(applicable_limit_mbps(&settings, 3), Some(50.0));
+        // Outside the window with no global cap: unlimited.
+        assert_eq!(applicable_limit_mbps(&settings, 12), None);
+    }
+
+    #[test]
+    fn content_type_defaults_to_octet_stream() {
+        assert_eq!(guess_content_type(Path::new("notes.xyz")), "application/octet-stream");
+        assert_eq!(guess_content_type(Path::new("no_extension")), "application/octet-stream");
+    }
+}
@@ -0,0 +1,181 @@
+//! Library directory scanning.
+//!
+//! Walking a library of tens of thousands of files can take minutes on
+//! network storage, so the scan runs on a blocking task, streams
+//! `scan-progress` events with running counts, honors a cancel flag, and
+//! caps the result set so one misdirected scan of `/` can't eat all memory.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::{AppError, Result};
+
+/// Extensions treated as video sources when scanning a library.
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "avi", "mov", "m4v", "webm", "wmv", "mpg", "mpeg", "ts",
+];
+
+/// Hard cap on returned paths; hitting it truncates the result with a
+/// warning rather than growing without bound.
+const MAX_SCAN_RESULTS: usize = 10_000;
+
+/// How many directory entries between `scan-progress` emissions — frequent
+/// enough to feel live, rare enough not to flood the event channel.
+const PROGRESS_EVERY: usize = 500;
+
+/// The cancel flag of the scan currently running (at most one), managed as
+/// tauri state.
+pub struct Scanner(Mutex<Option<Arc<AtomicBool>>>);
+
+impl Scanner {
+    pub fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+}
+
+/// Running counts emitted on `scan-progress` while the walk proceeds.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    pub dirs_scanned: usize,
+    pub files_seen: usize,
+    pub matches: usize,
+}
+
+/// Outcome of a scan. `files` is sorted; `truncated` means the cap was hit
+/// and deeper matches were dropped.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanResult {
+    pub files: Vec<PathBuf>,
+    pub dirs_scanned: usize,
+    pub files_seen: usize,
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+fn is_video_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| VIDEO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// The walk itself, run off the async runtime. Unreadable directories are
+/// skipped rather than failing the whole scan.
+fn walk(app: &AppHandle, root: &Path, cancel: &AtomicBool) -> ScanResult {
+    let mut result = ScanResult {
+        files: Vec::new(),
+        dirs_scanned: 0,
+        files_seen: 0,
+        truncated: false,
+        cancelled: false,
+    };
+    let mut stack = vec![root.to_path_buf()];
+    let mut since_progress = 0usize;
+    'outer: while let Some(dir) = stack.pop() {
+        result.dirs_scanned += 1;
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            if cancel.load(Ordering::SeqCst) {
+                result.cancelled = true;
+                break 'outer;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                result.files_seen += 1;
+                if is_video_file(&path) {
+                    if result.files.len() == MAX_SCAN_RESULTS {
+                        result.truncated = true;
+                        let _ = app.emit(
+                            "scan-warning",
+                            format!("scan hit the {MAX_SCAN_RESULTS}-file cap; result truncated"),
+                        );
+                        break 'outer;
+                    }
+                    result.files.push(path);
+                }
+            }
+            since_progress += 1;
+            if since_progress >= PROGRESS_EVERY {
+                since_progress = 0;
+                let _ = app.emit(
+                    "scan-progress",
+                    ScanProgress {
+                        dirs_scanned: result.dirs_scanned,
+                        files_seen: result.files_seen,
+                        matches: result.files.len(),
+                    },
+                );
+            }
+        }
+    }
+    result.files.sort();
+    result
+}
+
+/// Recursively scan `root` for video files, streaming progress events and
+/// returning the (sorted, possibly truncated) list when the walk finishes.
+/// Starting a new scan cancels any scan still running.
+#[tauri::command]
+pub async fn scan_video_files(
+    app: AppHandle,
+    scanner: State<'_, Scanner>,
+    root: PathBuf,
+) -> Result<ScanResult> {
+    if !root.is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "{} is not a directory",
+            root.display()
+        )));
+    }
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = scanner.0.lock().unwrap().replace(cancel.clone()) {
+        previous.store(true, Ordering::SeqCst);
+    }
+
+    let walker_app = app.clone();
+    let walker_cancel = cancel.clone();
+    let result = tokio::task::spawn_blocking(move || walk(&walker_app, &root, &walker_cancel))
+        .await
+        .expect("scan task panicked");
+    let _ = app.emit(
+        "scan-progress",
+        ScanProgress {
+            dirs_scanned: result.dirs_scanned,
+            files_seen: result.files_seen,
+            matches: result.files.len(),
+        },
+    );
+    Ok(result)
+}
+
+/// Stop the running scan; it returns promptly with what it found so far.
+/// Returns whether a scan was running.
+#[tauri::command]
+pub fn cancel_scan(scanner: State<'_, Scanner>) -> Result<bool> {
+    match scanner.0.lock().unwrap().take() {
+        Some(cancel) => {
+            cancel.store(true, Ordering::SeqCst);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_video_extensions_case_insensitively() {
+        assert!(is_video_file(Path::new("/lib/Movie.MKV")));
+        assert!(is_video_file(Path::new("/lib/movie.mp4")));
+        assert!(!is_video_file(Path::new("/lib/movie.srt")));
+        assert!(!is_video_file(Path::new("/lib/README")));
+    }
+}
@@ -0,0 +1,619 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::error::{AppError, Result};
+
+const KEYCHAIN_SERVICE: &str = "cinemafred-uploader";
+const KEYCHAIN_ACCOUNT: &str = "r2_secret_access_key";
+
+/// Where the R2 secret actually lives. The JSON store only records which
+/// backend is active; with `Keychain` the secret itself never touches disk
+/// outside the platform secret store.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    Keychain,
+    /// Fallback when no keychain is available (e.g. headless Linux): the
+    /// secret stays in the JSON store.
+    #[default]
+    Store,
+}
+
+/// A secondary S3-compatible destination every upload is copied to, for
+/// redundancy (e.g. a B2 backup of the R2 bucket). Each target brings its
+/// own endpoint and credentials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Target {
+    pub endpoint: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// When true, a failed copy to this target fails the upload instead of
+    /// only emitting a warning.
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Container format for HLS media segments.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HlsSegmentType {
+    #[default]
+    Mpegts,
+    /// Fragmented MP4 segments; per the HLS spec these require playlist
+    /// version 6 or later.
+    Fmp4,
+}
+
+/// Playlist type written to generated media playlists. VOD playlists end
+/// with `#EXT-X-ENDLIST`; event playlists don't, telling players more
+/// segments may still be appended.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HlsPlaylistType {
+    #[default]
+    Vod,
+    Event,
+}
+
+/// Whether conversion regenerates timestamps (`-fflags +genpts`) for
+/// sources with broken DTS/PTS. Auto scans the source first and repairs
+/// only when non-monotonic timestamps are actually detected.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFix {
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+/// What the upload phase does when a package's destination (its master
+/// playlist key) already holds an object.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionStrategy {
+    /// Refuse with an `object_exists` error.
+    #[default]
+    Fail,
+    /// Suffix the slug (or manifest-supplied prefix) with a timestamp so the
+    /// whole package — segments, playlists and manifest — lands under one
+    /// fresh path.
+    AppendTimestamp,
+    /// Replace the existing package in place.
+    Overwrite,
+}
+
+/// How audio reaches the player across the rendition ladder.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioStrategy {
+    /// Audio muxed into every video variant. Simplest and the most widely
+    /// compatible, at the cost of carrying the audio once per rendition.
+    #[default]
+    PerRendition,
+    /// One shared audio rendition referenced by every variant through
+    /// `#EXT-X-MEDIA` — the standard HLS layout, and smaller, but some
+    /// players handle separate audio poorly.
+    SharedGroup,
+}
+
+/// Per-GB billing rates used by cost estimation. Defaults match R2's
+/// standard pricing — $0.015/GB-month storage and free egress; self-hosters
+/// fronting the bucket with a paid CDN can override the egress rate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostRates {
+    pub storage_per_gb_month: f64,
+    pub egress_per_gb: f64,
+}
+
+impl Default for CostRates {
+    fn default() -> Self {
+        Self {
+            storage_per_gb_month: 0.015,
+            egress_per_gb: 0.0,
+        }
+    }
+}
+
+/// One scheduled bandwidth window, matched against the local hour of day.
+/// Windows may wrap midnight (`start_hour: 22, end_hour: 6`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthWindow {
+    pub start_hour: u8,
+    pub end_hour: u8,
+    pub max_mbps: f64,
+}
+
+/// Persisted app configuration. Stored as JSON in the user config dir so the
+/// same settings survive app updates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub r2_account_id: String,
+    pub r2_access_key_id: String,
+    pub r2_secret_access_key: String,
+    /// Which backend currently holds the secret; surfaced in the UI so users
+    /// know whether the keychain is in use.
+    pub secret_backend: SecretBackend,
+    pub r2_bucket: String,
+    /// Where converted HLS output is written before upload.
+    pub output_dir: PathBuf,
+    /// Seconds per HLS segment.
+    pub segment_duration: u32,
+    /// `#EXT-X-VERSION` written to generated playlists (3-7); older players
+    /// only understand lower versions.
+    pub hls_version: u32,
+    /// Emit `#EXT-X-INDEPENDENT-SEGMENTS` and the matching `-hls_flags`, for
+    /// players that seek mid-segment.
+    pub independent_segments: bool,
+    /// Segment container; fMP4 requires `hls_version` >= 6.
+    pub hls_segment_type: HlsSegmentType,
+    /// Write each rendition as one `.ts` addressed by `#EXT-X-BYTERANGE`
+    /// entries (ffmpeg's `-hls_flags single_file`) instead of thousands of
+    /// segment objects — 3-hour films otherwise balloon R2 object counts.
+    /// TS segments only.
+    pub hls_single_file: bool,
+    /// Passed to ffmpeg's `-hls_playlist_type`. Vod for finished movies
+    /// (the normal case); Event leaves the playlist open-ended.
+    pub hls_playlist_type: HlsPlaylistType,
+    /// Kill a rendition's ffmpeg and fail the job if it runs longer than
+    /// this, or reports no progress for a grace period — hung encodes (bad
+    /// input, stalled hardware) otherwise block the queue forever. None
+    /// disables the watchdog.
+    pub conversion_timeout_secs: Option<u32>,
+    /// How many conversion jobs may run at once.
+    pub max_concurrent_jobs: usize,
+    /// How many hardware-encoded jobs may run at once. Consumer NVIDIA cards
+    /// cap simultaneous nvenc sessions (often 3-5), so this is separate from
+    /// the general job limit.
+    pub max_gpu_jobs: usize,
+    /// Threads each ffmpeg encode may use (`-threads`); None lets ffmpeg
+    /// decide. With N parallel CPU jobs, cores/N avoids oversubscription.
+    pub ffmpeg_threads_per_job: Option<u32>,
+    /// How many DeleteObjects batches `delete_r2_prefix` sends at once.
+    pub delete_concurrency: usize,
+    /// Remove a job's conversion output when it is cancelled or fails.
+    pub cleanup_hls_temp_files: bool,
+    /// Unix permission bits (e.g. 0o644) applied to generated playlists and
+    /// segments after conversion, for setups where another local service
+    /// (nginx, a media scanner) reads the output directory. None keeps the
+    /// process umask; ignored on non-Unix platforms.
+    pub output_file_mode: Option<u32>,
+    /// Part size in bytes for multipart uploads.
+    pub upload_part_size: u64,
+    /// When false, uploads skip objects that already exist with matching
+    /// size/checksum instead of re-uploading them.
+    pub overwrite_existing: bool,
+    /// How a job resolves its destination when the key template (or a
+    /// manifest prefix) points at a package that already exists.
+    pub collision_strategy: CollisionStrategy,
+    /// Pause the upload phase while the active network connection reports
+    /// itself as metered (mobile hotspots, capped plans). Conversions keep
+    /// running; on platforms without metered detection the manual
+    /// `set_uploads_paused` toggle is the fallback.
+    pub pause_on_metered: bool,
+    /// Ordered encoder preference; conversion tries each until one
+    /// initializes (e.g. ["h264_nvenc", "h264_qsv", "libx264"]).
+    pub encoder_fallback_chain: Vec<String>,
+    /// Remux instead of transcode when the source already matches a target
+    /// rendition: an H.264/HEVC source at original resolution is segmented
+    /// with `-c copy` (and AAC audio copied) rather than re-encoded.
+    pub fast_remux_if_compatible: bool,
+    /// Regenerate timestamps for sources with non-monotonic DTS/PTS, which
+    /// otherwise produce glitchy HLS.
+    pub fix_timestamps: TimestampFix,
+    /// Make encodes bit-reproducible: single-threaded encoding plus
+    /// ffmpeg's bitexact flags, so the same input yields identical output
+    /// bytes. Costs most of the machine's cores — expect encodes several
+    /// times slower — and requires software encoders.
+    pub deterministic: bool,
+    /// Per-rendition encoder overrides keyed by rendition name (e.g.
+    /// hardware for the heavy original, libx264 for 480p to spare GPU
+    /// sessions). Renditions not listed use the fallback-chain selection.
+    pub rendition_encoders: HashMap<String, String>,
+    /// Decode on the GPU too when encoding with a hardware encoder, which
+    /// speeds up high-bitrate sources considerably.
+    pub hwaccel_decode: bool,
+    /// Which GPU hardware encodes run on, by detection index, for multi-GPU
+    /// machines (nvenc `-gpu N`, or the matching VAAPI render node). None
+    /// leaves the choice to the driver. Validated against the detected GPU
+    /// count when a conversion starts.
+    pub gpu_device_index: Option<u32>,
+    /// Downmix surround audio to stereo (`-ac 2`). Only applied when the
+    /// source actually has more than two channels.
+    pub downmix_to_stereo: bool,
+    /// Also produce an audio-only rendition (low-bitrate AAC split from the
+    /// main audio track) and list it in the master playlist, so adaptive
+    /// players can keep playing over very poor connections.
+    pub audio_only_rendition: bool,
+    /// Whether variants carry their own audio or share one audio rendition.
+    pub audio_strategy: AudioStrategy,
+    /// Also produce an I-frame-only playlist (`#EXT-X-I-FRAMES-ONLY`) and
+    /// reference it from the master with `#EXT-X-I-FRAME-STREAM-INF`, for
+    /// players that support trick play / fast scrubbing.
+    pub generate_iframe_playlist: bool,
+    /// Honor rotation metadata (phone videos): the rotation is baked into
+    /// the re-encoded pixels via ffmpeg's autorotate and the ladder uses
+    /// the displayed dimensions. Off passes `-noautorotate` and treats the
+    /// source as stored. TS segments can't carry the rotation tag, so
+    /// rotated sources are re-encoded even when fast remux is on.
+    pub respect_rotation: bool,
+    /// Keep a progressive-download MP4 copy of the source next to the HLS
+    /// output (`original.mp4`), remuxed rather than re-encoded, for
+    /// deployments that also serve the file directly.
+    pub keep_original_mp4: bool,
+    /// Write the kept MP4 with `-movflags +faststart` so the moov atom sits
+    /// at the front and playback can start before the download finishes.
+    pub faststart_original: bool,
+    /// Strip container metadata and chapters (`-map_metadata -1`,
+    /// `-map_chapters -1`) from output: encoder tags, comments and embedded
+    /// cover art leak info and bloat segments. Off by default so chapters
+    /// survive unless explicitly unwanted. The master playlist's audio
+    /// language tags are unaffected — they come from probing the source.
+    pub strip_metadata: bool,
+    /// How uploaded object keys are laid out, using `{slug}`, `{year}`,
+    /// `{rendition}` and `{segment}` placeholders (e.g. by-year buckets:
+    /// `movies/{year}/{slug}/{rendition}/{segment}`). Must contain `{slug}`
+    /// and `{segment}` so keys can't collide. The default matches the
+    /// layout the site has always served from.
+    pub object_key_template: String,
+    /// Enables HLS AES-128 encryption: URL template for the `#EXT-X-KEY`
+    /// URI, with `{slug}` replaced by the movie id (e.g.
+    /// `https://keys.cinemafred.com/{slug}`). A fresh key is generated per
+    /// movie and written next to the output for out-of-band delivery to the
+    /// key server — it is never uploaded with the segments. None disables
+    /// encryption.
+    pub key_server_url_template: Option<String>,
+    /// Origins the web player loads HLS from; used to validate bucket CORS.
+    pub cors_origins: Vec<String>,
+    /// Cache-Control max-age (seconds) for immutable segments (.ts/.m4s/.mp4).
+    pub segment_cache_max_age: u64,
+    /// Cache-Control max-age (seconds) for manifests (.m3u8/.mpd), which may
+    /// be replaced when a movie is re-encoded.
+    pub playlist_cache_max_age: u64,
+    /// Additional buckets every upload is mirrored to after the primary
+    /// write succeeds.
+    pub mirror_targets: Vec<S3Target>,
+    /// Upload bandwidth cap in Mbps when no schedule window applies.
+    /// None means unlimited.
+    pub max_upload_mbps: Option<f64>,
+    /// Time-of-day bandwidth windows (e.g. throttle during the workday,
+    /// full speed at night). The first matching window wins.
+    pub bandwidth_schedule: Vec<BandwidthWindow>,
+    /// Cloudflare zone the site is served through, for cache purges after
+    /// re-uploads. None disables `purge_cdn_cache`.
+    pub cloudflare_zone_id: Option<String>,
+    /// API token with cache-purge permission for that zone. Stored in the
+    /// settings file alongside the zone id.
+    pub cloudflare_api_token: Option<String>,
+    /// Public base URL the bucket is served from (the CDN domain), used by
+    /// `warm_cdn` to build object URLs from a package prefix. None means
+    /// warming needs explicit URLs.
+    pub cdn_base_url: Option<String>,
+    /// JSON version manifest `check_for_updates` polls. None disables the
+    /// check entirely.
+    pub update_manifest_url: Option<String>,
+    /// Rates `estimate_costs` projects from.
+    pub cost_rates: CostRates,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            r2_account_id: String::new(),
+            r2_access_key_id: String::new(),
+            r2_secret_access_key: String::new(),
+            secret_backend: SecretBackend::default(),
+            r2_bucket: "cinemafred".into(),
+            output_dir: std::env::temp_dir().join("cinemafred-uploader"),
+            segment_duration: 6,
+            hls_version: 3,
+            independent_segments: false,
+            hls_segment_type: HlsSegmentType::default(),
+            hls_single_file: false,
+            hls_playlist_type: HlsPlaylistType::default(),
+            conversion_timeout_secs: None,
+            max_concurrent_jobs: 2,
+            max_gpu_jobs: 2,
+            ffmpeg_threads_per_job: None,
+            delete_concurrency: 4,
+            cleanup_hls_temp_files: true,
+            output_file_mode: None,
+            upload_part_size: 64 * 1024 * 1024,
+            overwrite_existing: false,
+            collision_strategy: CollisionStrategy::Fail,
+            pause_on_metered: false,
+            encoder_fallback_chain: vec!["libx264".into()],
+            fast_remux_if_compatible: true,
+            fix_timestamps: TimestampFix::default(),
+            deterministic: false,
+            rendition_encoders: HashMap::new(),
+            hwaccel_decode: false,
+            gpu_device_index: None,
+            downmix_to_stereo: false,
+            audio_only_rendition: false,
+            audio_strategy: AudioStrategy::default(),
+            generate_iframe_playlist: false,
+            respect_rotation: true,
+            keep_original_mp4: false,
+            faststart_original: true,
+            strip_metadata: false,
+            object_key_template: "hls/{slug}/{rendition}/{segment}".into(),
+            key_server_url_template: None,
+            cors_origins: vec!["https://cinemafred.com".into()],
+            segment_cache_max_age: 365 * 24 * 60 * 60,
+            playlist_cache_max_age: 60,
+            mirror_targets: Vec::new(),
+            max_upload_mbps: None,
+            bandwidth_schedule: Vec::new(),
+            cloudflare_zone_id: None,
+            cloudflare_api_token: None,
+            cdn_base_url: None,
+            update_manifest_url: None,
+            cost_rates: CostRates::default(),
+        }
+    }
+}
+
+/// In-memory handle to the settings file, managed as tauri state.
+pub struct SettingsStore(Mutex<Settings>);
+
+fn keychain_entry() -> Option<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT).ok()
+}
+
+impl SettingsStore {
+    pub fn load_or_default() -> Self {
+        let mut settings: Settings = Self::path()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        // The store only holds a backend marker; pull the actual secret back
+        // out of the keychain transparently.
+        if settings.secret_backend == SecretBackend::Keychain {
+            match keychain_entry().and_then(|e| e.get_password().ok()) {
+                Some(secret) => settings.r2_secret_access_key = secret,
+                None => settings.secret_backend = SecretBackend::Store,
+            }
+        }
+        Self(Mutex::new(settings))
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("cinemafred-uploader").join("settings.json"))
+    }
+
+    pub fn get(&self) -> Settings {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, mut settings: Settings) -> Result<()> {
+        let path = Self::path()
+            .ok_or_else(|| AppError::Settings("no config directory available".into()))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Prefer the OS keychain for the secret; the JSON on disk then only
+        // carries the backend marker. Fall back to keeping it in the store
+        // when no keychain is available.
+        let mut persisted = settings.clone();
+        if !settings.r2_secret_access_key.is_empty() {
+            let stored_in_keychain = keychain_entry()
+                .map(|e| e.set_password(&settings.r2_secret_access_key).is_ok())
+                .unwrap_or(false);
+            if stored_in_keychain {
+                settings.secret_backend = SecretBackend::Keychain;
+                persisted = settings.clone();
+                persisted.r2_secret_access_key = String::new();
+            } else {
+                settings.secret_backend = SecretBackend::Store;
+                persisted = settings.clone();
+            }
+        }
+
+        fs::write(&path, serde_json::to_string_pretty(&persisted).unwrap())?;
+        *self.0.lock().unwrap() = settings;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn get_settings(store: State<'_, SettingsStore>) -> Settings {
+    store.get()
+}
+
+#[tauri::command]
+pub async fn update_settings(
+    app: tauri::AppHandle,
+    store: State<'_, SettingsStore>,
+    settings: Settings,
+) -> Result<()> {
+    use tauri::Emitter;
+
+    if settings.segment_duration == 0 {
+        return Err(AppError::Settings("segment_duration must be at least 1".into()));
+    }
+    if settings.conversion_timeout_secs == Some(0) {
+        return Err(AppError::Settings(
+            "conversion_timeout_secs must be at least 1 when set".into(),
+        ));
+    }
+    if settings.max_concurrent_jobs == 0 {
+        return Err(AppError::Settings("max_concurrent_jobs must be at least 1".into()));
+    }
+    if settings.delete_concurrency == 0 {
+        return Err(AppError::Settings("delete_concurrency must be at least 1".into()));
+    }
+    if let Some(threads) = settings.ffmpeg_threads_per_job {
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get() as u32)
+            .unwrap_or(1);
+        if threads == 0 || threads > cores {
+            return Err(AppError::Settings(format!(
+                "ffmpeg_threads_per_job must be between 1 and {cores} on this machine"
+            )));
+        }
+    }
+    if !(3..=7).contains(&settings.hls_version) {
+        return Err(AppError::Settings("hls_version must be between 3 and 7".into()));
+    }
+    if settings.hls_segment_type == HlsSegmentType::Fmp4 && settings.hls_version < 6 {
+        return Err(AppError::Settings(
+            "fMP4 segments require hls_version 6 or later".into(),
+        ));
+    }
+    if settings.hls_single_file && settings.hls_segment_type == HlsSegmentType::Fmp4 {
+        return Err(AppError::Settings(
+            "hls_single_file writes byte-range TS output and is incompatible with fMP4 segments"
+                .into(),
+        ));
+    }
+    if settings.hls_playlist_type == HlsPlaylistType::Event && settings.keep_original_mp4 {
+        return Err(AppError::Settings(
+            "keep_original_mp4 applies to finished VOD output; disable it for event playlists"
+                .into(),
+        ));
+    }
+    if settings.deterministic {
+        let hardware = settings
+            .encoder_fallback_chain
+            .iter()
+            .chain(settings.rendition_encoders.values())
+            .find(|e| crate::ffmpeg::is_hardware_encoder(e));
+        if let Some(encoder) = hardware {
+            return Err(AppError::Settings(format!(
+                "deterministic encodes require software encoders; {encoder} is hardware-accelerated"
+            )));
+        }
+    }
+    for rate in [
+        settings.cost_rates.storage_per_gb_month,
+        settings.cost_rates.egress_per_gb,
+    ] {
+        if !rate.is_finite() || rate < 0.0 {
+            return Err(AppError::Settings(
+                "cost_rates must be finite and non-negative".into(),
+            ));
+        }
+    }
+    for window in &settings.bandwidth_schedule {
+        if window.start_hour > 23 || window.end_hour > 23 {
+            return Err(AppError::Settings(
+                "bandwidth window hours must be between 0 and 23".into(),
+            ));
+        }
+        if window.max_mbps <= 0.0 {
+            return Err(AppError::Settings(
+                "bandwidth window max_mbps must be positive".into(),
+            ));
+        }
+    }
+    if let Some(mode) = settings.output_file_mode {
+        // Permission bits only; rwx for owner is the floor, or the app
+        // itself can no longer read what it just wrote.
+        if mode & !0o777 != 0 || mode & 0o600 != 0o600 {
+            return Err(AppError::Settings(format!(
+                "output_file_mode {mode:#o} must be permission bits only and keep owner read/write"
+            )));
+        }
+    }
+    crate::r2::validate_key_template(&settings.object_key_template)?;
+    if let Some(base) = &settings.cdn_base_url {
+        if !base.is_empty() && !base.starts_with("http") {
+            return Err(AppError::Settings(
+                "cdn_base_url must be an http(s) URL".into(),
+            ));
+        }
+    }
+    if let Some(template) = &settings.key_server_url_template {
+        if !template.starts_with("http") || !template.contains("{slug}") {
+            return Err(AppError::Settings(
+                "key_server_url_template must be an http(s) URL containing {slug}".into(),
+            ));
+        }
+    }
+    if settings.encoder_fallback_chain.is_empty() {
+        return Err(AppError::Settings(
+            "encoder_fallback_chain must contain at least one encoder".into(),
+        ));
+    }
+    // Warn about chain entries this ffmpeg build doesn't ship, but keep
+    // them: the same settings file may be shared with a machine that does.
+    if let Ok(available) = crate::ffmpeg::available_encoders().await {
+        for encoder in &settings.encoder_fallback_chain {
+            if !available.contains(encoder) {
+                let _ = app.emit(
+                    "settings-warning",
+                    format!("encoder {encoder} is not available in this ffmpeg build"),
+                );
+            }
+        }
+    }
+    store.set(settings)
+}
+
+/// What `clear_credentials` actually removed, for UI confirmation.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClearedCredentials {
+    pub store_cleared: bool,
+    pub keychain_cleared: bool,
+}
+
+/// Wipe the R2 credentials from both the JSON store and the OS keychain,
+/// leaving every non-sensitive setting intact. Idempotent: clearing when
+/// nothing is stored succeeds and reports nothing cleared.
+#[tauri::command]
+pub async fn clear_credentials(store: State<'_, SettingsStore>) -> Result<ClearedCredentials> {
+    let mut settings = store.get();
+    let store_cleared =
+        !settings.r2_access_key_id.is_empty() || !settings.r2_secret_access_key.is_empty();
+    settings.r2_access_key_id.clear();
+    settings.r2_secret_access_key.clear();
+    settings.secret_backend = SecretBackend::Store;
+
+    let keychain_cleared = keychain_entry()
+        .map(|e| e.delete_credential().is_ok())
+        .unwrap_or(false);
+
+    store.set(settings)?;
+    Ok(ClearedCredentials {
+        store_cleared,
+        keychain_cleared,
+    })
+}
+
+/// Merge a partial JSON object over the current settings, so the frontend
+/// can change one field without round-tripping (and possibly clobbering) the
+/// whole struct. Unknown keys are rejected to catch typos. Returns the
+/// merged, saved settings.
+#[tauri::command]
+pub async fn patch_settings(
+    app: tauri::AppHandle,
+    store: State<'_, SettingsStore>,
+    patch: serde_json::Value,
+) -> Result<Settings> {
+    let patch_map = patch
+        .as_object()
+        .ok_or_else(|| AppError::Settings("patch must be a JSON object".into()))?;
+
+    let mut current = serde_json::to_value(store.get()).expect("settings always serialize");
+    let current_map = current.as_object_mut().expect("settings are a JSON object");
+    for (key, value) in patch_map {
+        if !current_map.contains_key(key) {
+            return Err(AppError::Settings(format!("unknown settings key {key:?}")));
+        }
+        current_map.insert(key.clone(), value.clone());
+    }
+
+    let merged: Settings = serde_json::from_value(current)
+        .map_err(|e| AppError::Settings(format!("invalid patch: {e}")))?;
+    update_settings(app, store, merged.clone()).await?;
+    Ok(merged)
+}
@@ -0,0 +1,221 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tokio::process::Command;
+
+use crate::error::{AppError, Result};
+use crate::settings::SettingsStore;
+use crate::{ffmpeg, r2};
+
+/// Subtitle codecs ffmpeg can convert to WebVTT as text. Also the set the
+/// `subtitles` filter can burn in (image formats like PGS would need an
+/// overlay instead).
+pub(crate) const TEXT_SUBTITLE_CODECS: &[&str] = &["subrip", "srt", "ass", "ssa", "mov_text", "webvtt"];
+
+/// One subtitle track pulled out of the source container.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedSubtitle {
+    pub path: PathBuf,
+    pub language: String,
+    pub title: Option<String>,
+}
+
+/// Extract every text subtitle stream from `input_path` as WebVTT files in
+/// `output_dir`. Image-based tracks (PGS/VobSub) are skipped — they can't be
+/// converted to text without OCR.
+#[tauri::command]
+pub async fn extract_subtitles(
+    input_path: PathBuf,
+    output_dir: PathBuf,
+) -> Result<Vec<ExtractedSubtitle>> {
+    let streams = ffmpeg::probe_subtitle_streams(&input_path).await?;
+    tokio::fs::create_dir_all(&output_dir).await?;
+
+    let mut extracted = Vec::new();
+    for stream in streams {
+        if !TEXT_SUBTITLE_CODECS.contains(&stream.codec.as_str()) {
+            continue;
+        }
+        let out_path = output_dir.join(format!("{}_{}.vtt", stream.language, stream.index));
+        let output = Command::new("ffmpeg")
+            .arg("-y")
+            .arg("-i")
+            .arg(&input_path)
+            .args(["-map", &format!("0:{}", stream.index), "-c:s", "webvtt"])
+            .arg(&out_path)
+            .output()
+            .await
+            .map_err(crate::ffmpeg::spawn_error)?;
+        if !output.status.success() {
+            return Err(AppError::Ffmpeg(format!(
+                "subtitle extraction of stream {} exited with {}",
+                stream.index, output.status
+            )));
+        }
+        extracted.push(ExtractedSubtitle {
+            path: out_path,
+            language: stream.language,
+            title: stream.title,
+        });
+    }
+    Ok(extracted)
+}
+
+/// Convert a sidecar .srt/.ass/.ssa file to WebVTT, preserving timing and
+/// whatever styling survives the format change. Returns the output path.
+#[tauri::command]
+pub async fn convert_subtitle_to_vtt(input_path: PathBuf, output_path: PathBuf) -> Result<PathBuf> {
+    match input_path.extension().and_then(|e| e.to_str()) {
+        Some("srt") | Some("ass") | Some("ssa") => {}
+        other => {
+            return Err(AppError::InvalidInput(format!(
+                "{:?} is not a recognized text subtitle format (expected .srt/.ass/.ssa)",
+                other.unwrap_or("<none>")
+            )));
+        }
+    }
+    if let Some(parent) = output_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let output = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-i")
+        .arg(&input_path)
+        .args(["-c:s", "webvtt"])
+        .arg(&output_path)
+        .output()
+        .await
+        .map_err(crate::ffmpeg::spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "subtitle conversion of {} exited with {}",
+            input_path.display(),
+            output.status
+        )));
+    }
+    Ok(output_path)
+}
+
+/// Subtitle keys and the rewritten master playlist key.
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadedSubtitles {
+    pub subtitle_keys: Vec<String>,
+    pub master_playlist_key: String,
+}
+
+/// Insert `#EXT-X-MEDIA:TYPE=SUBTITLES` entries into a master playlist and
+/// tag every variant stream with the subtitle group.
+fn add_subtitles_to_master(master: &str, subtitles: &[ExtractedSubtitle]) -> String {
+    let mut out = String::with_capacity(master.len() + subtitles.len() * 96);
+    let mut media_inserted = false;
+    for line in master.lines() {
+        if line.starts_with("#EXT-X-STREAM-INF:") {
+            if !media_inserted {
+                for (i, subtitle) in subtitles.iter().enumerate() {
+                    let name = subtitle
+                        .title
+                        .clone()
+                        .unwrap_or_else(|| format!("Subtitles {}", i + 1));
+                    out.push_str(&format!(
+                        "#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME=\"{name}\",LANGUAGE=\"{}\",URI=\"subs/{}.m3u8\"\n",
+                        subtitle.language, subtitle.language
+                    ));
+                }
+                media_inserted = true;
+            }
+            if line.contains("SUBTITLES=") {
+                out.push_str(line);
+            } else {
+                out.push_str(line);
+                out.push_str(",SUBTITLES=\"subs\"");
+            }
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The tiny media playlist that wraps one VTT file, as HLS requires.
+fn subtitle_media_playlist(vtt_name: &str, duration_seconds: f64) -> String {
+    let duration = duration_seconds.ceil() as u64;
+    format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{duration}\n#EXT-X-MEDIA-SEQUENCE:0\n#EXT-X-PLAYLIST-TYPE:VOD\n#EXTINF:{duration_seconds},\n{vtt_name}\n#EXT-X-ENDLIST\n"
+    )
+}
+
+/// Upload extracted `.vtt` files under `hls/{movie_id}/subs/` and rewrite the
+/// already-uploaded master playlist to declare them as a subtitle group that
+/// every variant references. Returns the subtitle object keys.
+#[tauri::command]
+pub async fn upload_subtitles(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    movie_id: String,
+    subtitles: Vec<ExtractedSubtitle>,
+    duration_seconds: f64,
+) -> Result<UploadedSubtitles> {
+    let mut settings = store.get();
+    // Subtitle passes re-write playlists in place by design.
+    settings.overwrite_existing = true;
+    let client = r2::client(&settings)?;
+
+    let mut subtitle_keys = Vec::new();
+    for subtitle in &subtitles {
+        let vtt_key = format!("hls/{movie_id}/subs/{}.vtt", subtitle.language);
+        r2::upload_file(
+            &app,
+            &client,
+            &settings,
+            &subtitle.path,
+            &vtt_key,
+            &r2::UploadOptions {
+                content_type: Some("text/vtt".into()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let playlist_key = format!("hls/{movie_id}/subs/{}.m3u8", subtitle.language);
+        let playlist = subtitle_media_playlist(
+            &format!("{}.vtt", subtitle.language),
+            duration_seconds,
+        );
+        r2::put_string(&client, &settings, &playlist_key, &playlist, "application/vnd.apple.mpegurl")
+            .await?;
+        subtitle_keys.push(vtt_key);
+    }
+
+    let master_key = format!("hls/{movie_id}/playlist.m3u8");
+    let master = r2::get_string(&client, &settings, &master_key).await?;
+    let rewritten = add_subtitles_to_master(&master, &subtitles);
+    r2::put_string(&client, &settings, &master_key, &rewritten, "application/vnd.apple.mpegurl")
+        .await?;
+
+    Ok(UploadedSubtitles {
+        subtitle_keys,
+        master_playlist_key: master_key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_subtitle_group_to_master_playlist() {
+        let master = "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-STREAM-INF:BANDWIDTH=1500000,RESOLUTION=854x480\n480p/playlist.m3u8\n";
+        let subtitles = vec![ExtractedSubtitle {
+            path: PathBuf::from("/tmp/en_2.vtt"),
+            language: "en".into(),
+            title: None,
+        }];
+        let rewritten = add_subtitles_to_master(master, &subtitles);
+        assert!(rewritten.contains("TYPE=SUBTITLES"));
+        assert!(rewritten.contains("LANGUAGE=\"en\""));
+        assert!(rewritten.contains("URI=\"subs/en.m3u8\""));
+        assert!(rewritten.contains("RESOLUTION=854x480,SUBTITLES=\"subs\""));
+    }
+}
@@ -0,0 +1,312 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+use crate::error::{AppError, Result};
+use crate::ffmpeg;
+
+/// Thumbnail width in pixels; height follows the source aspect ratio.
+const THUMB_WIDTH: u32 = 160;
+/// Thumbnails per sprite row/column; one sheet holds GRID × GRID frames.
+const GRID: u32 = 10;
+
+/// Output of a sprite generation run: one or more sheets plus the WebVTT
+/// index the player uses to map seek-bar positions to sprite regions.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrubbingThumbnails {
+    pub sprites: Vec<PathBuf>,
+    pub vtt: PathBuf,
+    pub thumb_width: u32,
+    pub thumb_height: u32,
+    pub interval_seconds: f64,
+}
+
+/// "HH:MM:SS.mmm" as WebVTT wants it.
+fn format_timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Sprite sheet index and pixel region for the `i`-th thumbnail.
+fn sprite_region(index: usize, thumb_width: u32, thumb_height: u32) -> (usize, u32, u32) {
+    let per_sheet = (GRID * GRID) as usize;
+    let sheet = index / per_sheet;
+    let cell = (index % per_sheet) as u32;
+    let x = (cell % GRID) * thumb_width;
+    let y = (cell / GRID) * thumb_height;
+    (sheet, x, y)
+}
+
+/// Extract frames every `interval_seconds`, tile them into sprite sheets and
+/// write a WebVTT file mapping each interval to its sprite region. Returns
+/// the sprite and VTT paths, ready to upload next to the HLS assets.
+#[tauri::command]
+pub async fn generate_scrubbing_thumbnails(
+    input_path: PathBuf,
+    interval_seconds: f64,
+    output_dir: PathBuf,
+) -> Result<ScrubbingThumbnails> {
+    if interval_seconds <= 0.0 {
+        return Err(AppError::InvalidInput(
+            "interval_seconds must be positive".into(),
+        ));
+    }
+    let metadata = ffmpeg::probe(&input_path).await?;
+    // Keep the height even so encoders downstream don't complain.
+    let thumb_height = if metadata.width > 0 {
+        ((THUMB_WIDTH * metadata.height / metadata.width) + 1) & !1
+    } else {
+        90
+    };
+
+    tokio::fs::create_dir_all(&output_dir).await?;
+    let frames_dir = output_dir.join("frames");
+    tokio::fs::create_dir_all(&frames_dir).await?;
+
+    // Pass 1: one scaled frame per interval.
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-i")
+        .arg(&input_path)
+        .args([
+            "-vf",
+            &format!("fps=1/{interval_seconds},scale={THUMB_WIDTH}:{thumb_height}"),
+        ])
+        .arg(frames_dir.join("thumb_%05d.jpg"))
+        .output()
+        .await
+        .map_err(crate::ffmpeg::spawn_error)?;
+    if !status.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "thumbnail extraction exited with {}",
+            status.status
+        )));
+    }
+
+    let mut frame_count: usize = 0;
+    for entry in std::fs::read_dir(&frames_dir)? {
+        if entry?.path().extension().and_then(|e| e.to_str()) == Some("jpg") {
+            frame_count += 1;
+        }
+    }
+    if frame_count == 0 {
+        return Err(AppError::Ffmpeg("no thumbnails were extracted".into()));
+    }
+
+    // Pass 2: tile the frames into GRID×GRID sheets; ffmpeg emits one output
+    // image per full (or final partial) tile.
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-i")
+        .arg(frames_dir.join("thumb_%05d.jpg"))
+        .args(["-vf", &format!("tile={GRID}x{GRID}"), "-vsync", "0"])
+        .arg(output_dir.join("sprite_%d.jpg"))
+        .output()
+        .await
+        .map_err(crate::ffmpeg::spawn_error)?;
+    if !status.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "sprite tiling exited with {}",
+            status.status
+        )));
+    }
+
+    let sheet_count = frame_count.div_ceil((GRID * GRID) as usize);
+    let sprites: Vec<PathBuf> = (1..=sheet_count)
+        .map(|i| output_dir.join(format!("sprite_{i}.jpg")))
+        .collect();
+
+    let mut vtt = String::from("WEBVTT\n\n");
+    for i in 0..frame_count {
+        let start = i as f64 * interval_seconds;
+        let end = ((i + 1) as f64 * interval_seconds).min(metadata.duration_seconds.max(start));
+        let (sheet, x, y) = sprite_region(i, THUMB_WIDTH, thumb_height);
+        vtt.push_str(&format!(
+            "{} --> {}\nsprite_{}.jpg#xywh={},{},{},{}\n\n",
+            format_timestamp(start),
+            format_timestamp(end),
+            sheet + 1,
+            x,
+            y,
+            THUMB_WIDTH,
+            thumb_height
+        ));
+    }
+    let vtt_path = output_dir.join("thumbnails.vtt");
+    tokio::fs::write(&vtt_path, vtt).await?;
+
+    // The intermediate frames aren't needed once the sheets exist.
+    let _ = tokio::fs::remove_dir_all(&frames_dir).await;
+
+    Ok(ScrubbingThumbnails {
+        sprites,
+        vtt: vtt_path,
+        thumb_width: THUMB_WIDTH,
+        thumb_height,
+        interval_seconds,
+    })
+}
+
+/// Frame rate for animated hover previews; full rate would balloon the
+/// file for no visible gain at thumbnail size.
+const PREVIEW_FPS: u32 = 12;
+/// Width of animated previews; height follows the aspect ratio.
+const PREVIEW_WIDTH: u32 = 320;
+
+/// A generated hover preview and its size on disk (the UI surfaces the
+/// size so oversized previews stand out before upload).
+#[derive(Debug, Clone, Serialize)]
+pub struct AnimatedPreview {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Encode a short animated WebP or GIF from a window of the source, for
+/// library hover previews. The format follows the output extension; GIF
+/// goes through palette generation to avoid banding. A window reaching
+/// past the end of the source is clamped to what's actually there.
+#[tauri::command]
+pub async fn generate_animated_preview(
+    input_path: PathBuf,
+    start_seconds: f64,
+    duration_seconds: f64,
+    output_path: PathBuf,
+) -> Result<AnimatedPreview> {
+    if duration_seconds <= 0.0 {
+        return Err(AppError::InvalidInput(
+            "duration_seconds must be positive".into(),
+        ));
+    }
+    let extension = output_path.extension().and_then(|e| e.to_str());
+    if !matches!(extension, Some("webp") | Some("gif")) {
+        return Err(AppError::InvalidInput(format!(
+            "unsupported preview extension {extension:?}; use .webp or .gif"
+        )));
+    }
+
+    let metadata = ffmpeg::probe(&input_path).await?;
+    let start = start_seconds.clamp(0.0, metadata.duration_seconds);
+    let duration = duration_seconds.min(metadata.duration_seconds - start);
+    if duration <= 0.0 {
+        return Err(AppError::InvalidInput(format!(
+            "start_seconds {start_seconds} is at or past the end of the source"
+        )));
+    }
+
+    if let Some(parent) = output_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let scale = format!("fps={PREVIEW_FPS},scale={PREVIEW_WIDTH}:-2");
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-y")
+        .args(["-ss", &format!("{start:.3}")])
+        .args(["-t", &format!("{duration:.3}")])
+        .arg("-i")
+        .arg(&input_path);
+    if extension == Some("gif") {
+        // GIF's 256-color limit needs a dedicated palette pass or the
+        // output bands badly; do both passes in one filter graph.
+        cmd.args([
+            "-filter_complex",
+            &format!("{scale},split[a][b];[a]palettegen[p];[b][p]paletteuse"),
+        ]);
+    } else {
+        cmd.args(["-vf", &scale, "-vcodec", "libwebp", "-loop", "0"]);
+    }
+    let output = cmd
+        .arg("-an")
+        .arg(&output_path)
+        .output()
+        .await
+        .map_err(crate::ffmpeg::spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "preview generation exited with {}",
+            output.status
+        )));
+    }
+
+    let bytes = tokio::fs::metadata(&output_path).await?.len();
+    Ok(AnimatedPreview {
+        path: output_path,
+        bytes,
+    })
+}
+
+/// Grab a single frame at `timestamp_seconds` as a still image, for
+/// marketing shots and the like — distinct from the scrubbing sprites
+/// above, which are batch-extracted and tiled. The format follows the
+/// output extension (png or jpg); `width` scales the frame preserving
+/// aspect. Timestamps past the end are clamped to the duration.
+#[tauri::command]
+pub async fn extract_frame(
+    input_path: PathBuf,
+    timestamp_seconds: f64,
+    output_path: PathBuf,
+    width: Option<u32>,
+) -> Result<PathBuf> {
+    match output_path.extension().and_then(|e| e.to_str()) {
+        Some("png") | Some("jpg") | Some("jpeg") => {}
+        other => {
+            return Err(AppError::InvalidInput(format!(
+                "unsupported still image extension {other:?}; use .png or .jpg"
+            )));
+        }
+    }
+    let metadata = ffmpeg::probe(&input_path).await?;
+    let timestamp = timestamp_seconds.clamp(0.0, metadata.duration_seconds);
+
+    if let Some(parent) = output_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-y")
+        // -ss before -i seeks fast and, since we decode, lands on the exact
+        // frame rather than the nearest keyframe.
+        .args(["-ss", &format!("{timestamp:.3}")])
+        .arg("-i")
+        .arg(&input_path)
+        .args(["-frames:v", "1"]);
+    if let Some(width) = width {
+        cmd.args(["-vf", &format!("scale={width}:-2")]);
+    }
+    let output = cmd
+        .arg(&output_path)
+        .output()
+        .await
+        .map_err(crate::ffmpeg::spawn_error)?;
+    if !output.status.success() {
+        return Err(AppError::Ffmpeg(format!(
+            "frame extraction exited with {}",
+            output.status
+        )));
+    }
+    Ok(output_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_webvtt_timestamps() {
+        assert_eq!(format_timestamp(0.0), "00:00:00.000");
+        assert_eq!(format_timestamp(61.5), "00:01:01.500");
+        assert_eq!(format_timestamp(3661.25), "01:01:01.250");
+    }
+
+    #[test]
+    fn maps_indices_to_sprite_regions() {
+        assert_eq!(sprite_region(0, 160, 90), (0, 0, 0));
+        assert_eq!(sprite_region(9, 160, 90), (0, 9 * 160, 0));
+        assert_eq!(sprite_region(10, 160, 90), (0, 0, 90));
+        assert_eq!(sprite_region(100, 160, 90), (1, 0, 0));
+    }
+}